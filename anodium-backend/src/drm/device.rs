@@ -15,7 +15,7 @@ use smithay::{
         },
     },
     reexports::{
-        calloop::{Dispatcher, LoopHandle},
+        calloop::{Dispatcher, LoopHandle, RegistrationToken},
         drm::control::{connector, crtc, Device as _},
         nix::{fcntl::OFlag, unistd},
     },
@@ -75,6 +75,7 @@ impl<D> AsDrm for Dispatcher<'_, drm::DrmDevice<Device>, D> {
 pub struct DrmDevice {
     drm: Box<dyn AsDrm>,
     connectors: IndexMap<connector::Handle, connector::Info>,
+    registration_token: RegistrationToken,
 }
 
 impl DrmDevice {
@@ -90,14 +91,20 @@ impl DrmDevice {
         let drm = drm::DrmDevice::new(device, true, None)?;
 
         let drm = Dispatcher::new(drm, move |event, meta, data: &mut D| cb(event, meta, data));
-        let _registration_token = event_loop.register_dispatcher(drm.clone()).unwrap();
+        let registration_token = event_loop.register_dispatcher(drm.clone()).unwrap();
 
         Ok(Self {
             drm: Box::new(drm),
             connectors: Default::default(),
+            registration_token,
         })
     }
 
+    /// Token of the event source, removing it from the event loop stops drm events
+    pub fn registration_token(&self) -> RegistrationToken {
+        self.registration_token
+    }
+
     pub fn inner(&self) -> Ref<drm::DrmDevice<Device>> {
         self.drm.as_drm()
     }
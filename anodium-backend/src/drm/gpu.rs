@@ -1,9 +1,10 @@
-use std::{cell::RefCell, path::Path, rc::Rc};
+use std::{cell::RefCell, collections::HashSet, path::Path, rc::Rc};
 
 use anyhow::Result;
 use indexmap::IndexMap;
 use smithay::{
     backend::{
+        allocator::Format,
         drm::{DrmEvent, DrmNode, GbmBufferedSurface},
         egl::{EGLContext, EGLDevice, EGLDisplay},
         renderer::{
@@ -13,24 +14,30 @@ use smithay::{
         },
         session::{auto::AutoSession, Signal as SessionSignal},
     },
-    output::Mode as WlMode,
+    output::{Mode as WlMode, PhysicalProperties, Subpixel},
     reexports::{
         calloop::LoopHandle,
         drm::control::{connector, crtc, Device as _, ModeTypeFlags},
         gbm::Device as GbmDevice,
+        nix::sys::stat::dev_t,
     },
     utils::{
         signaling::{Linkable, Signaler},
-        Rectangle,
+        Rectangle, Transform,
     },
 };
 
-use super::{utils, Device, DrmDevice, DrmOutputId, DrmRenderer};
-use crate::BackendHandler;
+use super::{utils, Device, DrmDevice, DrmOutputId, DrmRenderer, OUTPUT_ID_MAP};
+use crate::{BackendHandler, NewOutputDescriptor, OutputId};
 
 pub struct Gpu {
     drm: DrmDevice,
     drm_node: DrmNode,
+    /// Device the gpu got opened from, udev reports its removal with this id
+    device_id: dev_t,
+    gbm: Rc<RefCell<GbmDevice<Device>>>,
+    formats: HashSet<Format>,
+    session_signal: Signaler<SessionSignal>,
     pub outputs: IndexMap<crtc::Handle, GpuConnector>,
 }
 
@@ -46,6 +53,7 @@ impl Gpu {
         D: BackendHandler,
         D: 'static,
     {
+        let device_id = DrmNode::from_path(path)?.dev_id();
         let device = Device::open(session, path)?;
 
         let mut drm = DrmDevice::new(
@@ -70,82 +78,139 @@ impl Gpu {
         info!("connectors: {:#?}", &res);
 
         let formats = {
-            let display = unsafe { EGLDisplay::new(&*gbm.borrow(), None).unwrap() };
+            let display = unsafe { EGLDisplay::new(&*gbm.borrow(), None)? };
 
             EGLDevice::device_for_display(&display)
                 .ok()
                 .and_then(|x| x.try_get_render_node().ok());
 
-            let context = EGLContext::new(&display, None).unwrap();
+            let context = EGLContext::new(&display, None)?;
 
             context.dmabuf_render_formats().clone()
         };
 
-        let mut outputs: IndexMap<crtc::Handle, GpuConnector> = IndexMap::new();
+        let mut gpu = Gpu {
+            drm,
+            drm_node,
+            device_id,
+            gbm,
+            formats,
+            session_signal,
+            outputs: IndexMap::new(),
+        };
 
         for (connector, crtc) in res.map {
-            let drm = drm.inner();
+            gpu.setup_connector(connector, crtc)?;
+        }
 
-            let connector_info = drm.get_connector(connector, false).unwrap();
+        Ok(gpu)
+    }
 
-            let connector_name = utils::format_connector_name(
-                connector_info.interface(),
-                connector_info.interface_id(),
-            );
+    /// Create a surface for the connector, driven by the crtc
+    fn setup_connector(&mut self, connector: connector::Handle, crtc: crtc::Handle) -> Result<()> {
+        let drm = self.drm.inner();
 
-            info!(
-                "Trying to setup connector {:?}-{} with crtc {:?} ({})",
-                connector_info.interface(),
-                connector_info.interface_id(),
-                crtc,
-                connector_name,
-            );
+        let connector_info = drm.get_connector(connector, false)?;
 
-            let drm_modes = connector_info.modes();
+        let connector_name =
+            utils::format_connector_name(connector_info.interface(), connector_info.interface_id());
 
-            let wl_modes: Vec<WlMode> = drm_modes
-                .iter()
-                .map(|mode| WlMode {
-                    size: (mode.size().0 as i32, mode.size().1 as i32).into(),
-                    refresh: (mode.vrefresh() * 1000) as i32,
-                })
-                .collect();
+        info!(
+            "Trying to setup connector {:?}-{} with crtc {:?} ({})",
+            connector_info.interface(),
+            connector_info.interface_id(),
+            crtc,
+            connector_name,
+        );
 
-            let mode_id = drm_modes
-                .iter()
-                .position(|mode| mode.mode_type().contains(ModeTypeFlags::PREFERRED))
-                .unwrap_or(0);
+        let drm_modes = connector_info.modes();
+
+        let wl_modes: Vec<WlMode> = drm_modes
+            .iter()
+            .map(|mode| WlMode {
+                size: (mode.size().0 as i32, mode.size().1 as i32).into(),
+                refresh: (mode.vrefresh() * 1000) as i32,
+            })
+            .collect();
+
+        let mode_id = drm_modes
+            .iter()
+            .position(|mode| mode.mode_type().contains(ModeTypeFlags::PREFERRED))
+            .unwrap_or(0);
+
+        let drm_mode = *drm_modes
+            .get(mode_id)
+            .ok_or_else(|| anyhow::anyhow!("Connector {} has no modes", connector_name))?;
+
+        let mut drm_surface = drm.create_surface(crtc, drm_mode, &[connector])?;
+        drm_surface.link(self.session_signal.clone());
+
+        let gbm_surface =
+            GbmBufferedSurface::new(drm_surface, self.gbm.clone(), self.formats.clone(), None)?;
+
+        let (width, height) = connector_info.size().unwrap_or((0, 0));
+
+        self.outputs.insert(
+            crtc,
+            GpuConnector {
+                connector,
+                name: connector_name,
+                physical_size: (width as i32, height as i32),
+                gbm_surface,
+                drm_modes: drm_modes.to_vec(),
+                preferred_mode: wl_modes[mode_id],
+                wl_modes,
+            },
+        );
 
-            let drm_mode = drm_modes[mode_id];
+        Ok(())
+    }
 
-            let mut drm_surface = drm.create_surface(crtc, drm_mode, &[connector])?;
-            drm_surface.link(session_signal.clone());
+    /// Crtc that can drive the connector and is not used by any other output
+    fn free_crtc(&self, connector: connector::Handle) -> Option<crtc::Handle> {
+        let drm = self.drm.inner();
 
-            let gbm_surface =
-                GbmBufferedSurface::new(drm_surface, gbm.clone(), formats.clone(), None)?;
+        let res_handles = drm.resource_handles().ok()?;
+        let connector_info = drm.get_connector(connector, false).ok()?;
 
-            outputs.insert(
-                crtc,
-                GpuConnector {
-                    connector,
-                    gbm_surface,
-                    drm_modes: drm_modes.to_vec(),
-                    wl_modes,
-                },
-            );
+        let encoder_infos = connector_info
+            .encoders()
+            .iter()
+            .flat_map(|encoder_handle| drm.get_encoder(*encoder_handle));
+
+        for encoder_info in encoder_infos {
+            for crtc in res_handles.filter_crtcs(encoder_info.possible_crtcs()) {
+                if !self.outputs.contains_key(&crtc) {
+                    return Some(crtc);
+                }
+            }
         }
 
-        Ok(Gpu {
-            drm,
-            drm_node,
-            outputs,
-        })
+        None
+    }
+
+    fn is_connected(&self, connector: connector::Handle) -> bool {
+        self.drm
+            .inner()
+            .get_connector(connector, false)
+            .map(|info| info.state() == connector::State::Connected)
+            .unwrap_or(false)
     }
 
-    pub fn clear_all(&mut self, renderer: &mut GpuManager<EglGlesBackend>) -> Result<bool> {
+    /// Stop drm events of the device, should be called before the gpu is dropped
+    pub fn unregister<D>(&self, event_loop: &LoopHandle<'static, D>) {
+        event_loop.remove(self.drm.registration_token());
+    }
+
+    /// Rendering is done by the primary gpu, the result is copied over to this one
+    pub fn clear_all(
+        &mut self,
+        primary_gpu: &DrmNode,
+        renderer: &mut GpuManager<EglGlesBackend>,
+    ) -> Result<bool> {
         let mut is_err = false;
 
-        let mut renderer = renderer.renderer(&self.drm_node, &self.drm_node)?;
+        let mut renderer = renderer.renderer(primary_gpu, &self.drm_node)?;
 
         for (_, output) in self.outputs.iter_mut() {
             is_err |= output.clear(&mut renderer).is_err();
@@ -154,12 +219,77 @@ impl Gpu {
         Ok(!is_err)
     }
 
+    /// Descriptors of all outputs, to announce them through [`OutputHandler::output_created`](crate::OutputHandler::output_created)
+    pub(super) fn descriptors(&self) -> Vec<(DrmOutputId, NewOutputDescriptor)> {
+        self.outputs
+            .iter()
+            .map(|(crtc, output)| {
+                let id = DrmOutputId {
+                    drm_node: self.drm_node,
+                    crtc: *crtc,
+                };
+
+                (id, output.descriptor(id.output_id()))
+            })
+            .collect()
+    }
+
+    /// Draw the first frame of new outputs, it starts their vblank loop
+    fn start_outputs(
+        &mut self,
+        crtcs: Vec<crtc::Handle>,
+        primary_gpu: &DrmNode,
+        gpu_manager: &mut GpuManager<EglGlesBackend>,
+    ) -> Vec<(DrmOutputId, NewOutputDescriptor)> {
+        let mut renderer = match gpu_manager.renderer(primary_gpu, &self.drm_node) {
+            Ok(renderer) => renderer,
+            Err(err) => {
+                error!("Failed to get the renderer of {:?}: {}", self.drm_node, err);
+                return Vec::new();
+            }
+        };
+
+        let mut created = Vec::new();
+
+        for crtc in crtcs {
+            let id = DrmOutputId {
+                drm_node: self.drm_node,
+                crtc,
+            };
+
+            let output = match self.outputs.get_mut(&crtc) {
+                Some(output) => output,
+                None => continue,
+            };
+
+            if let Err(err) = output.clear(&mut renderer) {
+                error!("Failed to clear output {}: {}", output.name, err);
+            }
+
+            created.push((id, output.descriptor(id.output_id())));
+        }
+
+        created
+    }
+
     pub fn drm_vblank<D>(drm_node: DrmNode, crtc: crtc::Handle, handler: &mut D) -> Result<()>
     where
         D: BackendHandler,
     {
         let primary_gpu = handler.backend_state().drm().primary_gpu;
 
+        // The connector could have been unplugged while the frame was in flight
+        let connected = handler
+            .backend_state()
+            .drm()
+            .gpu(&drm_node)
+            .map(|gpu| gpu.outputs.contains_key(&crtc))
+            .unwrap_or(false);
+
+        if !connected {
+            return Ok(());
+        }
+
         let gpu_manager = handler.backend_state().drm().gpu_manager.clone();
         let mut gpu_manager = gpu_manager.borrow_mut();
 
@@ -232,39 +362,206 @@ impl Gpu {
     where
         D: BackendHandler,
     {
-        if let Some(gpu) = handler.backend_state().drm().gpu(&drm_node) {
-            let scan = gpu.drm.scan_connectors();
-            info!("connectors: {:#?}", &scan);
+        let state = handler.backend_state().drm();
 
-            let removed: Vec<crtc::Handle> = scan
-                .removed
-                .iter()
-                .flat_map(|connector| {
-                    gpu.outputs
-                        .iter()
-                        .filter(|(_, o)| o.connector == *connector)
-                        .map(|(crtc, _)| *crtc)
-                })
-                .collect();
+        let gpu = match state.gpus.get_mut(&drm_node) {
+            Some(gpu) => gpu,
+            None => return,
+        };
+
+        let scan = gpu.drm.scan_connectors();
+        info!("connectors: {:#?}", &scan);
+
+        let removed: Vec<crtc::Handle> = scan
+            .removed
+            .iter()
+            .flat_map(|connector| {
+                gpu.outputs
+                    .iter()
+                    .filter(|(_, o)| o.connector == *connector)
+                    .map(|(crtc, _)| *crtc)
+            })
+            .collect();
+
+        // Dropping the surface releases the crtc
+        for crtc in removed.iter() {
+            gpu.outputs.remove(crtc);
+        }
 
-            for crtc in removed {
-                let id = super::DrmOutputId { drm_node, crtc };
-                handler.output_removed(&id.output_id());
+        let mut added = Vec::new();
+
+        for connector in scan.added {
+            let in_use = gpu.outputs.values().any(|o| o.connector == connector);
+            if in_use || !gpu.is_connected(connector) {
+                continue;
             }
 
-            for _connector in scan.added {}
+            let crtc = match gpu.free_crtc(connector) {
+                Some(crtc) => crtc,
+                None => {
+                    warn!("No free crtc for connector {:?}", connector);
+                    continue;
+                }
+            };
+
+            match gpu.setup_connector(connector, crtc) {
+                Ok(()) => added.push(crtc),
+                Err(err) => error!("Failed to setup connector {:?}: {}", connector, err),
+            }
+        }
+
+        let created = if added.is_empty() {
+            Vec::new()
+        } else {
+            let mut gpu_manager = state.gpu_manager.borrow_mut();
+            gpu.start_outputs(added, &state.primary_gpu, &mut gpu_manager)
+        };
+
+        let removed = removed
+            .into_iter()
+            .map(|crtc| DrmOutputId { drm_node, crtc });
+
+        remove_outputs(handler, removed);
+        create_outputs(handler, created);
+    }
+
+    /// Udev added event, opens the hotplugged gpu and brings up its outputs
+    pub fn added_event<D>(
+        event_loop: &LoopHandle<'static, D>,
+        session: &mut AutoSession,
+        session_signal: Signaler<SessionSignal>,
+        path: &Path,
+        drm_node: DrmNode,
+        handler: &mut D,
+    ) where
+        D: BackendHandler,
+        D: 'static,
+    {
+        let state = handler.backend_state().drm();
+
+        if state.gpus.contains_key(&drm_node) {
+            return;
+        }
+
+        let mut gpu = match Gpu::new(event_loop.clone(), session, session_signal, path, drm_node) {
+            Ok(gpu) => gpu,
+            Err(err) => {
+                error!("Failed to open gpu {:?}: {}", path, err);
+                return;
+            }
+        };
+
+        info!("GPU added: {:?}", path);
+
+        let crtcs = gpu.outputs.keys().copied().collect();
+        let created = {
+            let mut gpu_manager = state.gpu_manager.borrow_mut();
+            gpu.start_outputs(crtcs, &state.primary_gpu, &mut gpu_manager)
+        };
+
+        state.gpus.insert(drm_node, gpu);
+
+        create_outputs(handler, created);
+    }
+
+    /// Udev removed event, tears down the gpu and its outputs
+    ///
+    /// Nothing can be rendered without the primary gpu, so losing it closes the compositor.
+    pub fn removed_event<D>(event_loop: &LoopHandle<'static, D>, device_id: dev_t, handler: &mut D)
+    where
+        D: BackendHandler,
+    {
+        let state = handler.backend_state().drm();
+
+        // The device node is gone already, so it can not be turned into a render node
+        let drm_node = state
+            .gpus
+            .iter()
+            .find(|(_, gpu)| gpu.device_id == device_id)
+            .map(|(node, _)| *node);
+
+        let drm_node = match drm_node {
+            Some(drm_node) => drm_node,
+            None => return,
+        };
+
+        let is_primary = drm_node == state.primary_gpu;
+
+        // Every output is rendered by the primary gpu, none of them can be used without it
+        let gpus: Vec<Gpu> = if is_primary {
+            error!("The primary GPU got removed, nothing can be rendered anymore");
+            state.gpus.drain().map(|(_, gpu)| gpu).collect()
+        } else {
+            info!("GPU removed: {:?}", drm_node);
+            state.gpus.remove(&drm_node).into_iter().collect()
+        };
+
+        let mut removed = Vec::new();
+        for gpu in gpus.iter() {
+            gpu.unregister(event_loop);
+            removed.extend(gpu.descriptors().into_iter().map(|(id, _)| id));
+        }
+
+        // Releases surfaces and devices, before the compositor forgets the outputs
+        drop(gpus);
+
+        remove_outputs(handler, removed);
+
+        if is_primary {
+            handler.close_compositor();
         }
     }
 }
 
+/// Announce new outputs to the compositor
+pub(super) fn create_outputs<D>(handler: &mut D, outputs: Vec<(DrmOutputId, NewOutputDescriptor)>)
+where
+    D: BackendHandler,
+{
+    for (id, desc) in outputs {
+        OUTPUT_ID_MAP.with(|map| map.borrow_mut().insert(id.output_id(), id));
+        handler.output_created(desc);
+    }
+}
+
+fn remove_outputs<D>(handler: &mut D, outputs: impl IntoIterator<Item = DrmOutputId>)
+where
+    D: BackendHandler,
+{
+    for id in outputs {
+        OUTPUT_ID_MAP.with(|map| map.borrow_mut().remove(&id.output_id()));
+        handler.output_removed(&id.output_id());
+    }
+}
+
 pub struct GpuConnector {
     connector: connector::Handle,
+    name: String,
+    /// In millimeters
+    physical_size: (i32, i32),
     gbm_surface: GbmBufferedSurface<Rc<RefCell<GbmDevice<Device>>>, Device>,
     drm_modes: Vec<smithay::reexports::drm::control::Mode>,
+    preferred_mode: WlMode,
     wl_modes: Vec<WlMode>,
 }
 
 impl GpuConnector {
+    pub fn descriptor(&self, id: OutputId) -> NewOutputDescriptor {
+        NewOutputDescriptor {
+            id,
+            name: self.name.clone(),
+            physical_properties: PhysicalProperties {
+                size: self.physical_size.into(),
+                subpixel: Subpixel::Unknown,
+                make: "".into(),
+                model: "".into(),
+            },
+            prefered_mode: self.preferred_mode,
+            possible_modes: self.wl_modes.clone(),
+            transform: Transform::Normal,
+        }
+    }
+
     pub fn clear(&mut self, renderer: &mut DrmRenderer) -> Result<()> {
         self.gbm_surface.frame_submitted()?;
 
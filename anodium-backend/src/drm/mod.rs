@@ -17,7 +17,6 @@ use smithay::{
         },
         session::{auto::AutoSession, Session, Signal as SessionSignal},
     },
-    output::{Mode as WlMode, PhysicalProperties},
     reexports::{calloop::EventLoop, drm::control::crtc, wayland_server::DisplayHandle},
     utils::signaling::SignalToken,
     wayland::dmabuf::{DmabufGlobal, ImportError},
@@ -29,7 +28,7 @@ use device::{Device, DrmDevice};
 mod utils;
 
 mod gpu;
-use gpu::Gpu;
+use gpu::{create_outputs, Gpu};
 
 mod udev;
 
@@ -73,7 +72,7 @@ impl DrmBackendState {
 
     fn clear_all(&mut self) {
         for (_, gpu) in self.gpus.iter_mut() {
-            if let Err(err) = gpu.clear_all(&mut self.gpu_manager.borrow_mut()) {
+            if let Err(err) = gpu.clear_all(&self.primary_gpu, &mut self.gpu_manager.borrow_mut()) {
                 error!("{}", err);
             }
         }
@@ -133,7 +132,7 @@ where
 
     info!("Primary GPU: {:?}", primary_gpu_path);
 
-    udev::init(event_loop.handle(), session.seat())?;
+    udev::init(event_loop.handle(), session.clone(), session_signal.clone())?;
 
     let handle = event_loop.handle();
     let restart_token = session_signal.register(move |signal| match signal {
@@ -148,16 +147,38 @@ where
     let gpu = Gpu::new(
         event_loop.handle(),
         &mut session,
-        session_signal,
+        session_signal.clone(),
         &primary_gpu_path,
         primary_gpu_node,
     )?;
 
-    let outputs: Vec<_> = gpu.outputs.iter().map(|(crtc, _)| *crtc).collect();
-
     let mut gpus = HashMap::new();
     gpus.insert(primary_gpu_node, gpu);
 
+    // Outputs of other gpus are rendered by the primary one and copied over
+    for (path, node) in udev::all_gpus(&session.seat()) {
+        if gpus.contains_key(&node) {
+            continue;
+        }
+
+        info!("Secondary GPU: {:?}", path);
+
+        match Gpu::new(
+            event_loop.handle(),
+            &mut session,
+            session_signal.clone(),
+            &path,
+            node,
+        ) {
+            Ok(gpu) => {
+                gpus.insert(node, gpu);
+            }
+            Err(err) => error!("Failed to open gpu {:?}: {}", path, err),
+        }
+    }
+
+    let outputs: Vec<_> = gpus.values().flat_map(Gpu::descriptors).collect();
+
     let gpu_manager = GpuManager::new(EglGlesBackend, None)?;
     let gpu_manager = Rc::new(RefCell::new(gpu_manager));
 
@@ -211,33 +232,7 @@ where
         .dmabuf_state()
         .create_global::<D::WaylandState, _>(display, dmabuf_formats, None);
 
-    for crtc in outputs {
-        let id = DrmOutputId {
-            drm_node: primary_gpu_node,
-            crtc,
-        };
-
-        let mode = WlMode {
-            size: (1920, 1080).into(),
-            refresh: 60_000,
-        };
-
-        OUTPUT_ID_MAP.with(|map| map.borrow_mut().insert(id.output_id(), id));
-
-        handler.output_created(crate::NewOutputDescriptor {
-            id: id.output_id(),
-            name: "".into(),
-            physical_properties: PhysicalProperties {
-                size: (1920, 1080).into(),
-                subpixel: smithay::output::Subpixel::Unknown,
-                make: "".into(),
-                model: "".into(),
-            },
-            prefered_mode: mode,
-            possible_modes: vec![mode],
-            transform: smithay::utils::Transform::Normal,
-        })
-    }
+    create_outputs(handler, outputs);
 
     handler.start_compositor();
 
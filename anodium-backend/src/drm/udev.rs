@@ -4,14 +4,23 @@ use anyhow::Result;
 use smithay::{
     backend::{
         drm::{DrmNode, NodeType},
+        session::{auto::AutoSession, Session, Signal as SessionSignal},
         udev::{self, UdevBackend, UdevEvent},
     },
     reexports::calloop::LoopHandle,
+    utils::signaling::Signaler,
 };
 
 use super::gpu::Gpu;
 use crate::BackendHandler;
 
+/// Gpus are identified by their render node, if they have one
+fn render_node(node: DrmNode) -> DrmNode {
+    node.node_with_type(NodeType::Render)
+        .and_then(Result::ok)
+        .unwrap_or(node)
+}
+
 pub fn primary_gpu(seat: &str) -> (PathBuf, DrmNode) {
     udev::primary_gpu(seat)
         .unwrap()
@@ -31,24 +40,51 @@ pub fn primary_gpu(seat: &str) -> (PathBuf, DrmNode) {
         })
 }
 
-pub fn init<D>(event_loop: LoopHandle<D>, seat: String) -> Result<()>
+/// Every gpu of the seat, the primary one included
+pub fn all_gpus(seat: &str) -> Vec<(PathBuf, DrmNode)> {
+    udev::all_gpus(seat)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|p| {
+            let node = DrmNode::from_path(&p).ok()?;
+            Some((p, render_node(node)))
+        })
+        .collect()
+}
+
+pub fn init<D>(
+    event_loop: LoopHandle<'static, D>,
+    mut session: AutoSession,
+    session_signal: Signaler<SessionSignal>,
+) -> Result<()>
 where
     D: BackendHandler,
+    D: 'static,
 {
-    let udev_backend = UdevBackend::new(seat, None)?;
+    let udev_backend = UdevBackend::new(session.seat(), None)?;
 
+    let handle = event_loop.clone();
     event_loop
         .insert_source(udev_backend, move |event, _, handler| match event {
-            UdevEvent::Added { .. } => {
-                error!("GPU hotplug not supported");
+            UdevEvent::Added { device_id, path } => {
+                if let Ok(drm_node) = DrmNode::from_dev_id(device_id) {
+                    Gpu::added_event(
+                        &handle,
+                        &mut session,
+                        session_signal.clone(),
+                        &path,
+                        render_node(drm_node),
+                        handler,
+                    );
+                }
             }
             UdevEvent::Changed { device_id } => {
                 if let Ok(drm_node) = DrmNode::from_dev_id(device_id) {
-                    Gpu::changed_event(drm_node, handler);
+                    Gpu::changed_event(render_node(drm_node), handler);
                 }
             }
-            UdevEvent::Removed { .. } => {
-                error!("GPU hotplug not supported");
+            UdevEvent::Removed { device_id } => {
+                Gpu::removed_event(&handle, device_id, handler);
             }
         })
         .unwrap();
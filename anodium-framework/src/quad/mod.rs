@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 
-use std::num::NonZeroU32;
+use std::{num::NonZeroU32, rc::Rc};
 
 use cgmath::Matrix3;
 use smithay::{
@@ -8,18 +8,78 @@ use smithay::{
         ffi::{self, Gles2},
         Gles2Error, Gles2Frame, Gles2Renderer,
     },
-    desktop::space::{RenderElement, SpaceOutputTuple},
+    desktop::space::{RenderElement, RenderZindex, SpaceOutputTuple},
     utils::{Logical, Physical, Point, Rectangle, Scale, Size, Transform},
 };
 
 mod glow;
 use glow::{Program, Shader};
 
+/// Look of a quad drawn by the [`QuadPipeline`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuadStyle {
+    pub color: [f32; 4],
+    /// Only the outline of this width gets drawn, `0.0` fills the whole quad
+    pub border_width: f32,
+    pub corner_radius: f32,
+    /// Multiplied with the alpha of the color
+    pub alpha: f32,
+}
+
+impl QuadStyle {
+    /// Rectangle filled with the color
+    pub fn solid(color: [f32; 4]) -> Self {
+        Self {
+            color,
+            border_width: 0.0,
+            corner_radius: 0.0,
+            alpha: 1.0,
+        }
+    }
+
+    /// Outline of given width
+    pub fn border(color: [f32; 4], border_width: f32) -> Self {
+        Self {
+            border_width,
+            ..Self::solid(color)
+        }
+    }
+
+    pub fn with_corner_radius(mut self, corner_radius: f32) -> Self {
+        self.corner_radius = corner_radius;
+        self
+    }
+
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    /// Style with sizes converted from logical to physical pixels
+    fn to_physical(self, scale: f64) -> Self {
+        Self {
+            border_width: self.border_width * scale as f32,
+            corner_radius: self.corner_radius * scale as f32,
+            ..self
+        }
+    }
+}
+
+impl From<[f32; 4]> for QuadStyle {
+    fn from(color: [f32; 4]) -> Self {
+        Self::solid(color)
+    }
+}
+
 pub struct QuadPipeline {
     program: glow::Program,
 
     projection: glow::UniformLocation,
     color: glow::UniformLocation,
+    size: glow::UniformLocation,
+    border_width: glow::UniformLocation,
+    corner_radius: glow::UniformLocation,
+    alpha: glow::UniformLocation,
     position: u32, // AtributeLocation,
 }
 
@@ -31,31 +91,62 @@ impl QuadPipeline {
             include_str!("./shaders/quad.frag"),
         );
 
-        let (projection, color, position) = unsafe {
-            (
-                glow::get_uniform_location(gl, program, "projection").unwrap(),
-                glow::get_uniform_location(gl, program, "color").unwrap(),
-                glow::get_attrib_location(gl, program, "position").unwrap(),
-            )
-        };
+        let uniform = |name| unsafe { glow::get_uniform_location(gl, program, name).unwrap() };
 
         Self {
             program,
 
-            projection,
-            position,
-            color,
+            projection: uniform("projection"),
+            color: uniform("color"),
+            size: uniform("size"),
+            border_width: uniform("border_width"),
+            corner_radius: uniform("corner_radius"),
+            alpha: uniform("alpha"),
+            position: unsafe { glow::get_attrib_location(gl, program, "position").unwrap() },
         }
     }
 
     pub fn render(
+        &self,
+        output_geometry: Rectangle<f64, Physical>,
+        quad_rect: Rectangle<f64, Physical>,
+        transform: Transform,
+        gl: &Gles2,
+        style: QuadStyle,
+    ) {
+        self.render_clipped(output_geometry, quad_rect, quad_rect, transform, gl, style);
+    }
+
+    /// Same as [`QuadPipeline::render`], but only the part of the quad inside of `clip` gets drawn
+    ///
+    /// `clip` is in the same coordinates as `quad_rect`.
+    pub fn render_clipped(
         &self,
         output_geometry: Rectangle<f64, Physical>,
         mut quad_rect: Rectangle<f64, Physical>,
+        clip: Rectangle<f64, Physical>,
         transform: Transform,
         gl: &Gles2,
-        alpha: f32,
+        style: QuadStyle,
     ) {
+        let clip = match clip.intersection(quad_rect) {
+            Some(clip) => clip,
+            None => return,
+        };
+
+        // Corners of the clip within the quad, the shader still sees the whole quad
+        let x0 = ((clip.loc.x - quad_rect.loc.x) / quad_rect.size.w) as f32;
+        let y0 = ((clip.loc.y - quad_rect.loc.y) / quad_rect.size.h) as f32;
+        let x1 = ((clip.loc.x + clip.size.w - quad_rect.loc.x) / quad_rect.size.w) as f32;
+        let y1 = ((clip.loc.y + clip.size.h - quad_rect.loc.y) / quad_rect.size.h) as f32;
+
+        let verts: [ffi::types::GLfloat; 8] = [
+            x1, y0, // top right
+            x0, y0, // top left
+            x1, y1, // bottom right
+            x0, y1, // bottom left
+        ];
+
         quad_rect.loc.x -= output_geometry.loc.x;
 
         let screen = Matrix3 {
@@ -89,13 +180,12 @@ impl QuadPipeline {
                 mat.as_ptr(),
             );
 
-            gl.Uniform4f(
-                self.color.0 as i32,
-                26.0 / 255.0,
-                95.0 / 255.0,
-                205.0 / 255.0,
-                alpha,
-            );
+            let [r, g, b, a] = style.color;
+            gl.Uniform4f(self.color.0 as i32, r, g, b, a);
+            gl.Uniform2f(self.size.0 as i32, w, h);
+            gl.Uniform1f(self.border_width.0 as i32, style.border_width);
+            gl.Uniform1f(self.corner_radius.0 as i32, style.corner_radius);
+            gl.Uniform1f(self.alpha.0 as i32, style.alpha);
 
             gl.VertexAttribPointer(
                 self.position,
@@ -103,7 +193,7 @@ impl QuadPipeline {
                 ffi::FLOAT,
                 ffi::FALSE as u8,
                 0,
-                VERTS.as_ptr() as *const _,
+                verts.as_ptr() as *const _,
             );
 
             gl.EnableVertexAttribArray(self.position);
@@ -117,30 +207,65 @@ impl QuadPipeline {
 }
 
 pub struct QuadElement {
-    pipeline: QuadPipeline,
+    pipeline: Rc<QuadPipeline>,
+    id: usize,
     position: Point<i32, Logical>,
     size: Size<i32, Logical>,
+    style: QuadStyle,
+    z_index: u8,
+    damaged: bool,
     output_geometry: Rectangle<f64, Physical>,
 }
 
 impl QuadElement {
+    /// Rectangle, or its outline, with optionally rounded corners, sizes of the style are logical
+    ///
+    /// `rect` is in global coordinates, `output_size` is the physical size of the rendered output.
     pub fn new(
-        gl: &Gles2,
-        position: Point<i32, Logical>,
-        output_geometry: Rectangle<f64, Physical>,
+        pipeline: Rc<QuadPipeline>,
+        rect: Rectangle<i32, Logical>,
+        style: impl Into<QuadStyle>,
+        output_size: Size<f64, Physical>,
     ) -> Self {
         Self {
-            pipeline: QuadPipeline::new(gl),
-            position,
-            size: (100, 100).into(),
-            output_geometry,
+            pipeline,
+            id: 0,
+            position: rect.loc,
+            size: rect.size,
+            style: style.into(),
+            z_index: RenderZindex::Overlay as u8,
+            damaged: true,
+            output_geometry: Rectangle::from_loc_and_size((0.0, 0.0), output_size),
         }
     }
+
+    /// Elements drawn in the same frame need distinct ids for damage tracking
+    pub fn with_id(mut self, id: usize) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Layer the quad is drawn in, above everything by default
+    ///
+    /// Quads that belong to a window should be drawn in [`RenderZindex::Shell`],
+    /// so they keep the stacking order of windows.
+    pub fn with_z_index(mut self, z_index: u8) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    /// Did the look of the quad change since the last frame, it is redrawn every frame by default
+    ///
+    /// Moves and resizes get damaged by the space anyway.
+    pub fn with_damaged(mut self, damaged: bool) -> Self {
+        self.damaged = damaged;
+        self
+    }
 }
 
 impl RenderElement<Gles2Renderer> for QuadElement {
     fn id(&self) -> usize {
-        0
+        self.id
     }
 
     fn location(&self, scale: impl Into<Scale<f64>>) -> Point<f64, Physical> {
@@ -156,6 +281,10 @@ impl RenderElement<Gles2Renderer> for QuadElement {
         scale: impl Into<Scale<f64>>,
         _: Option<SpaceOutputTuple<'_, '_>>,
     ) -> Vec<Rectangle<i32, Physical>> {
+        if !self.damaged {
+            return Vec::new();
+        }
+
         vec![Rectangle::from_loc_and_size((0, 0), self.size).to_physical_precise_up(scale)]
     }
 
@@ -165,20 +294,27 @@ impl RenderElement<Gles2Renderer> for QuadElement {
         _frame: &mut Gles2Frame,
         scale: impl Into<Scale<f64>>,
         location: Point<f64, Physical>,
-        _damage: &[Rectangle<i32, Physical>],
+        damage: &[Rectangle<i32, Physical>],
         _log: &slog::Logger,
     ) -> Result<(), Gles2Error> {
+        let scale = scale.into();
+        let quad_rect = Rectangle::from_loc_and_size(
+            self.output_geometry.loc.to_f64() + location,
+            self.size.to_f64().to_physical(scale),
+        );
+
         renderer.with_context(|_, gl| {
-            self.pipeline.render(
-                self.output_geometry,
-                Rectangle::from_loc_and_size(
-                    self.output_geometry.loc.to_f64() + location,
-                    self.size.to_f64().to_physical(scale),
-                ),
-                Transform::Flipped180,
-                gl,
-                1.0,
-            )
+            // Drawing outside of the damage would blend translucent parts twice
+            for damage in damage {
+                self.pipeline.render_clipped(
+                    self.output_geometry,
+                    quad_rect,
+                    damage.to_f64(),
+                    Transform::Flipped180,
+                    gl,
+                    self.style.to_physical(scale.x),
+                )
+            }
         })
     }
 
@@ -188,14 +324,11 @@ impl RenderElement<Gles2Renderer> for QuadElement {
     ) -> Option<Vec<Rectangle<i32, Physical>>> {
         None
     }
-}
 
-static VERTS: [ffi::types::GLfloat; 8] = [
-    1.0, 0.0, // top right
-    0.0, 0.0, // top left
-    1.0, 1.0, // bottom right
-    0.0, 1.0, // bottom left
-];
+    fn z_index(&self) -> u8 {
+        self.z_index
+    }
+}
 
 fn create_program(
     gl: &Gles2,
@@ -1,9 +1,38 @@
 #version 100
 
 precision mediump float;
-varying vec4 v_color;
+
+uniform vec4 color;
+// Size of the quad in physical pixels
+uniform vec2 size;
+uniform float border_width;
+uniform float corner_radius;
+uniform float alpha;
+
 varying vec2 v_texcoord;
 
+// Signed distance from a rounded rectangle centered at the origin
+float rounded_rect(vec2 point, vec2 half_size, float radius) {
+	vec2 q = abs(point) - half_size + radius;
+	return length(max(q, 0.0)) + min(max(q.x, q.y), 0.0) - radius;
+}
+
 void main() {
-	gl_FragColor = v_color * v_color.w;
-};
\ No newline at end of file
+	vec2 half_size = size * 0.5;
+	vec2 point = v_texcoord * size - half_size;
+
+	float radius = min(corner_radius, min(half_size.x, half_size.y));
+
+	// Anti-aliased over a single pixel
+	float coverage = clamp(0.5 - rounded_rect(point, half_size, radius), 0.0, 1.0);
+
+	if (border_width > 0.0) {
+		float inner_radius = max(radius - border_width, 0.0);
+		float inner = rounded_rect(point, half_size - border_width, inner_radius);
+		coverage *= clamp(0.5 + inner, 0.0, 1.0);
+	}
+
+	// Premultiplied alpha
+	float a = color.a * alpha * coverage;
+	gl_FragColor = vec4(color.rgb * a, a);
+}
@@ -9,13 +9,8 @@ fn main() {
     let out_dir = Path::new(&out_dir_str);
 
     println!("cargo:rerun-if-changed={}", protocol_file);
-    generate_code_with_destructor_events(
-        protocol_file,
-        out_dir.join("server_api.rs"),
-        Side::Server,
-        &[],
-    );
 
+    // Server code is generated by the macros of the newer scanner, see `server::protocol`
     generate_code_with_destructor_events(
         protocol_file,
         out_dir.join("client_api.rs"),
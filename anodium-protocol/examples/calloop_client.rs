@@ -1,23 +1,31 @@
-use anodium_protocol::client::AnodiumOutputEvent;
+use anodium_protocol::client::{AnodiumOutputEvent, AnodiumWorkspaceManagerEvent};
 use calloop::EventLoop;
 
 fn main() {
     let mut ev: EventLoop<()> = EventLoop::try_new().unwrap();
 
-    anodium_protocol::client::calloop::init(ev.handle(), |output, _ddata| {
-        println!("New Output: {:?}", output);
+    anodium_protocol::client::calloop::init(ev.handle(), |event, _ddata| match event {
+        AnodiumWorkspaceManagerEvent::NewOutput(output) => {
+            println!("New Output: {:?}", output);
 
-        output.init(|output_event, _ddata| match output_event {
-            AnodiumOutputEvent::NewWorkspace(workspace) => {
-                println!("New Workspace: {:?}", workspace);
-                workspace.init(|event, _ddata| {
-                    println!("New Workspace Event: {:?}", event);
-                });
-            }
-            AnodiumOutputEvent::Name(name) => {
-                println!("Output Name: {:?}", name);
-            }
-        })
+            output.init(|output_event, _ddata| match output_event {
+                AnodiumOutputEvent::NewWorkspace(workspace) => {
+                    println!("New Workspace: {:?}", workspace);
+                    workspace.init(|event, _ddata| {
+                        println!("New Workspace Event: {:?}", event);
+                    });
+                }
+                AnodiumOutputEvent::Name(name) => {
+                    println!("Output Name: {:?}", name);
+                }
+                AnodiumOutputEvent::Removed => {
+                    println!("Output Removed");
+                }
+            })
+        }
+        AnodiumWorkspaceManagerEvent::BindingMode(name) => {
+            println!("Binding Mode: {:?}", name);
+        }
     })
     .unwrap();
 
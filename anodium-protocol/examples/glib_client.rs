@@ -1,4 +1,4 @@
-use anodium_protocol::client::AnodiumOutputEvent;
+use anodium_protocol::client::{AnodiumOutputEvent, AnodiumWorkspaceManagerEvent};
 
 fn main() {
     let context = glib::MainContext::default();
@@ -6,20 +6,28 @@ fn main() {
 
     println!("{:?}", context.is_owner());
 
-    anodium_protocol::client::glib::init((), |output, _ddata| {
-        println!("New Output: {:?}", output);
+    anodium_protocol::client::glib::init((), |event, _ddata| match event {
+        AnodiumWorkspaceManagerEvent::NewOutput(output) => {
+            println!("New Output: {:?}", output);
 
-        output.init(|output_event, _ddata| match output_event {
-            AnodiumOutputEvent::NewWorkspace(workspace) => {
-                println!("New Workspace: {:?}", workspace);
-                workspace.init(|event, _ddata| {
-                    println!("New Workspace Event: {:?}", event);
-                });
-            }
-            AnodiumOutputEvent::Name(name) => {
-                println!("Output Name: {:?}", name);
-            }
-        })
+            output.init(|output_event, _ddata| match output_event {
+                AnodiumOutputEvent::NewWorkspace(workspace) => {
+                    println!("New Workspace: {:?}", workspace);
+                    workspace.init(|event, _ddata| {
+                        println!("New Workspace Event: {:?}", event);
+                    });
+                }
+                AnodiumOutputEvent::Name(name) => {
+                    println!("Output Name: {:?}", name);
+                }
+                AnodiumOutputEvent::Removed => {
+                    println!("Output Removed");
+                }
+            })
+        }
+        AnodiumWorkspaceManagerEvent::BindingMode(name) => {
+            println!("Binding Mode: {:?}", name);
+        }
     });
 
     l.run();
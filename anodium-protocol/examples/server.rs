@@ -1,86 +1,86 @@
-use std::{cell::RefCell, error::Error, rc::Rc, time::Duration};
+use std::{sync::Arc, time::Duration};
 
-use anodium_protocol::server::AnodiumProtocol;
-use calloop::{EventLoop, LoopHandle};
-use wayland_server::Display;
+use anodium_protocol::{
+    delegate_anodium_protocol,
+    server::{AnodiumProtocol, AnodiumProtocolHandler, OutputDescription, WorkspaceDescription},
+};
+use wayland_server::{
+    backend::{ClientData, ClientId, DisconnectReason},
+    Display, ListeningSocket,
+};
 
 struct State {
-    display: Rc<RefCell<Display>>,
     anodium_protocol: AnodiumProtocol,
 }
 
-fn insert_wayland_source(
-    handle: LoopHandle<'static, State>,
-    display: &Display,
-) -> Result<(), Box<dyn Error>> {
-    handle.insert_source(
-        calloop::generic::Generic::from_fd(
-            display.get_poll_fd(), // The file descriptor which indicates there are pending messages
-            calloop::Interest::READ,
-            calloop::Mode::Level,
-        ),
-        // This callback is invoked when the poll file descriptor has had activity, indicating there are pending messages.
-        move |_, _, state: &mut State| {
-            let display = state.display.clone();
-            let mut display = display.borrow_mut();
-            // Display::dispatch will process any queued up requests and send those events to any objects created on the server.
-            display.dispatch(Duration::from_millis(0), state).unwrap();
-
-            Ok(calloop::PostAction::Continue)
-        },
-    )?;
-    Ok(())
+impl AnodiumProtocolHandler for State {
+    fn anodium_protocol(&mut self) -> &mut AnodiumProtocol {
+        &mut self.anodium_protocol
+    }
 }
 
-fn main() {
-    let mut display = Display::new();
-    let socket_name = "wayland-0";
-
-    display
-        .add_socket(Some(socket_name))
-        .expect("Failed to add wayland socket");
-
-    println!("Listening on wayland socket {}", socket_name);
-
-    let mut event_loop: EventLoop<State> = EventLoop::try_new().unwrap();
+delegate_anodium_protocol!(State);
 
-    insert_wayland_source(event_loop.handle(), &display).unwrap();
+struct ClientState;
 
-    let (mut anodium_protocol, _) = AnodiumProtocol::init(&mut display);
-
-    let mut out1 = anodium_protocol.new_output();
-    out1.set_name("HDMI-1");
+impl ClientData for ClientState {
+    fn initialized(&self, _client_id: ClientId) {}
+    fn disconnected(&self, _client_id: ClientId, _reason: DisconnectReason) {}
+}
 
-    let display = Rc::new(RefCell::new(display));
+fn main() {
+    let mut display: Display<State> = Display::new().unwrap();
+    let socket_name = "wayland-0";
 
-    let timer = calloop::timer::Timer::new().unwrap();
-    let handle = timer.handle();
+    let listener = ListeningSocket::bind(socket_name).expect("Failed to add wayland socket");
 
-    event_loop
-        .handle()
-        .insert_source(timer, {
-            let handle = handle.clone();
-            move |count, _, state| {
-                let mut output = state.anodium_protocol.new_output();
-                output.set_name(format!("HDMI-{}", count));
+    println!("Listening on wayland socket {}", socket_name);
 
-                handle.add_timeout(Duration::from_secs(1), count + 1);
+    let mut state = State {
+        anodium_protocol: AnodiumProtocol::new::<State>(&display.handle()),
+    };
+
+    let mut outputs = Vec::new();
+
+    for count in 0.. {
+        if let Some(stream) = listener.accept().unwrap() {
+            display
+                .handle()
+                .insert_client(stream, Arc::new(ClientState))
+                .unwrap();
+        }
+
+        // A new output every second, the workspace shown on all of them moves along
+        if count % 10 == 0 {
+            outputs.push(OutputDescription {
+                name: format!("HDMI-{}", outputs.len()),
+                workspaces: (1..=3)
+                    .map(|id| WorkspaceDescription {
+                        name: id.to_string(),
+                        active: false,
+                    })
+                    .collect(),
+            });
+
+            for output in outputs.iter_mut() {
+                for (id, workspace) in output.workspaces.iter_mut().enumerate() {
+                    workspace.active = id == (count / 10) % 3;
+                }
             }
-        })
-        .unwrap();
-
-    handle.add_timeout(Duration::from_secs(1), 0);
-
-    event_loop
-        .run(
-            None,
-            &mut State {
-                display,
-                anodium_protocol,
-            },
-            |state| {
-                state.display.clone().borrow_mut().flush_clients(state);
-            },
-        )
-        .unwrap();
+
+            state
+                .anodium_protocol
+                .set_outputs::<State>(&display.handle(), outputs.clone());
+            state.anodium_protocol.set_binding_mode(if count % 20 == 0 {
+                "default"
+            } else {
+                "resize"
+            });
+        }
+
+        display.dispatch_clients(&mut state).unwrap();
+        display.flush_clients().unwrap();
+
+        std::thread::sleep(Duration::from_millis(100));
+    }
 }
@@ -1,6 +1,8 @@
 use gtk::prelude::*;
 
-use anodium_protocol::client::{AnodiumOutputEvent, AnodiumWorkspaceEvent};
+use anodium_protocol::client::{
+    AnodiumOutputEvent, AnodiumWorkspaceEvent, AnodiumWorkspaceManagerEvent,
+};
 
 fn build_ui(application: &gtk::Application) {
     let window = gtk::ApplicationWindow::new(application);
@@ -15,7 +17,18 @@ fn build_ui(application: &gtk::Application) {
 
     std::env::set_var("WAYLAND_DISPLAY", "wayland-0");
 
-    anodium_protocol::client::glib::init((), move |output, _| {
+    let mode_label = gtk::Label::builder().label("Mode: Unknown").build();
+    root.add(&mode_label);
+
+    anodium_protocol::client::glib::init((), move |event, _| {
+        let output = match event {
+            AnodiumWorkspaceManagerEvent::NewOutput(output) => output,
+            AnodiumWorkspaceManagerEvent::BindingMode(name) => {
+                mode_label.set_text(&format!("Mode: {}", name));
+                return;
+            }
+        };
+
         let output_root = gtk::Box::builder()
             .orientation(gtk::Orientation::Vertical)
             .build();
@@ -23,21 +36,39 @@ fn build_ui(application: &gtk::Application) {
 
         output_root.add(&label);
         root.add(&output_root);
+        output_root.show_all();
 
+        let root = root.clone();
         output.init(move |output_event, _| match output_event {
             AnodiumOutputEvent::NewWorkspace(workspace) => {
                 let label = gtk::Label::builder().label("Workspace: Unknown").build();
                 output_root.add(&label);
+                label.show();
+
+                let output_root = output_root.clone();
+
+                let name = std::cell::RefCell::new(String::new());
 
                 workspace.init(move |event, _| match event {
-                    AnodiumWorkspaceEvent::Name(name) => {
-                        label.set_text(&format!("Workspace: {}", name));
+                    AnodiumWorkspaceEvent::Name(new_name) => {
+                        label.set_text(&format!("Workspace: {}", new_name));
+                        *name.borrow_mut() = new_name;
+                    }
+                    AnodiumWorkspaceEvent::Active(active) => {
+                        let marker = if active { "*" } else { "" };
+                        label.set_text(&format!("Workspace: {}{}", name.borrow(), marker));
+                    }
+                    AnodiumWorkspaceEvent::Removed => {
+                        output_root.remove(&label);
                     }
                 });
             }
             AnodiumOutputEvent::Name(name) => {
                 label.set_text(&format!("Output: {}", name));
             }
+            AnodiumOutputEvent::Removed => {
+                root.remove(&output_root);
+            }
         });
     });
 
@@ -3,7 +3,7 @@ use std::{cell::RefCell, error::Error, rc::Rc};
 use calloop::{LoopHandle, RegistrationToken};
 use wayland_client::{DispatchData, Display, EventQueue, GlobalManager};
 
-use super::{init_global, AnodiumOutput};
+use super::{init_global, AnodiumWorkspaceManagerEvent};
 
 pub fn init<D, F>(
     handle: LoopHandle<'static, D>,
@@ -11,7 +11,7 @@ pub fn init<D, F>(
 ) -> Result<RegistrationToken, Box<dyn Error + Send + Sync>>
 where
     D: 'static,
-    F: Fn(AnodiumOutput, DispatchData) + 'static,
+    F: Fn(AnodiumWorkspaceManagerEvent, DispatchData) + 'static,
 {
     // Connect to the server
     let display = Display::connect_to_env()?;
@@ -1,11 +1,11 @@
 use glib::MainContext;
 use wayland_client::{DispatchData, Display, GlobalManager};
 
-use super::{init_global, AnodiumOutput};
+use super::{init_global, AnodiumWorkspaceManagerEvent};
 
 pub fn init<F, D>(mut data: D, cb: F) -> glib::SourceId
 where
-    F: Fn(AnodiumOutput, DispatchData) + 'static,
+    F: Fn(AnodiumWorkspaceManagerEvent, DispatchData) + 'static,
     D: 'static,
 {
     // Connect to the server
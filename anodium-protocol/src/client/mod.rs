@@ -27,6 +27,10 @@ pub mod glib;
 #[derive(Debug)]
 pub enum AnodiumWorkspaceEvent {
     Name(String),
+    /// Shown on its output or hidden
+    Active(bool),
+    /// No more events follow, the workspace got destroyed
+    Removed,
 }
 
 #[derive(Debug)]
@@ -43,9 +47,16 @@ impl AnodiumWorkspace {
     where
         F: Fn(AnodiumWorkspaceEvent, DispatchData) + 'static,
     {
-        self.res.quick_assign(move |_workspace, event, ddata| {
+        self.res.quick_assign(move |workspace, event, ddata| {
             let event = match event {
                 anodium_workspace::Event::Name { name } => AnodiumWorkspaceEvent::Name(name),
+                anodium_workspace::Event::Active { active } => {
+                    AnodiumWorkspaceEvent::Active(active != 0)
+                }
+                anodium_workspace::Event::Removed => {
+                    workspace.destroy();
+                    AnodiumWorkspaceEvent::Removed
+                }
             };
 
             cb(event, ddata);
@@ -57,6 +68,8 @@ impl AnodiumWorkspace {
 pub enum AnodiumOutputEvent {
     NewWorkspace(AnodiumWorkspace),
     Name(String),
+    /// No more events follow, the output got destroyed
+    Removed,
 }
 
 #[derive(Debug)]
@@ -73,7 +86,7 @@ impl AnodiumOutput {
     where
         F: Fn(AnodiumOutputEvent, DispatchData) + 'static,
     {
-        self.res.quick_assign(move |_output, event, ddata| {
+        self.res.quick_assign(move |output, event, ddata| {
             match event {
                 anodium_output::Event::Workspace { workspace } => {
                     cb(
@@ -84,21 +97,39 @@ impl AnodiumOutput {
                 anodium_output::Event::Name { name } => {
                     cb(AnodiumOutputEvent::Name(name), ddata);
                 }
+                anodium_output::Event::Removed => {
+                    output.destroy();
+                    cb(AnodiumOutputEvent::Removed, ddata);
+                }
             };
         });
     }
 }
 
+#[derive(Debug)]
+pub enum AnodiumWorkspaceManagerEvent {
+    NewOutput(AnodiumOutput),
+    /// Name of the active keybinding mode
+    BindingMode(String),
+}
+
 pub fn init_global<F>(globals: &GlobalManager, cb: F)
 where
-    F: Fn(AnodiumOutput, DispatchData) + 'static,
+    F: Fn(AnodiumWorkspaceManagerEvent, DispatchData) + 'static,
 {
+    // Version 1 compositors send no binding mode, active or removed events
     globals
-        .instantiate_exact::<anodium_workspace_manager::AnodiumWorkspaceManager>(1)
+        .instantiate_range::<anodium_workspace_manager::AnodiumWorkspaceManager>(1, 2)
         .expect("Compositor does not support anodium protocol")
         .quick_assign(move |_manager, event, ddata| match event {
             anodium_workspace_manager::Event::Output { output } => {
-                cb(AnodiumOutput::new(output), ddata);
+                cb(
+                    AnodiumWorkspaceManagerEvent::NewOutput(AnodiumOutput::new(output)),
+                    ddata,
+                );
+            }
+            anodium_workspace_manager::Event::BindingMode { name } => {
+                cb(AnodiumWorkspaceManagerEvent::BindingMode(name), ddata);
             }
         });
 }
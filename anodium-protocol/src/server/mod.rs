@@ -1,6 +1,7 @@
-use std::{cell::RefCell, ops::Deref, rc::Rc};
-
-use wayland_server::{Display, Filter, Global, Main};
+use wayland_server::{
+    backend::{ClientId, GlobalId, ObjectId},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
 
 /// Generated interfaces for the protocol
 pub mod protocol {
@@ -8,77 +9,266 @@ pub mod protocol {
     #![allow(non_upper_case_globals, non_snake_case, unused_imports)]
     #![allow(missing_docs, clippy::all)]
 
-    use wayland_commons::{
-        map::{Object, ObjectMetadata},
-        smallvec,
-        wire::{Argument, ArgumentType, Message, MessageDesc},
-        Interface, MessageGroup,
-    };
-    use wayland_server::*;
-    include!(concat!(env!("OUT_DIR"), "/server_api.rs"));
+    use wayland_server;
+
+    pub mod __interfaces {
+        wayland_scanner::generate_interfaces!("./anodium.xml");
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_server_code!("./anodium.xml");
 }
 
-use protocol::anodium_workspace_manager::AnodiumWorkspaceManager;
+#[doc(hidden)]
+pub use wayland_server as __wayland_server;
+
+use protocol::{
+    anodium_output::{self, AnodiumOutput},
+    anodium_workspace::{self, AnodiumWorkspace},
+    anodium_workspace_manager::{self, AnodiumWorkspaceManager},
+};
 
 mod output;
 mod workspace;
 
-pub use output::AnodiumProtocolOutput;
-pub use workspace::AnodiumProtocolWorkspace;
+use output::AnodiumProtocolOutput;
 
-#[derive(Default)]
-struct Inner {
-    known: Vec<AnodiumWorkspaceManager>,
-    outputs: Vec<AnodiumProtocolOutput>,
+/// Workspace as clients see it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceDescription {
+    pub name: String,
+    /// Shown on its output
+    pub active: bool,
 }
 
+/// Output as clients see it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputDescription {
+    pub name: String,
+    pub workspaces: Vec<WorkspaceDescription>,
+}
+
+/// State of the `anodium_workspace_manager` global
+///
+/// The compositor pushes the whole picture with [`AnodiumProtocol::set_outputs`]
+/// and [`AnodiumProtocol::set_binding_mode`], clients only get told what changed.
+#[derive(Debug)]
 pub struct AnodiumProtocol {
-    inner: Rc<RefCell<Inner>>,
+    global: GlobalId,
+    managers: Vec<AnodiumWorkspaceManager>,
+    outputs: Vec<AnodiumProtocolOutput>,
+    binding_mode: String,
+}
+
+pub trait AnodiumProtocolHandler {
+    fn anodium_protocol(&mut self) -> &mut AnodiumProtocol;
 }
 
 impl AnodiumProtocol {
-    pub fn init(display: &mut Display) -> (AnodiumProtocol, Global<AnodiumWorkspaceManager>) {
-        let inner = Rc::new(RefCell::new(Inner::default()));
-
-        let global: Global<AnodiumWorkspaceManager> = display.create_global(1, {
-            let inner = inner.clone();
-            Filter::new(
-                move |(manager, _): (Main<AnodiumWorkspaceManager>, _), _, _| {
-                    manager.quick_assign(|_res, _, _| {});
-                    manager.assign_destructor(Filter::new({
-                        let inner = inner.clone();
-                        move |res: AnodiumWorkspaceManager, _, _| {
-                            inner
-                                .borrow_mut()
-                                .known
-                                .retain(|m| !m.as_ref().equals(res.as_ref()));
-                        }
-                    }));
-
-                    let mut inner = inner.borrow_mut();
-                    for output in inner.outputs.iter_mut() {
-                        output.new_instance(&manager)
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<AnodiumWorkspaceManager, ()> + 'static,
+    {
+        let global = display.create_global::<D, AnodiumWorkspaceManager, _>(2, ());
+
+        Self {
+            global,
+            managers: Vec::new(),
+            outputs: Vec::new(),
+            binding_mode: String::new(),
+        }
+    }
+
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Name of the active set of keybindings
+    pub fn set_binding_mode(&mut self, name: &str) {
+        if self.binding_mode == name {
+            return;
+        }
+
+        self.binding_mode = name.to_owned();
+
+        for manager in self.managers.iter() {
+            send_binding_mode(manager, name);
+        }
+    }
+
+    /// Outputs and their workspaces, matched with the previous ones by name
+    pub fn set_outputs<D>(&mut self, dh: &DisplayHandle, outputs: Vec<OutputDescription>)
+    where
+        D: Dispatch<AnodiumOutput, ()> + Dispatch<AnodiumWorkspace, ()> + 'static,
+    {
+        let (kept, removed): (Vec<_>, Vec<_>) = std::mem::take(&mut self.outputs)
+            .into_iter()
+            .partition(|known| outputs.iter().any(|output| output.name == known.name()));
+
+        self.outputs = kept;
+
+        for output in removed {
+            output.remove();
+        }
+
+        for description in outputs {
+            let known = self
+                .outputs
+                .iter_mut()
+                .find(|known| known.name() == description.name);
+
+            match known {
+                Some(output) => output.set_workspaces::<D>(dh, &description.workspaces),
+                None => {
+                    let mut output = AnodiumProtocolOutput::new(description);
+
+                    for manager in self.managers.iter() {
+                        output.new_instance::<D>(dh, manager);
                     }
 
-                    inner.known.push(manager.deref().clone());
-                },
-            )
-        });
+                    self.outputs.push(output);
+                }
+            }
+        }
+    }
+
+    fn new_manager<D>(&mut self, dh: &DisplayHandle, manager: AnodiumWorkspaceManager)
+    where
+        D: Dispatch<AnodiumOutput, ()> + Dispatch<AnodiumWorkspace, ()> + 'static,
+    {
+        send_binding_mode(&manager, &self.binding_mode);
+
+        for output in self.outputs.iter_mut() {
+            output.new_instance::<D>(dh, &manager);
+        }
+
+        self.managers.push(manager);
+    }
+
+    /// Forget a destroyed object of any of the interfaces
+    fn instance_destroyed(&mut self, id: &ObjectId) {
+        self.managers.retain(|manager| manager.id() != *id);
+
+        for output in self.outputs.iter_mut() {
+            output.instance_destroyed(id);
+        }
+    }
+}
+
+/// Managers bound at version 1 don't know about binding modes
+fn send_binding_mode(manager: &AnodiumWorkspaceManager, name: &str) {
+    if manager.version() >= anodium_workspace_manager::EVT_BINDING_MODE_SINCE {
+        manager.binding_mode(name.to_owned());
+    }
+}
 
-        (AnodiumProtocol { inner }, global)
+impl<D> GlobalDispatch<AnodiumWorkspaceManager, (), D> for AnodiumProtocol
+where
+    D: GlobalDispatch<AnodiumWorkspaceManager, ()>
+        + Dispatch<AnodiumWorkspaceManager, ()>
+        + Dispatch<AnodiumOutput, ()>
+        + Dispatch<AnodiumWorkspace, ()>
+        + AnodiumProtocolHandler
+        + 'static,
+{
+    fn bind(
+        state: &mut D,
+        dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<AnodiumWorkspaceManager>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let manager = data_init.init(resource, ());
+        state.anodium_protocol().new_manager::<D>(dh, manager);
     }
+}
 
-    pub fn new_output(&mut self) -> AnodiumProtocolOutput {
-        let mut inner = self.inner.borrow_mut();
+impl<D> Dispatch<AnodiumWorkspaceManager, (), D> for AnodiumProtocol
+where
+    D: Dispatch<AnodiumWorkspaceManager, ()> + AnodiumProtocolHandler + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &AnodiumWorkspaceManager,
+        request: anodium_workspace_manager::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            anodium_workspace_manager::Request::Destroy => {}
+        }
+    }
 
-        let mut output = AnodiumProtocolOutput::new();
+    fn destroyed(state: &mut D, _client: ClientId, resource: ObjectId, _data: &()) {
+        state.anodium_protocol().instance_destroyed(&resource);
+    }
+}
 
-        for res in inner.known.iter() {
-            output.new_instance(res);
+impl<D> Dispatch<AnodiumOutput, (), D> for AnodiumProtocol
+where
+    D: Dispatch<AnodiumOutput, ()> + AnodiumProtocolHandler + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &AnodiumOutput,
+        request: anodium_output::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            anodium_output::Request::Destroy => {}
         }
+    }
 
-        inner.outputs.push(output.clone());
+    fn destroyed(state: &mut D, _client: ClientId, resource: ObjectId, _data: &()) {
+        state.anodium_protocol().instance_destroyed(&resource);
+    }
+}
 
-        output
+impl<D> Dispatch<AnodiumWorkspace, (), D> for AnodiumProtocol
+where
+    D: Dispatch<AnodiumWorkspace, ()> + AnodiumProtocolHandler + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &AnodiumWorkspace,
+        request: anodium_workspace::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            anodium_workspace::Request::Destroy => {}
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: ObjectId, _data: &()) {
+        state.anodium_protocol().instance_destroyed(&resource);
     }
 }
+
+/// Implement the protocol for the compositor state, it has to implement [`AnodiumProtocolHandler`]
+#[macro_export]
+macro_rules! delegate_anodium_protocol {
+    ($ty: ty) => {
+        $crate::server::__wayland_server::delegate_global_dispatch!($ty: [
+            $crate::server::protocol::anodium_workspace_manager::AnodiumWorkspaceManager: ()
+        ] => $crate::server::AnodiumProtocol);
+
+        $crate::server::__wayland_server::delegate_dispatch!($ty: [
+            $crate::server::protocol::anodium_workspace_manager::AnodiumWorkspaceManager: ()
+        ] => $crate::server::AnodiumProtocol);
+        $crate::server::__wayland_server::delegate_dispatch!($ty: [
+            $crate::server::protocol::anodium_output::AnodiumOutput: ()
+        ] => $crate::server::AnodiumProtocol);
+        $crate::server::__wayland_server::delegate_dispatch!($ty: [
+            $crate::server::protocol::anodium_workspace::AnodiumWorkspace: ()
+        ] => $crate::server::AnodiumProtocol);
+    };
+}
@@ -1,82 +1,121 @@
-use std::{cell::RefCell, ops::Deref, rc::Rc};
-
-use wayland_server::{Filter, Main};
+use wayland_server::{backend::ObjectId, Dispatch, DisplayHandle, Resource};
 
 use super::{
-    protocol::{anodium_output::AnodiumOutput, anodium_workspace_manager::AnodiumWorkspaceManager},
+    protocol::{
+        anodium_output::{self, AnodiumOutput},
+        anodium_workspace::AnodiumWorkspace,
+        anodium_workspace_manager::AnodiumWorkspaceManager,
+    },
     workspace::AnodiumProtocolWorkspace,
+    OutputDescription, WorkspaceDescription,
 };
 
-#[derive(Default)]
-struct Inner {
+/// Output and its instances, one for every bound manager
+#[derive(Debug)]
+pub(super) struct AnodiumProtocolOutput {
     name: String,
     workspaces: Vec<AnodiumProtocolWorkspace>,
     known: Vec<AnodiumOutput>,
 }
 
-#[derive(Clone)]
-pub struct AnodiumProtocolOutput {
-    inner: Rc<RefCell<Inner>>,
-}
-
 impl AnodiumProtocolOutput {
-    pub(super) fn new() -> Self {
+    pub(super) fn new(description: OutputDescription) -> Self {
         Self {
-            inner: Default::default(),
+            name: description.name,
+            workspaces: description
+                .workspaces
+                .into_iter()
+                .map(AnodiumProtocolWorkspace::new)
+                .collect(),
+            known: Vec::new(),
         }
     }
 
-    pub(super) fn new_instance(&mut self, manager: &AnodiumWorkspaceManager) {
-        let mut inner = self.inner.borrow_mut();
+    pub(super) fn name(&self) -> &str {
+        &self.name
+    }
 
-        let output = manager.as_ref().client().and_then(|client| {
-            let output: Main<AnodiumOutput> = client.create_resource(1)?;
-            output.quick_assign(|_res, _, _| {});
-            output.assign_destructor(Filter::new({
-                let inner = self.inner.clone();
-                move |res: AnodiumOutput, _, _| {
-                    inner.borrow_mut().known.retain(|o| o != &res);
-                }
-            }));
+    pub(super) fn new_instance<D>(&mut self, dh: &DisplayHandle, manager: &AnodiumWorkspaceManager)
+    where
+        D: Dispatch<AnodiumOutput, ()> + Dispatch<AnodiumWorkspace, ()> + 'static,
+    {
+        let output = dh.get_client(manager.id()).and_then(|client| {
+            client.create_resource::<AnodiumOutput, (), D>(dh, manager.version(), ())
+        });
 
-            manager.output(output.deref());
+        let output = match output {
+            Ok(output) => output,
+            // The client is gone already
+            Err(_) => return,
+        };
 
-            output.name(inner.name.clone());
+        manager.output(&output);
+        output.name(self.name.clone());
 
-            for ws in inner.workspaces.iter_mut() {
-                ws.new_instance(&output);
-            }
+        for workspace in self.workspaces.iter_mut() {
+            workspace.new_instance::<D>(dh, &output);
+        }
 
-            Some(output.deref().clone())
-        });
+        self.known.push(output);
+    }
 
-        if let Some(output) = output {
-            inner.known.push(output);
+    /// Workspaces are matched with the previous ones by name
+    pub(super) fn set_workspaces<D>(
+        &mut self,
+        dh: &DisplayHandle,
+        workspaces: &[WorkspaceDescription],
+    ) where
+        D: Dispatch<AnodiumWorkspace, ()> + 'static,
+    {
+        let (kept, removed): (Vec<_>, Vec<_>) = std::mem::take(&mut self.workspaces)
+            .into_iter()
+            .partition(|known| workspaces.iter().any(|ws| ws.name == known.name()));
+
+        self.workspaces = kept;
+
+        for workspace in removed {
+            workspace.remove();
         }
-    }
 
-    pub fn new_workspace(&mut self) -> AnodiumProtocolWorkspace {
-        let mut inner = self.inner.borrow_mut();
+        for description in workspaces {
+            let known = self
+                .workspaces
+                .iter_mut()
+                .find(|known| known.name() == description.name);
+
+            match known {
+                Some(workspace) => workspace.set_active(description.active),
+                None => {
+                    let mut workspace = AnodiumProtocolWorkspace::new(description.clone());
 
-        let mut workspace = AnodiumProtocolWorkspace::new();
+                    for output in self.known.iter() {
+                        workspace.new_instance::<D>(dh, output);
+                    }
 
-        for res in inner.known.iter() {
-            workspace.new_instance(res);
+                    self.workspaces.push(workspace);
+                }
+            }
         }
+    }
 
-        inner.workspaces.push(workspace.clone());
+    /// Tell clients that the output and its workspaces are gone
+    pub(super) fn remove(self) {
+        for workspace in self.workspaces {
+            workspace.remove();
+        }
 
-        workspace
+        for output in self.known.iter() {
+            if output.version() >= anodium_output::EVT_REMOVED_SINCE {
+                output.removed();
+            }
+        }
     }
 
-    pub fn set_name<S: AsRef<str>>(&mut self, name: S) {
-        let mut inner = self.inner.borrow_mut();
-        let name = name.as_ref();
-
-        inner.name = name.to_owned();
+    pub(super) fn instance_destroyed(&mut self, id: &ObjectId) {
+        self.known.retain(|output| output.id() != *id);
 
-        for res in inner.known.iter() {
-            res.name(name.to_owned())
+        for workspace in self.workspaces.iter_mut() {
+            workspace.instance_destroyed(id);
         }
     }
 }
@@ -1,60 +1,84 @@
-use std::{cell::RefCell, ops::Deref, rc::Rc};
+use wayland_server::{backend::ObjectId, Dispatch, DisplayHandle, Resource};
 
-use wayland_server::{Filter, Main};
+use super::{
+    protocol::{
+        anodium_output::AnodiumOutput,
+        anodium_workspace::{self, AnodiumWorkspace},
+    },
+    WorkspaceDescription,
+};
 
-use super::protocol::{anodium_output::AnodiumOutput, anodium_workspace::AnodiumWorkspace};
-
-#[derive(Default)]
-struct Inner {
+/// Workspace and its instances, one for every instance of its output
+#[derive(Debug)]
+pub(super) struct AnodiumProtocolWorkspace {
     name: String,
+    active: bool,
     known: Vec<AnodiumWorkspace>,
 }
 
-#[derive(Clone)]
-pub struct AnodiumProtocolWorkspace {
-    inner: Rc<RefCell<Inner>>,
-}
-
 impl AnodiumProtocolWorkspace {
-    pub(super) fn new() -> Self {
+    pub(super) fn new(description: WorkspaceDescription) -> Self {
         Self {
-            inner: Default::default(),
+            name: description.name,
+            active: description.active,
+            known: Vec::new(),
         }
     }
 
-    pub(super) fn new_instance(&mut self, output: &AnodiumOutput) {
-        let mut inner = self.inner.borrow_mut();
+    pub(super) fn name(&self) -> &str {
+        &self.name
+    }
 
-        let output = output.as_ref().client().and_then(|client| {
-            let workspace: Main<AnodiumWorkspace> = client.create_resource(1)?;
-            workspace.quick_assign(|_res, _, _| {});
-            workspace.assign_destructor(Filter::new({
-                let inner = self.inner.clone();
-                move |res: AnodiumWorkspace, _, _| {
-                    inner.borrow_mut().known.retain(|w| w != &res);
-                }
-            }));
+    pub(super) fn new_instance<D>(&mut self, dh: &DisplayHandle, output: &AnodiumOutput)
+    where
+        D: Dispatch<AnodiumWorkspace, ()> + 'static,
+    {
+        let workspace = dh.get_client(output.id()).and_then(|client| {
+            client.create_resource::<AnodiumWorkspace, (), D>(dh, output.version(), ())
+        });
 
-            output.workspace(workspace.deref());
+        let workspace = match workspace {
+            Ok(workspace) => workspace,
+            // The client is gone already
+            Err(_) => return,
+        };
 
-            workspace.name(inner.name.clone());
+        output.workspace(&workspace);
+        workspace.name(self.name.clone());
+        send_active(&workspace, self.active);
 
-            Some(workspace.deref().clone())
-        });
+        self.known.push(workspace);
+    }
 
-        if let Some(output) = output {
-            inner.known.push(output);
+    pub(super) fn set_active(&mut self, active: bool) {
+        if self.active == active {
+            return;
         }
-    }
 
-    pub fn set_name<S: AsRef<str>>(&mut self, name: S) {
-        let mut inner = self.inner.borrow_mut();
-        let name = name.as_ref();
+        self.active = active;
 
-        inner.name = name.to_owned();
+        for workspace in self.known.iter() {
+            send_active(workspace, active);
+        }
+    }
 
-        for res in inner.known.iter() {
-            res.name(name.to_owned())
+    /// Tell clients that the workspace is gone
+    pub(super) fn remove(self) {
+        for workspace in self.known.iter() {
+            if workspace.version() >= anodium_workspace::EVT_REMOVED_SINCE {
+                workspace.removed();
+            }
         }
     }
+
+    pub(super) fn instance_destroyed(&mut self, id: &ObjectId) {
+        self.known.retain(|workspace| workspace.id() != *id);
+    }
+}
+
+/// Workspaces of version 1 are never told about their state
+fn send_active(workspace: &AnodiumWorkspace, active: bool) {
+    if workspace.version() >= anodium_workspace::EVT_ACTIVE_SINCE {
+        workspace.active(active as u32);
+    }
 }
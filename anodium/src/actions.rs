@@ -0,0 +1,201 @@
+use rhai::FnPtr;
+use slog_scope::error;
+use smithay::{
+    desktop::{Kind, Window, WindowSurfaceType},
+    utils::{Logical, Point, Size, SERIAL_COUNTER},
+};
+
+use crate::{
+    switcher::SwitchDirection,
+    tiling::{self, Direction, Orientation},
+    workspace::LayoutMode,
+    State,
+};
+
+/// Window to focus, relative to the currently focused one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusTarget {
+    /// Previously focused window
+    Next,
+    /// Least recently focused window
+    Previous,
+    /// Closest window in the given direction
+    Direction(Direction),
+}
+
+/// Something that can be bound to a key or a button
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Spawn a command using `sh -c`
+    Spawn(String),
+    /// Ask the focused window to close
+    Close,
+    /// Move focus to another window
+    Focus(FocusTarget),
+    /// Open the window switcher, or move its selection
+    Switcher(SwitchDirection),
+    /// Move focused window by given offset
+    Move(Point<i32, Logical>),
+    /// Grow focused floating window by given width and height, negative values shrink it
+    Resize(Point<i32, Logical>),
+    /// Swap the focused window with the tiled window in the given direction
+    Swap(Direction),
+    /// Orientation of the split the next tiled window is placed in
+    Split(Orientation),
+    /// Change the layout mode of the active workspace
+    Layout(LayoutMode),
+    /// Tile the focused window if it floats, and the other way around
+    ToggleFloating,
+    /// Switch to the workspace with given index on the active output
+    Workspace(usize),
+    /// Move the focused window to the workspace with given index
+    MoveToWorkspace(usize),
+    /// Switch the active keybinding mode
+    Mode(String),
+    /// Stop the compositor
+    Quit,
+    /// Run a config callback
+    Callback(FnPtr),
+}
+
+impl State {
+    /// Toplevel window that currently has keyboard focus
+    pub fn focused_window(&self) -> Option<Window> {
+        let surface = self.seat.get_keyboard()?.current_focus()?;
+
+        self.space
+            .window_for_surface(&surface, WindowSurfaceType::TOPLEVEL)
+            .cloned()
+    }
+
+    pub fn run_action(&mut self, action: Action) {
+        match action {
+            Action::Spawn(command) => {
+                if let Err(err) = std::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(&command)
+                    .spawn()
+                {
+                    error!("Failed to spawn `{}`: {}", command, err);
+                }
+            }
+            Action::Close => {
+                if let Some(window) = self.focused_window() {
+                    self.close_window(&window);
+                }
+            }
+            Action::Focus(FocusTarget::Direction(direction)) => {
+                self.focus_direction(direction);
+            }
+            Action::Focus(target) => {
+                let windows = self.focus_candidates();
+
+                if windows.is_empty() {
+                    return;
+                }
+
+                let focused = self
+                    .focused_window()
+                    .and_then(|focused| windows.iter().position(|w| *w == focused));
+
+                let id = match (focused, target) {
+                    (Some(id), FocusTarget::Next) => (id + 1) % windows.len(),
+                    (Some(id), FocusTarget::Previous) => (id + windows.len() - 1) % windows.len(),
+                    (None, _) | (_, FocusTarget::Direction(_)) => 0,
+                };
+
+                self.focus_window(&windows[id]);
+            }
+            Action::Switcher(direction) => {
+                self.switch_window(direction);
+            }
+            Action::Move(offset) => {
+                // Tiled, maximized and fullscreen windows are placed by us
+                let window = self
+                    .focused_window()
+                    .filter(|window| !self.is_tiled(window) && !self.has_fixed_geometry(window));
+
+                if let Some(window) = window {
+                    if let Some(location) = self.space.window_location(&window) {
+                        self.set_window_location(&window, location + offset);
+                    }
+                }
+            }
+            Action::Resize(delta) => {
+                if let Some(window) = self.focused_window() {
+                    self.resize_window_by(&window, delta);
+                }
+            }
+            Action::Swap(direction) => {
+                self.swap_direction(direction);
+            }
+            Action::Split(orientation) => {
+                self.set_split(orientation);
+            }
+            Action::Layout(mode) => {
+                self.set_layout_mode(mode);
+            }
+            Action::ToggleFloating => {
+                if let Some(window) = self.focused_window() {
+                    self.toggle_floating(&window);
+                }
+            }
+            Action::Workspace(index) => {
+                self.switch_workspace(index);
+            }
+            Action::MoveToWorkspace(index) => {
+                if let Some(window) = self.focused_window() {
+                    self.move_window_to_workspace(&window, index);
+
+                    // The window could have been hidden, so focus whatever is on top now
+                    if self.space.window_location(&window).is_none() {
+                        if let Some(top) = self.space.windows().last().cloned() {
+                            self.focus_window(&top);
+                        } else {
+                            let keyboard = self.seat.get_keyboard().unwrap();
+                            keyboard.set_focus(self, None, SERIAL_COUNTER.next_serial());
+                        }
+                    }
+                }
+            }
+            Action::Mode(mode) => {
+                self.keybindings.set_mode(&self._loop_handle, &mode);
+            }
+            Action::Quit => {
+                self.loop_signal.stop();
+            }
+            Action::Callback(callback) => {
+                self.config.call_fn(&callback);
+            }
+        }
+    }
+
+    /// Change the size of a floating window, within its size constraints
+    fn resize_window_by(&mut self, window: &Window, delta: Point<i32, Logical>) {
+        // Tiled, maximized and fullscreen windows get their size from the compositor
+        if self.is_tiled(window) || self.has_fixed_geometry(window) {
+            return;
+        }
+
+        let (min_size, max_size) = tiling::size_constraints(window);
+
+        let max_width = (max_size.w == 0).then(i32::max_value).unwrap_or(max_size.w);
+        let max_height = (max_size.h == 0).then(i32::max_value).unwrap_or(max_size.h);
+
+        let size = window.geometry().size;
+        let size = Size::from((
+            (size.w + delta.x).max(min_size.w.max(1)).min(max_width),
+            (size.h + delta.y).max(min_size.h.max(1)).min(max_height),
+        ));
+
+        self.configure_size(window, size);
+    }
+
+    pub fn close_window(&mut self, window: &Window) {
+        match window.toplevel() {
+            Kind::Xdg(toplevel) => toplevel.send_close(),
+            #[cfg(feature = "xwayland")]
+            Kind::X11(_) => crate::xwayland::close_window(self, window),
+        }
+    }
+}
@@ -0,0 +1,598 @@
+//! Window animations
+//!
+//! Animations only change how a window is drawn, the window itself is placed in the
+//! [`Space`](smithay::desktop::Space) at its final geometry right away. Animated windows are drawn
+//! as custom elements that report damage every frame, so outputs keep getting redrawn until all
+//! animations end.
+//!
+//! Closing windows, minimized windows and windows of a workspace that got hidden are gone from the
+//! space, they are drawn as [`Ghost`]s from the [`Snapshot`] of textures taken at their last frame.
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use smithay::{
+    backend::renderer::{
+        gles2::{Gles2Error, Gles2Frame, Gles2Renderer, Gles2Texture},
+        utils::RendererSurfaceStateUserData,
+        Frame as _,
+    },
+    desktop::{
+        space::{RenderElement, RenderZindex, SpaceOutputTuple},
+        Window,
+    },
+    utils::{Logical, Physical, Point, Rectangle, Scale, Size, Transform},
+    wayland::compositor::{with_surface_tree_upward, SubsurfaceCachedState, TraversalAction},
+};
+
+use crate::{data::window::WindowState, State};
+
+/// Size of opening and closing windows, relative to their real size
+const OPEN_SCALE: f64 = 0.9;
+
+/// Easing of the animation progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Curve {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Curve {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "linear" => Self::Linear,
+            "ease-in" => Self::EaseIn,
+            "ease-out" => Self::EaseOut,
+            "ease-in-out" => Self::EaseInOut,
+            _ => return None,
+        })
+    }
+
+    /// Eased progress, `t` goes from 0.0 to 1.0
+    fn apply(self, t: f64) -> f64 {
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t.powi(3),
+            Self::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Self::EaseInOut if t < 0.5 => 4.0 * t.powi(3),
+            Self::EaseInOut => 1.0 - (2.0 - 2.0 * t).powi(3) / 2.0,
+        }
+    }
+}
+
+/// What triggered the animation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationKind {
+    /// Window got mapped
+    Open,
+    /// Window got closed or minimized
+    Close,
+    /// Window got moved or resized by the layout, eg. tiling or maximizing
+    Move,
+    /// Windows of a workspace got shown or hidden
+    Workspace,
+}
+
+impl AnimationKind {
+    pub const ALL: [Self; 4] = [Self::Open, Self::Close, Self::Move, Self::Workspace];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "open" => Self::Open,
+            "close" => Self::Close,
+            "move" => Self::Move,
+            "workspace" => Self::Workspace,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Close => "close",
+            Self::Move => "move",
+            Self::Workspace => "workspace",
+        }
+    }
+}
+
+/// Duration and curve of one [`AnimationKind`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimationStyle {
+    /// Zero disables the animation
+    pub duration: Duration,
+    pub curve: Curve,
+}
+
+impl AnimationStyle {
+    pub fn default_for(kind: AnimationKind) -> Self {
+        let (duration, curve) = match kind {
+            AnimationKind::Open => (150, Curve::EaseOut),
+            AnimationKind::Close => (150, Curve::EaseIn),
+            AnimationKind::Move => (200, Curve::EaseInOut),
+            AnimationKind::Workspace => (250, Curve::EaseOut),
+        };
+
+        Self {
+            duration: Duration::from_millis(duration),
+            curve,
+        }
+    }
+}
+
+/// Where and how opaque a window is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame {
+    /// Window geometry
+    pub rect: Rectangle<f64, Logical>,
+    pub alpha: f32,
+}
+
+impl Frame {
+    pub fn new(rect: Rectangle<i32, Logical>) -> Self {
+        Self {
+            rect: rect.to_f64(),
+            alpha: 1.0,
+        }
+    }
+
+    fn lerp(self, to: Self, t: f64) -> Self {
+        let lerp = |a: f64, b: f64| a + (b - a) * t;
+
+        Self {
+            rect: Rectangle::from_loc_and_size(
+                (
+                    lerp(self.rect.loc.x, to.rect.loc.x),
+                    lerp(self.rect.loc.y, to.rect.loc.y),
+                ),
+                (
+                    lerp(self.rect.size.w, to.rect.size.w),
+                    lerp(self.rect.size.h, to.rect.size.h),
+                ),
+            ),
+            alpha: lerp(self.alpha as f64, to.alpha as f64) as f32,
+        }
+    }
+
+    /// Scaled around its center
+    fn scaled(self, factor: f64) -> Self {
+        let size: Size<f64, Logical> =
+            (self.rect.size.w * factor, self.rect.size.h * factor).into();
+        let loc = (
+            self.rect.loc.x + (self.rect.size.w - size.w) / 2.0,
+            self.rect.loc.y + (self.rect.size.h - size.h) / 2.0,
+        );
+
+        Self {
+            rect: Rectangle::from_loc_and_size(loc, size),
+            alpha: self.alpha,
+        }
+    }
+
+    fn translated(self, offset: Point<f64, Logical>) -> Self {
+        Self {
+            rect: Rectangle::from_loc_and_size(self.rect.loc + offset, self.rect.size),
+            alpha: self.alpha,
+        }
+    }
+
+    fn with_alpha(self, alpha: f32) -> Self {
+        Self { alpha, ..self }
+    }
+
+    /// Scale of content of the given size drawn in this frame
+    pub fn scale_of(&self, size: Size<f64, Logical>) -> Scale<f64> {
+        let ratio = |drawn: f64, real: f64| if real > 0.0 { drawn / real } else { 1.0 };
+
+        Scale {
+            x: ratio(self.rect.size.w, size.w),
+            y: ratio(self.rect.size.h, size.h),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Animation {
+    style: AnimationStyle,
+    start: Instant,
+    from: Frame,
+    /// `None` follows the real geometry of the window, which can change while animating
+    to: Option<Frame>,
+}
+
+impl Animation {
+    fn new(style: AnimationStyle, from: Frame, to: Option<Frame>) -> Self {
+        Self {
+            style,
+            start: Instant::now(),
+            from,
+            to,
+        }
+    }
+
+    pub fn is_done(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.start) >= self.style.duration
+    }
+
+    /// Frame drawn at `now`, `real` is where the window actually is
+    pub fn frame(&self, now: Instant, real: Frame) -> Frame {
+        let elapsed = now.saturating_duration_since(self.start).as_secs_f64();
+        let t = (elapsed / self.style.duration.as_secs_f64()).min(1.0);
+
+        self.from
+            .lerp(self.to.unwrap_or(real), self.style.curve.apply(t))
+    }
+}
+
+/// Single surface of a [`Snapshot`]
+#[derive(Clone)]
+struct SnapshotSurface {
+    texture: Gles2Texture,
+    /// Relative to the window geometry
+    location: Point<f64, Logical>,
+    size: Size<i32, Logical>,
+    buffer_scale: i32,
+    transform: Transform,
+}
+
+/// Textures of the surfaces of a window, they stay alive once the client destroys its buffers
+#[derive(Clone)]
+pub struct Snapshot {
+    surfaces: Vec<SnapshotSurface>,
+    /// Size of the window geometry the surfaces belong to
+    geometry_size: Size<f64, Logical>,
+}
+
+impl fmt::Debug for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("surfaces", &self.surfaces.len())
+            .field("geometry_size", &self.geometry_size)
+            .finish()
+    }
+}
+
+impl Snapshot {
+    /// Take textures of the window imported by the last frame, popups are left out
+    pub fn capture(renderer: &Gles2Renderer, window: &Window) -> Self {
+        let geometry = window.geometry();
+        let mut surfaces = Vec::new();
+
+        with_surface_tree_upward(
+            window.toplevel().wl_surface(),
+            Point::<f64, Logical>::from((-geometry.loc.x as f64, -geometry.loc.y as f64)),
+            |_, states, location| {
+                let mut location = *location;
+
+                if states.role == Some("subsurface") {
+                    let current = states.cached_state.current::<SubsurfaceCachedState>();
+                    location += current.location.to_f64();
+                }
+
+                TraversalAction::DoChildren(location)
+            },
+            |_, states, location| {
+                let mut location = *location;
+
+                if states.role == Some("subsurface") {
+                    let current = states.cached_state.current::<SubsurfaceCachedState>();
+                    location += current.location.to_f64();
+                }
+
+                let data = match states.data_map.get::<RendererSurfaceStateUserData>() {
+                    Some(data) => data.borrow(),
+                    None => return,
+                };
+
+                if let Some(texture) = data.texture(renderer) {
+                    surfaces.push(SnapshotSurface {
+                        texture: texture.clone(),
+                        location,
+                        size: data.surface_size().unwrap_or_default(),
+                        buffer_scale: data.buffer_scale(),
+                        transform: data.buffer_transform(),
+                    });
+                }
+            },
+            |_, _, _| true,
+        );
+
+        Self {
+            surfaces,
+            geometry_size: geometry.size.to_f64(),
+        }
+    }
+}
+
+/// Window that is not in the space anymore, drawn from its snapshot until the animation ends
+#[derive(Debug)]
+pub struct Ghost {
+    /// Id of the window element it replaces, see [`RenderElement::id`]
+    id: usize,
+    snapshot: Snapshot,
+    animation: Animation,
+}
+
+impl Ghost {
+    fn element(&self, now: Instant) -> GhostElement {
+        let to = self.animation.to.unwrap_or(self.animation.from);
+
+        GhostElement {
+            id: self.id,
+            snapshot: self.snapshot.clone(),
+            frame: self.animation.frame(now, to),
+        }
+    }
+}
+
+pub struct GhostElement {
+    id: usize,
+    snapshot: Snapshot,
+    frame: Frame,
+}
+
+impl GhostElement {
+    fn scale(&self) -> Scale<f64> {
+        self.frame.scale_of(self.snapshot.geometry_size)
+    }
+
+    /// Area of all surfaces, relative to the drawn window geometry
+    fn bbox(&self) -> Rectangle<f64, Logical> {
+        let scale = self.scale();
+
+        self.snapshot
+            .surfaces
+            .iter()
+            .map(|surface| {
+                Rectangle::from_loc_and_size(
+                    (surface.location.x * scale.x, surface.location.y * scale.y),
+                    (
+                        surface.size.w as f64 * scale.x,
+                        surface.size.h as f64 * scale.y,
+                    ),
+                )
+            })
+            .reduce(|a, b| a.merge(b))
+            .unwrap_or_else(|| Rectangle::from_loc_and_size((0.0, 0.0), (0.0, 0.0)))
+    }
+}
+
+impl RenderElement<Gles2Renderer> for GhostElement {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn location(&self, scale: impl Into<Scale<f64>>) -> Point<f64, Physical> {
+        self.frame.rect.loc.to_physical(scale)
+    }
+
+    fn geometry(&self, scale: impl Into<Scale<f64>>) -> Rectangle<i32, Physical> {
+        let mut bbox = self.bbox();
+        bbox.loc += self.frame.rect.loc;
+        bbox.to_physical(scale).to_i32_up()
+    }
+
+    fn accumulated_damage(
+        &self,
+        scale: impl Into<Scale<f64>>,
+        _: Option<SpaceOutputTuple<'_, '_>>,
+    ) -> Vec<Rectangle<i32, Physical>> {
+        // Redrawn every frame while animating
+        vec![self.bbox().to_physical(scale).to_i32_up()]
+    }
+
+    fn opaque_regions(
+        &self,
+        _scale: impl Into<Scale<f64>>,
+    ) -> Option<Vec<Rectangle<i32, Physical>>> {
+        None
+    }
+
+    fn z_index(&self) -> u8 {
+        RenderZindex::Shell as u8
+    }
+
+    fn draw(
+        &self,
+        _renderer: &mut Gles2Renderer,
+        frame: &mut Gles2Frame,
+        scale: impl Into<Scale<f64>>,
+        location: Point<f64, Physical>,
+        _damage: &[Rectangle<i32, Physical>],
+        _log: &slog::Logger,
+    ) -> Result<(), Gles2Error> {
+        let output_scale = scale.into();
+        let window_scale = self.scale();
+        let scale = Scale {
+            x: output_scale.x * window_scale.x,
+            y: output_scale.y * window_scale.y,
+        };
+
+        for surface in self.snapshot.surfaces.iter() {
+            let offset = Point::<f64, Logical>::from((
+                surface.location.x * window_scale.x,
+                surface.location.y * window_scale.y,
+            ))
+            .to_physical(output_scale);
+
+            let size = surface.size.to_f64().to_physical(scale).to_i32_round();
+
+            frame.render_texture_at(
+                &surface.texture,
+                (location + offset).to_i32_round(),
+                surface.buffer_scale,
+                scale,
+                surface.transform,
+                &[Rectangle::from_loc_and_size((0, 0), size)],
+                self.frame.alpha,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl State {
+    fn animation_style(&self, kind: AnimationKind) -> Option<AnimationStyle> {
+        let animations = self.config.animations();
+
+        if !animations.enabled() {
+            return None;
+        }
+
+        Some(animations.style(kind)).filter(|style| !style.duration.is_zero())
+    }
+
+    /// Where the window is drawn right now, `None` if it was not drawn yet
+    fn current_frame(&self, window: &Window) -> Option<Frame> {
+        let window_state = WindowState::for_window(window);
+        let drawn = window_state.drawn_frame()?;
+
+        let animation = window_state.animation();
+        if matches!(animation, Some(animation) if !animation.is_done(Instant::now())) {
+            return Some(drawn);
+        }
+
+        let loc = self.space.window_location(window)?;
+        Some(Frame::new(Rectangle::from_loc_and_size(
+            loc,
+            window.geometry().size,
+        )))
+    }
+
+    /// Fade and grow the window into its place
+    pub fn animate_open(&mut self, window: &Window) {
+        let style = match self.animation_style(AnimationKind::Open) {
+            Some(style) => style,
+            None => return,
+        };
+
+        let loc = match self.space.window_location(window) {
+            Some(loc) => loc,
+            None => return,
+        };
+
+        let from = Frame::new(Rectangle::from_loc_and_size(loc, window.geometry().size))
+            .scaled(OPEN_SCALE)
+            .with_alpha(0.0);
+
+        WindowState::for_window(window).set_animation(Some(Animation::new(style, from, None)));
+    }
+
+    /// Fade and shrink the window out, it has to be drawn at least once before
+    ///
+    /// Should be called before the window gets unmapped or forgotten.
+    pub fn animate_close(&mut self, window: &Window) {
+        let window_state = WindowState::for_window(window);
+        let style = self.animation_style(AnimationKind::Close);
+
+        let (style, from, snapshot) =
+            match (style, window_state.drawn_frame(), window_state.snapshot()) {
+                (Some(style), Some(from), Some(snapshot)) => (style, from, snapshot),
+                _ => return,
+            };
+
+        window_state.set_animation(None);
+
+        let to = from.scaled(OPEN_SCALE).with_alpha(0.0);
+        self.push_ghost(window, snapshot, Animation::new(style, from, Some(to)));
+    }
+
+    /// Map the window at `location`, the way to `rect` is animated once the window got drawn
+    ///
+    /// `rect` is the geometry the window is going to have, its size could be still pending.
+    pub fn map_window_animated(
+        &mut self,
+        window: &Window,
+        rect: Rectangle<i32, Logical>,
+        activate: bool,
+    ) {
+        let from = self.current_frame(window);
+
+        self.space.map_window(window, rect.loc, None, activate);
+
+        let style = match self.animation_style(AnimationKind::Move) {
+            Some(style) => style,
+            None => return,
+        };
+
+        if let Some(from) = from.filter(|from| from.rect != rect.to_f64()) {
+            WindowState::for_window(window).set_animation(Some(Animation::new(style, from, None)));
+        }
+    }
+
+    /// Slide windows of a workspace that got hidden out, by `offset`
+    ///
+    /// Should be called before the windows get unmapped.
+    pub fn animate_workspace_out(&mut self, windows: &[Window], offset: Point<f64, Logical>) {
+        let style = match self.animation_style(AnimationKind::Workspace) {
+            Some(style) => style,
+            None => return,
+        };
+
+        for window in windows {
+            let from = self.current_frame(window);
+            let snapshot = WindowState::for_window(window).snapshot();
+
+            if let (Some(from), Some(snapshot)) = (from, snapshot) {
+                let to = from.translated(offset);
+                self.push_ghost(window, snapshot, Animation::new(style, from, Some(to)));
+            }
+        }
+    }
+
+    /// Slide windows of a workspace that got shown in, from `offset`
+    pub fn animate_workspace_in(&mut self, windows: &[Window], offset: Point<f64, Logical>) {
+        let style = match self.animation_style(AnimationKind::Workspace) {
+            Some(style) => style,
+            None => return,
+        };
+
+        for window in windows {
+            let loc = match self.space.window_location(window) {
+                Some(loc) => loc,
+                None => continue,
+            };
+
+            let from = Frame::new(Rectangle::from_loc_and_size(loc, window.geometry().size))
+                .translated(offset);
+
+            WindowState::for_window(window).set_animation(Some(Animation::new(style, from, None)));
+        }
+    }
+
+    fn push_ghost(&mut self, window: &Window, snapshot: Snapshot, animation: Animation) {
+        // Kept apart from the ids of windows and their decorations
+        let id = WindowState::for_window(window).id() | (1 << (usize::BITS - 3));
+
+        self.ghosts.retain(|ghost| ghost.id != id);
+        self.ghosts.push(Ghost {
+            id,
+            snapshot,
+            animation,
+        });
+    }
+
+    /// Elements of ghosts that are still animating, finished ones are dropped
+    pub fn ghost_elements(&mut self, now: Instant) -> Vec<GhostElement> {
+        self.ghosts.retain(|ghost| !ghost.animation.is_done(now));
+        self.ghosts.iter().map(|ghost| ghost.element(now)).collect()
+    }
+
+    /// Keep textures of drawn windows around, they are needed once the window disappears
+    pub fn capture_snapshots(&self, renderer: &Gles2Renderer, windows: &[Window]) {
+        if self.animation_style(AnimationKind::Close).is_none()
+            && self.animation_style(AnimationKind::Workspace).is_none()
+        {
+            return;
+        }
+
+        for window in windows {
+            WindowState::for_window(window).set_snapshot(Some(Snapshot::capture(renderer, window)));
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use rhai::{Engine, EvalAltResult};
+
+use crate::{
+    actions::{Action, FocusTarget},
+    switcher::SwitchDirection,
+    tiling::{Direction, Orientation},
+    workspace::LayoutMode,
+};
+
+fn parse_direction(direction: &str) -> Result<Direction, Box<EvalAltResult>> {
+    Direction::from_name(direction)
+        .ok_or_else(|| format!("Unknown direction: {}", direction).into())
+}
+
+/// Workspaces are numbered from 1 in the config
+fn workspace_index(index: i64) -> Result<usize, Box<EvalAltResult>> {
+    if index < 1 {
+        return Err(format!("Invalid workspace: {}", index).into());
+    }
+
+    Ok(index as usize - 1)
+}
+
+/// `Action` script global, constructs actions that can be bound to keys
+#[derive(Debug, Clone)]
+pub struct Actions;
+
+impl Actions {
+    fn exec(&mut self, command: &str) -> Action {
+        Action::Spawn(command.to_string())
+    }
+
+    fn close(&mut self) -> Action {
+        Action::Close
+    }
+
+    fn focus(&mut self, target: &str) -> Result<Action, Box<EvalAltResult>> {
+        let target = match target {
+            "next" => FocusTarget::Next,
+            "previous" => FocusTarget::Previous,
+            other => FocusTarget::Direction(parse_direction(other)?),
+        };
+
+        Ok(Action::Focus(target))
+    }
+
+    fn switcher(&mut self, direction: &str) -> Result<Action, Box<EvalAltResult>> {
+        let direction = match direction {
+            "next" => SwitchDirection::Forward,
+            "previous" => SwitchDirection::Backward,
+            other => return Err(format!("Unknown switcher direction: {}", other).into()),
+        };
+
+        Ok(Action::Switcher(direction))
+    }
+
+    fn move_window(&mut self, x: i64, y: i64) -> Action {
+        Action::Move((x as i32, y as i32).into())
+    }
+
+    fn resize(&mut self, dx: i64, dy: i64) -> Action {
+        Action::Resize((dx as i32, dy as i32).into())
+    }
+
+    fn swap(&mut self, direction: &str) -> Result<Action, Box<EvalAltResult>> {
+        Ok(Action::Swap(parse_direction(direction)?))
+    }
+
+    fn split(&mut self, orientation: &str) -> Result<Action, Box<EvalAltResult>> {
+        let orientation = match orientation {
+            "horizontal" => Orientation::Horizontal,
+            "vertical" => Orientation::Vertical,
+            other => return Err(format!("Unknown split orientation: {}", other).into()),
+        };
+
+        Ok(Action::Split(orientation))
+    }
+
+    fn layout(&mut self, mode: &str) -> Result<Action, Box<EvalAltResult>> {
+        let mode = match mode {
+            "floating" => LayoutMode::Floating,
+            "tiling" => LayoutMode::Tiling,
+            "universal" => LayoutMode::Universal,
+            other => return Err(format!("Unknown layout: {}", other).into()),
+        };
+
+        Ok(Action::Layout(mode))
+    }
+
+    fn toggle_floating(&mut self) -> Action {
+        Action::ToggleFloating
+    }
+
+    fn workspace(&mut self, index: i64) -> Result<Action, Box<EvalAltResult>> {
+        Ok(Action::Workspace(workspace_index(index)?))
+    }
+
+    fn move_to_workspace(&mut self, index: i64) -> Result<Action, Box<EvalAltResult>> {
+        Ok(Action::MoveToWorkspace(workspace_index(index)?))
+    }
+
+    fn mode(&mut self, name: &str) -> Action {
+        Action::Mode(name.to_string())
+    }
+
+    fn quit(&mut self) -> Action {
+        Action::Quit
+    }
+}
+
+pub fn register(engine: &mut Engine) {
+    engine
+        .register_type_with_name::<Action>("Action")
+        .register_type_with_name::<Actions>("Actions")
+        .register_fn("exec", Actions::exec)
+        .register_fn("close", Actions::close)
+        .register_fn("focus", Actions::focus)
+        .register_fn("switcher", Actions::switcher)
+        .register_fn("move", Actions::move_window)
+        .register_fn("resize", Actions::resize)
+        .register_fn("swap", Actions::swap)
+        .register_fn("split", Actions::split)
+        .register_fn("layout", Actions::layout)
+        .register_fn("toggle_floating", Actions::toggle_floating)
+        .register_fn("workspace", Actions::workspace)
+        .register_fn("move_to_workspace", Actions::move_to_workspace)
+        .register_fn("mode", Actions::mode)
+        .register_fn("quit", Actions::quit);
+}
@@ -0,0 +1,111 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+use rhai::{Engine, EvalAltResult, ImmutableString, Map};
+
+use crate::animation::{AnimationKind, AnimationStyle, Curve};
+
+fn style_from_map(
+    kind: AnimationKind,
+    map: Map,
+    mut style: AnimationStyle,
+) -> Result<AnimationStyle, Box<EvalAltResult>> {
+    for key in map.keys() {
+        if key != "duration" && key != "curve" {
+            return Err(format!("Unknown animation key `{}`", key).into());
+        }
+    }
+
+    if let Some(duration) = map.get("duration") {
+        let duration = duration.as_int().map_err(|ty| {
+            format!(
+                "Animation `{}` duration should be an int, got {}",
+                kind.name(),
+                ty
+            )
+        })?;
+
+        if duration < 0 {
+            return Err(format!("Animation `{}` duration can not be negative", kind.name()).into());
+        }
+
+        style.duration = Duration::from_millis(duration as u64);
+    }
+
+    if let Some(curve) = map.get("curve") {
+        let name = curve.clone().into_string().map_err(|ty| {
+            format!(
+                "Animation `{}` curve should be a string, got {}",
+                kind.name(),
+                ty
+            )
+        })?;
+
+        style.curve = Curve::from_name(&name).ok_or_else(|| format!("Unknown curve: {}", name))?;
+    }
+
+    Ok(style)
+}
+
+/// `Animations` script global
+///
+/// ```rhai
+/// Animations.enabled = true;
+///
+/// // One of "open", "close", "move", "workspace"
+/// // `duration` is in milliseconds, 0 disables the animation,
+/// // `curve` is one of "linear", "ease-in", "ease-out", "ease-in-out"
+/// Animations.set("open", #{ duration: 150, curve: "ease-out" });
+/// Animations.set("workspace", #{ duration: 0 });
+/// ```
+#[derive(Debug, Clone)]
+pub struct Animations {
+    enabled: Rc<Cell<bool>>,
+    styles: Rc<Cell<[AnimationStyle; 4]>>,
+}
+
+impl Default for Animations {
+    fn default() -> Self {
+        Self {
+            enabled: Rc::new(Cell::new(true)),
+            styles: Rc::new(Cell::new(
+                AnimationKind::ALL.map(AnimationStyle::default_for),
+            )),
+        }
+    }
+}
+
+impl Animations {
+    pub fn enabled(&self) -> bool {
+        self.enabled.get()
+    }
+
+    pub fn style(&self, kind: AnimationKind) -> AnimationStyle {
+        self.styles.get()[kind as usize]
+    }
+
+    fn get_enabled(&mut self) -> bool {
+        self.enabled.get()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled.set(enabled);
+    }
+
+    fn set(&mut self, kind: ImmutableString, style: Map) -> Result<(), Box<EvalAltResult>> {
+        let kind = AnimationKind::from_name(&kind)
+            .ok_or_else(|| format!("Unknown animation: {}", kind))?;
+
+        let mut styles = self.styles.get();
+        styles[kind as usize] = style_from_map(kind, style, styles[kind as usize])?;
+        self.styles.set(styles);
+
+        Ok(())
+    }
+}
+
+pub fn register(engine: &mut Engine) {
+    engine
+        .register_type_with_name::<Animations>("Animations")
+        .register_get_set("enabled", Animations::get_enabled, Animations::set_enabled)
+        .register_fn("set", Animations::set);
+}
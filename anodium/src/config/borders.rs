@@ -0,0 +1,95 @@
+use std::{cell::Cell, rc::Rc};
+
+use rhai::{Engine, EvalAltResult, ImmutableString};
+
+use super::decorations::{format_color, parse_color};
+use crate::decorations::BorderTheme;
+
+/// `Borders` script global, border drawn around every window
+///
+/// ```rhai
+/// Borders.width = 2;
+/// Borders.corner_radius = 0;
+/// Borders.focused_color = "#1a5fcd";
+/// Borders.unfocused_color = "#3b3b3b";
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Borders {
+    theme: Rc<Cell<BorderTheme>>,
+}
+
+impl Borders {
+    pub fn theme(&self) -> BorderTheme {
+        self.theme.get()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut BorderTheme)) {
+        let mut theme = self.theme.get();
+        f(&mut theme);
+        self.theme.set(theme);
+    }
+
+    fn get_color(&mut self, field: fn(&BorderTheme) -> [f32; 4]) -> ImmutableString {
+        format_color(field(&self.theme.get())).into()
+    }
+
+    fn set_color(
+        &mut self,
+        color: ImmutableString,
+        field: fn(&mut BorderTheme) -> &mut [f32; 4],
+    ) -> Result<(), Box<EvalAltResult>> {
+        let color = parse_color(&color)?;
+        self.update(|theme| *field(theme) = color);
+
+        Ok(())
+    }
+
+    fn get_width(&mut self) -> i64 {
+        self.theme.get().width as i64
+    }
+
+    fn set_width(&mut self, width: i64) -> Result<(), Box<EvalAltResult>> {
+        if !(0..=64).contains(&width) {
+            return Err("`width` should be between 0 and 64".into());
+        }
+
+        self.update(|theme| theme.width = width as i32);
+
+        Ok(())
+    }
+
+    fn get_corner_radius(&mut self) -> i64 {
+        self.theme.get().corner_radius as i64
+    }
+
+    fn set_corner_radius(&mut self, radius: i64) -> Result<(), Box<EvalAltResult>> {
+        if !(0..=256).contains(&radius) {
+            return Err("`corner_radius` should be between 0 and 256".into());
+        }
+
+        self.update(|theme| theme.corner_radius = radius as i32);
+
+        Ok(())
+    }
+}
+
+pub fn register(engine: &mut Engine) {
+    engine
+        .register_type_with_name::<Borders>("Borders")
+        .register_get_set("width", Borders::get_width, Borders::set_width)
+        .register_get_set(
+            "corner_radius",
+            Borders::get_corner_radius,
+            Borders::set_corner_radius,
+        )
+        .register_get_set(
+            "focused_color",
+            |b: &mut Borders| b.get_color(|t| t.focused_color),
+            |b: &mut Borders, c: ImmutableString| b.set_color(c, |t| &mut t.focused_color),
+        )
+        .register_get_set(
+            "unfocused_color",
+            |b: &mut Borders| b.get_color(|t| t.unfocused_color),
+            |b: &mut Borders, c: ImmutableString| b.set_color(c, |t| &mut t.unfocused_color),
+        );
+}
@@ -0,0 +1,155 @@
+use std::{cell::Cell, rc::Rc};
+
+use rhai::{Engine, EvalAltResult, ImmutableString};
+
+use crate::decorations::DecorationTheme;
+
+/// Parse `#rrggbb` or `#rrggbbaa`
+pub fn parse_color(color: &str) -> Result<[f32; 4], Box<EvalAltResult>> {
+    let invalid = || format!("Invalid color: {}, expected #rrggbb or #rrggbbaa", color);
+
+    let hex = color.strip_prefix('#').ok_or_else(invalid)?;
+
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return Err(invalid().into());
+    }
+
+    let mut rgba = [1.0; 4];
+
+    for (id, channel) in rgba.iter_mut().enumerate().take(hex.len() / 2) {
+        let value = u8::from_str_radix(&hex[id * 2..id * 2 + 2], 16).map_err(|_| invalid())?;
+        *channel = value as f32 / 255.0;
+    }
+
+    Ok(rgba)
+}
+
+pub fn format_color(color: [f32; 4]) -> String {
+    let [r, g, b, a] = color.map(|channel| (channel * 255.0).round() as u8);
+    format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+}
+
+/// `Decorations` script global, titlebars and borders drawn for clients that don't draw their own
+///
+/// ```rhai
+/// Decorations.server_side = true;
+/// Decorations.titlebar_height = 24;
+/// Decorations.border_width = 1;
+/// Decorations.focused_color = "#1a5fcd";
+/// Decorations.unfocused_color = "#3b3b3b";
+/// Decorations.close_color = "#e06c75";
+/// Decorations.maximize_color = "#98c379";
+/// Decorations.minimize_color = "#e5c07b";
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Decorations {
+    theme: Rc<Cell<DecorationTheme>>,
+}
+
+impl Decorations {
+    pub fn theme(&self) -> DecorationTheme {
+        self.theme.get()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut DecorationTheme)) {
+        let mut theme = self.theme.get();
+        f(&mut theme);
+        self.theme.set(theme);
+    }
+
+    fn get_color(&mut self, field: fn(&DecorationTheme) -> [f32; 4]) -> ImmutableString {
+        format_color(field(&self.theme.get())).into()
+    }
+
+    fn set_color(
+        &mut self,
+        color: ImmutableString,
+        field: fn(&mut DecorationTheme) -> &mut [f32; 4],
+    ) -> Result<(), Box<EvalAltResult>> {
+        let color = parse_color(&color)?;
+        self.update(|theme| *field(theme) = color);
+
+        Ok(())
+    }
+
+    fn get_server_side(&mut self) -> bool {
+        self.theme.get().server_side
+    }
+
+    fn set_server_side(&mut self, server_side: bool) {
+        self.update(|theme| theme.server_side = server_side);
+    }
+
+    fn get_titlebar_height(&mut self) -> i64 {
+        self.theme.get().titlebar_height as i64
+    }
+
+    fn set_titlebar_height(&mut self, height: i64) -> Result<(), Box<EvalAltResult>> {
+        if !(8..=256).contains(&height) {
+            return Err("`titlebar_height` should be between 8 and 256".into());
+        }
+
+        self.update(|theme| theme.titlebar_height = height as i32);
+
+        Ok(())
+    }
+
+    fn get_border_width(&mut self) -> i64 {
+        self.theme.get().border_width as i64
+    }
+
+    fn set_border_width(&mut self, width: i64) -> Result<(), Box<EvalAltResult>> {
+        if !(0..=64).contains(&width) {
+            return Err("`border_width` should be between 0 and 64".into());
+        }
+
+        self.update(|theme| theme.border_width = width as i32);
+
+        Ok(())
+    }
+}
+
+pub fn register(engine: &mut Engine) {
+    engine
+        .register_type_with_name::<Decorations>("Decorations")
+        .register_get_set(
+            "server_side",
+            Decorations::get_server_side,
+            Decorations::set_server_side,
+        )
+        .register_get_set(
+            "titlebar_height",
+            Decorations::get_titlebar_height,
+            Decorations::set_titlebar_height,
+        )
+        .register_get_set(
+            "border_width",
+            Decorations::get_border_width,
+            Decorations::set_border_width,
+        )
+        .register_get_set(
+            "focused_color",
+            |d: &mut Decorations| d.get_color(|t| t.focused_color),
+            |d: &mut Decorations, c: ImmutableString| d.set_color(c, |t| &mut t.focused_color),
+        )
+        .register_get_set(
+            "unfocused_color",
+            |d: &mut Decorations| d.get_color(|t| t.unfocused_color),
+            |d: &mut Decorations, c: ImmutableString| d.set_color(c, |t| &mut t.unfocused_color),
+        )
+        .register_get_set(
+            "close_color",
+            |d: &mut Decorations| d.get_color(|t| t.close_color),
+            |d: &mut Decorations, c: ImmutableString| d.set_color(c, |t| &mut t.close_color),
+        )
+        .register_get_set(
+            "maximize_color",
+            |d: &mut Decorations| d.get_color(|t| t.maximize_color),
+            |d: &mut Decorations, c: ImmutableString| d.set_color(c, |t| &mut t.maximize_color),
+        )
+        .register_get_set(
+            "minimize_color",
+            |d: &mut Decorations| d.get_color(|t| t.minimize_color),
+            |d: &mut Decorations, c: ImmutableString| d.set_color(c, |t| &mut t.minimize_color),
+        );
+}
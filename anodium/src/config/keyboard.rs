@@ -0,0 +1,333 @@
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult, FnPtr, Map};
+use xkbcommon::xkb;
+
+use crate::{
+    actions::Action,
+    keybindings::{KeyCombo, KeyModifiers, Keybinding, KeybindingTable, Trigger, DEFAULT_MODE},
+};
+
+pub fn parse_modifiers(modifiers: Array) -> Result<KeyModifiers, Box<EvalAltResult>> {
+    modifiers
+        .into_iter()
+        .try_fold(KeyModifiers::empty(), |acc, m| {
+            let name = m
+                .into_string()
+                .map_err(|ty| format!("Modifier should be a string, got {}", ty))?;
+
+            let modifier = KeyModifiers::from_name(&name)
+                .ok_or_else(|| format!("Unknown modifier: {}", name))?;
+
+            Ok(acc | modifier)
+        })
+}
+
+/// Accepts either an `Action` or a script callback
+pub fn parse_action(action: Dynamic) -> Result<Action, Box<EvalAltResult>> {
+    if action.is::<FnPtr>() {
+        Ok(Action::Callback(action.cast()))
+    } else if action.is::<Action>() {
+        Ok(action.cast())
+    } else {
+        Err(format!(
+            "Expected an Action or a function, got {}",
+            action.type_name()
+        )
+        .into())
+    }
+}
+
+fn parse_key(key: &str) -> Result<u32, Box<EvalAltResult>> {
+    let keysym = xkb::keysym_from_name(key, xkb::KEYSYM_CASE_INSENSITIVE);
+
+    if keysym == xkb::KEY_NoSymbol {
+        Err(format!("Unknown key: {}", key).into())
+    } else {
+        Ok(keysym)
+    }
+}
+
+/// Parses chords like `"alt+space w v"`
+fn parse_chord(chord: &str) -> Result<Vec<KeyCombo>, Box<EvalAltResult>> {
+    let keys = chord
+        .split_whitespace()
+        .map(|step| {
+            let mut parts: Vec<&str> = step.split('+').collect();
+            let key = parts.pop().unwrap_or_default();
+
+            let modifiers = parts
+                .into_iter()
+                .try_fold(KeyModifiers::empty(), |acc, m| {
+                    let modifier = KeyModifiers::from_name(m)
+                        .ok_or_else(|| format!("Unknown modifier: {}", m))?;
+                    Ok::<_, Box<EvalAltResult>>(acc | modifier)
+                })?;
+
+            Ok(KeyCombo {
+                modifiers,
+                keysym: parse_key(key)?,
+            })
+        })
+        .collect::<Result<Vec<_>, Box<EvalAltResult>>>()?;
+
+    if keys.is_empty() {
+        return Err("Chord should not be empty".into());
+    }
+
+    Ok(keys)
+}
+
+fn parse_options(options: Map) -> Result<(Trigger, bool), Box<EvalAltResult>> {
+    let mut trigger = Trigger::Press;
+    let mut repeat = false;
+
+    for (name, value) in options {
+        match name.as_str() {
+            "trigger" => {
+                let value = value
+                    .into_string()
+                    .map_err(|ty| format!("`trigger` should be a string, got {}", ty))?;
+
+                trigger = match value.as_str() {
+                    "press" => Trigger::Press,
+                    "release" => Trigger::Release,
+                    other => return Err(format!("Unknown trigger: {}", other).into()),
+                };
+            }
+            "repeat" => {
+                repeat = value
+                    .as_bool()
+                    .map_err(|ty| format!("`repeat` should be a bool, got {}", ty))?;
+            }
+            other => return Err(format!("Unknown keybind option: {}", other).into()),
+        }
+    }
+
+    // The key is already up once a release binding runs, there is nothing to repeat
+    if trigger == Trigger::Release && repeat {
+        return Err("`repeat` can not be used with the release trigger".into());
+    }
+
+    Ok((trigger, repeat))
+}
+
+/// `Keyboard` script global
+///
+/// ```rhai
+/// Keyboard.keybind(["alt"], "return", Action.exec("weston-terminal"));
+/// Keyboard.keybind(["alt"], "q", || Log.info("Hi!"), #{ trigger: "release" });
+/// Keyboard.keybind(["alt"], "l", Action.move(10, 0), #{ repeat: true });
+///
+/// // Keys pressed one after another
+/// Keyboard.chord("alt+space t", Action.exec("weston-terminal"));
+/// Keyboard.chord_timeout = 500;
+///
+/// // Bindings that are active only in the "resize" mode
+/// let resize = Keyboard.mode("resize");
+/// resize.keybind([], "l", Action.resize(10, 0), #{ repeat: true });
+/// resize.keybind([], "escape", Action.mode("default"));
+/// Keyboard.keybind(["alt"], "r", Action.mode("resize"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Keyboard {
+    table: Rc<RefCell<KeybindingTable>>,
+    /// Mode that new bindings are added to
+    mode: String,
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self {
+            table: Default::default(),
+            mode: DEFAULT_MODE.into(),
+        }
+    }
+}
+
+impl Keyboard {
+    pub fn keybindings(&self) -> KeybindingTable {
+        self.table.borrow().clone()
+    }
+
+    fn add(
+        &mut self,
+        keys: Vec<KeyCombo>,
+        action: Dynamic,
+        options: Map,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let action = parse_action(action)?;
+        let (trigger, repeat) = parse_options(options)?;
+
+        self.table
+            .borrow_mut()
+            .modes
+            .entry(self.mode.clone())
+            .or_default()
+            .push(Keybinding {
+                keys,
+                trigger,
+                repeat,
+                action,
+            });
+
+        Ok(())
+    }
+
+    fn keybind(
+        &mut self,
+        modifiers: Array,
+        key: &str,
+        action: Dynamic,
+    ) -> Result<(), Box<EvalAltResult>> {
+        self.keybind_with_options(modifiers, key, action, Map::new())
+    }
+
+    fn keybind_with_options(
+        &mut self,
+        modifiers: Array,
+        key: &str,
+        action: Dynamic,
+        options: Map,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let keys = vec![KeyCombo {
+            modifiers: parse_modifiers(modifiers)?,
+            keysym: parse_key(key)?,
+        }];
+
+        self.add(keys, action, options)
+    }
+
+    fn chord(&mut self, chord: &str, action: Dynamic) -> Result<(), Box<EvalAltResult>> {
+        self.chord_with_options(chord, action, Map::new())
+    }
+
+    fn chord_with_options(
+        &mut self,
+        chord: &str,
+        action: Dynamic,
+        options: Map,
+    ) -> Result<(), Box<EvalAltResult>> {
+        self.add(parse_chord(chord)?, action, options)
+    }
+
+    /// Handle that adds bindings to the given mode
+    fn mode(&mut self, name: &str) -> Keyboard {
+        self.table
+            .borrow_mut()
+            .modes
+            .entry(name.to_string())
+            .or_default();
+
+        Keyboard {
+            table: self.table.clone(),
+            mode: name.to_string(),
+        }
+    }
+
+    fn get_chord_timeout(&mut self) -> i64 {
+        self.table.borrow().chord_timeout.as_millis() as i64
+    }
+
+    fn set_chord_timeout(&mut self, timeout: i64) -> Result<(), Box<EvalAltResult>> {
+        if timeout <= 0 {
+            return Err("`chord_timeout` should be positive".into());
+        }
+
+        self.table.borrow_mut().chord_timeout = Duration::from_millis(timeout as u64);
+
+        Ok(())
+    }
+}
+
+pub fn register(engine: &mut Engine) {
+    engine
+        .register_type_with_name::<Keyboard>("Keyboard")
+        .register_fn("keybind", Keyboard::keybind)
+        .register_fn("keybind", Keyboard::keybind_with_options)
+        .register_fn("chord", Keyboard::chord)
+        .register_fn("chord", Keyboard::chord_with_options)
+        .register_fn("mode", Keyboard::mode)
+        .register_get_set(
+            "chord_timeout",
+            Keyboard::get_chord_timeout,
+            Keyboard::set_chord_timeout,
+        );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chord_steps() {
+        let chord = parse_chord("alt+space w  V").unwrap();
+
+        assert_eq!(
+            chord,
+            vec![
+                KeyCombo {
+                    modifiers: KeyModifiers::ALT,
+                    keysym: xkb::KEY_space,
+                },
+                KeyCombo {
+                    modifiers: KeyModifiers::empty(),
+                    keysym: xkb::KEY_w,
+                },
+                KeyCombo {
+                    modifiers: KeyModifiers::empty(),
+                    keysym: xkb::KEY_v,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn chord_modifiers() {
+        let chord = parse_chord("Ctrl+super+Return").unwrap();
+
+        assert_eq!(chord[0].modifiers, KeyModifiers::CTRL | KeyModifiers::LOGO);
+        assert_eq!(chord[0].keysym, xkb::KEY_Return);
+    }
+
+    #[test]
+    fn invalid_chords() {
+        assert!(parse_chord("").is_err());
+        assert!(parse_chord("hyper+a").is_err());
+        assert!(parse_chord("alt+nokey").is_err());
+        assert!(parse_chord("alt+").is_err());
+    }
+
+    fn options(pairs: &[(&str, Dynamic)]) -> Map {
+        pairs
+            .iter()
+            .map(|(name, value)| ((*name).into(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn keybind_options() {
+        let (trigger, repeat) = parse_options(options(&[])).unwrap();
+        assert_eq!((trigger, repeat), (Trigger::Press, false));
+
+        let (trigger, repeat) = parse_options(options(&[
+            ("trigger", "press".into()),
+            ("repeat", true.into()),
+        ]))
+        .unwrap();
+        assert_eq!((trigger, repeat), (Trigger::Press, true));
+
+        let (trigger, repeat) = parse_options(options(&[("trigger", "release".into())])).unwrap();
+        assert_eq!((trigger, repeat), (Trigger::Release, false));
+    }
+
+    #[test]
+    fn release_binding_can_not_repeat() {
+        let result = parse_options(options(&[
+            ("trigger", "release".into()),
+            ("repeat", true.into()),
+        ]));
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,37 @@
+use rhai::Engine;
+
+/// `Log` script global
+#[derive(Debug, Clone)]
+pub struct Log;
+
+impl Log {
+    fn trace(&mut self, msg: &str) {
+        slog_scope::trace!("{}", msg);
+    }
+
+    fn debug(&mut self, msg: &str) {
+        slog_scope::debug!("{}", msg);
+    }
+
+    fn info(&mut self, msg: &str) {
+        slog_scope::info!("{}", msg);
+    }
+
+    fn warn(&mut self, msg: &str) {
+        slog_scope::warn!("{}", msg);
+    }
+
+    fn error(&mut self, msg: &str) {
+        slog_scope::error!("{}", msg);
+    }
+}
+
+pub fn register(engine: &mut Engine) {
+    engine
+        .register_type_with_name::<Log>("Log")
+        .register_fn("trace", Log::trace)
+        .register_fn("debug", Log::debug)
+        .register_fn("info", Log::info)
+        .register_fn("warn", Log::warn)
+        .register_fn("error", Log::error);
+}
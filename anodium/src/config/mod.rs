@@ -0,0 +1,213 @@
+//! Rhai based configuration
+//!
+//! The config script is evaluated once at startup, it has access to a set of globals:
+//! - `Outputs` - output layout
+//! - `Keyboard` - keybindings
+//! - `Mouse` - pointer button bindings and focus policy
+//! - `Windows` - window placement and rules
+//! - `Decorations` - server-side titlebars and borders
+//! - `Borders` - focus borders around all windows
+//! - `Animations` - durations and curves of window animations
+//! - `Action` - actions that can be bound to keys
+//! - `System` - process spawning
+//! - `Log` - logging
+//!
+//! Script callbacks (eg. keybindings) are kept as [`FnPtr`] and executed with [`ConfigVM::call_fn`].
+//!
+//! The script is re-evaluated every time it changes on disk, see [`init_config_watcher`].
+
+use std::{fmt, path::Path};
+
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, Position, Scope, AST};
+
+mod actions;
+mod animations;
+mod borders;
+mod decorations;
+mod keyboard;
+mod log;
+mod mouse;
+mod outputs;
+mod system;
+mod watcher;
+mod windows;
+
+pub use animations::Animations;
+pub use borders::Borders;
+pub use decorations::Decorations;
+pub use keyboard::Keyboard;
+pub use mouse::Mouse;
+pub use outputs::Outputs;
+pub use watcher::init_config_watcher;
+pub use windows::Windows;
+
+/// Error produced while loading or running the config script
+#[derive(Debug)]
+pub struct ConfigError {
+    message: String,
+    position: Position,
+}
+
+impl ConfigError {
+    /// Line at which the error occurred, if known
+    pub fn line(&self) -> Option<usize> {
+        self.position.line()
+    }
+
+    /// Column at which the error occurred, if known
+    pub fn column(&self) -> Option<usize> {
+        self.position.position()
+    }
+}
+
+impl From<Box<EvalAltResult>> for ConfigError {
+    fn from(err: Box<EvalAltResult>) -> Self {
+        // Strip the position from the message, we report it separately
+        let mut err = *err;
+        let position = err.take_position();
+
+        Self {
+            message: err.to_string(),
+            position,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line(), self.column()) {
+            (Some(line), Some(column)) => {
+                write!(f, "{} (line {}, column {})", self.message, line, column)
+            }
+            (Some(line), None) => write!(f, "{} (line {})", self.message, line),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+pub struct ConfigVM {
+    engine: Engine,
+    ast: AST,
+
+    outputs: Outputs,
+    keyboard: Keyboard,
+    mouse: Mouse,
+    windows: Windows,
+    decorations: Decorations,
+    borders: Borders,
+    animations: Animations,
+}
+
+impl ConfigVM {
+    fn new_engine() -> Engine {
+        let mut engine = Engine::new();
+
+        engine.on_print(|msg| slog_scope::info!("{}", msg));
+        engine.on_debug(|msg, _, pos| slog_scope::debug!("{} ({})", msg, pos));
+
+        outputs::register(&mut engine);
+        keyboard::register(&mut engine);
+        mouse::register(&mut engine);
+        windows::register(&mut engine);
+        decorations::register(&mut engine);
+        borders::register(&mut engine);
+        animations::register(&mut engine);
+        actions::register(&mut engine);
+        system::register(&mut engine);
+        log::register(&mut engine);
+
+        engine
+    }
+
+    /// Config that does nothing, used when the script failed to load
+    pub fn empty() -> Self {
+        Self {
+            engine: Self::new_engine(),
+            ast: AST::empty(),
+            outputs: Outputs::default(),
+            keyboard: Keyboard::default(),
+            mouse: Mouse::default(),
+            windows: Windows::default(),
+            decorations: Decorations::default(),
+            borders: Borders::default(),
+            animations: Animations::default(),
+        }
+    }
+
+    /// Compile and evaluate the script at `path`
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let engine = Self::new_engine();
+        let ast = engine.compile_file(path.into())?;
+
+        let outputs = Outputs::default();
+        let keyboard = Keyboard::default();
+        let mouse = Mouse::default();
+        let windows = Windows::default();
+        let decorations = Decorations::default();
+        let borders = Borders::default();
+        let animations = Animations::default();
+
+        let mut scope = Scope::new();
+        scope.push("Outputs", outputs.clone());
+        scope.push("Keyboard", keyboard.clone());
+        scope.push("Mouse", mouse.clone());
+        scope.push("Windows", windows.clone());
+        scope.push("Decorations", decorations.clone());
+        scope.push("Borders", borders.clone());
+        scope.push("Animations", animations.clone());
+        scope.push("Action", actions::Actions);
+        scope.push("System", system::System);
+        scope.push("Log", log::Log);
+
+        engine.run_ast_with_scope(&mut scope, &ast)?;
+
+        Ok(Self {
+            engine,
+            ast,
+            outputs,
+            keyboard,
+            mouse,
+            windows,
+            decorations,
+            borders,
+            animations,
+        })
+    }
+
+    pub fn outputs(&self) -> &Outputs {
+        &self.outputs
+    }
+
+    pub fn keyboard(&self) -> &Keyboard {
+        &self.keyboard
+    }
+
+    pub fn mouse(&self) -> &Mouse {
+        &self.mouse
+    }
+
+    pub fn windows(&self) -> &Windows {
+        &self.windows
+    }
+
+    pub fn decorations(&self) -> &Decorations {
+        &self.decorations
+    }
+
+    pub fn borders(&self) -> &Borders {
+        &self.borders
+    }
+
+    pub fn animations(&self) -> &Animations {
+        &self.animations
+    }
+
+    /// Run script callback, errors are logged
+    pub fn call_fn(&self, callback: &FnPtr) {
+        if let Err(err) = callback.call::<Dynamic>(&self.engine, &self.ast, ()) {
+            slog_scope::error!("Config callback failed: {}", ConfigError::from(err));
+        }
+    }
+}
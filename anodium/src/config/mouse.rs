@@ -0,0 +1,150 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::Duration,
+};
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult, ImmutableString};
+
+use super::keyboard::{parse_action, parse_modifiers};
+use crate::{
+    focus::FocusPolicy,
+    mousebindings::{button_from_name, MouseAction, Mousebinding},
+};
+
+fn parse_button(button: Dynamic) -> Result<u32, Box<EvalAltResult>> {
+    if let Ok(code) = button.as_int() {
+        return u32::try_from(code).map_err(|_| format!("Invalid button code: {}", code).into());
+    }
+
+    let name = button
+        .into_string()
+        .map_err(|ty| format!("Button should be a string or a button code, got {}", ty))?;
+
+    button_from_name(&name).ok_or_else(|| format!("Unknown button: {}", name).into())
+}
+
+/// Accepts a window action name, an `Action` or a script callback
+fn parse_mouse_action(action: Dynamic) -> Result<MouseAction, Box<EvalAltResult>> {
+    if !action.is::<ImmutableString>() {
+        return parse_action(action).map(MouseAction::Action);
+    }
+
+    let name = action.cast::<ImmutableString>();
+
+    Ok(match name.as_str() {
+        "move" => MouseAction::Move,
+        "resize" => MouseAction::Resize,
+        "raise" => MouseAction::Raise,
+        "lower" => MouseAction::Lower,
+        "close" => MouseAction::Close,
+        other => return Err(format!("Unknown mouse action: {}", other).into()),
+    })
+}
+
+/// `Mouse` script global
+///
+/// ```rhai
+/// Mouse.bind(["super"], "left", "move");
+/// Mouse.bind(["super"], "right", "resize");
+/// Mouse.bind(["super"], "middle", "lower");
+/// Mouse.bind(["super", "shift"], "middle", || Log.info("Hi!"));
+///
+/// // One of "click", "sloppy", "strict"
+/// Mouse.focus = "sloppy";
+/// // Raise windows focused by the pointer, after a delay in milliseconds
+/// Mouse.auto_raise = true;
+/// Mouse.auto_raise_delay = 300;
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Mouse {
+    bindings: Rc<RefCell<Vec<Mousebinding>>>,
+    focus_policy: Rc<Cell<FocusPolicy>>,
+    auto_raise: Rc<Cell<bool>>,
+    auto_raise_delay: Rc<Cell<Duration>>,
+}
+
+impl Mouse {
+    pub fn bindings(&self) -> Vec<Mousebinding> {
+        self.bindings.borrow().clone()
+    }
+
+    /// How the keyboard focus follows the pointer
+    pub fn focus_policy(&self) -> FocusPolicy {
+        self.focus_policy.get()
+    }
+
+    /// Raise windows that got focused by the pointer
+    pub fn auto_raise(&self) -> bool {
+        self.auto_raise.get()
+    }
+
+    /// Time a window has to stay focused before it gets raised
+    pub fn auto_raise_delay(&self) -> Duration {
+        self.auto_raise_delay.get()
+    }
+
+    fn get_focus_policy(&mut self) -> ImmutableString {
+        self.focus_policy.get().name().into()
+    }
+
+    fn set_focus_policy(&mut self, name: ImmutableString) -> Result<(), Box<EvalAltResult>> {
+        let policy = FocusPolicy::from_name(&name)
+            .ok_or_else(|| format!("Unknown focus policy: {}", name))?;
+
+        self.focus_policy.set(policy);
+
+        Ok(())
+    }
+
+    fn get_auto_raise(&mut self) -> bool {
+        self.auto_raise.get()
+    }
+
+    fn set_auto_raise(&mut self, auto_raise: bool) {
+        self.auto_raise.set(auto_raise);
+    }
+
+    fn get_auto_raise_delay(&mut self) -> i64 {
+        self.auto_raise_delay.get().as_millis() as i64
+    }
+
+    fn set_auto_raise_delay(&mut self, delay: i64) -> Result<(), Box<EvalAltResult>> {
+        if delay < 0 {
+            return Err("`auto_raise_delay` should not be negative".into());
+        }
+
+        self.auto_raise_delay
+            .set(Duration::from_millis(delay as u64));
+
+        Ok(())
+    }
+
+    fn bind(
+        &mut self,
+        modifiers: Array,
+        button: Dynamic,
+        action: Dynamic,
+    ) -> Result<(), Box<EvalAltResult>> {
+        self.bindings.borrow_mut().push(Mousebinding {
+            modifiers: parse_modifiers(modifiers)?,
+            button: parse_button(button)?,
+            action: parse_mouse_action(action)?,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn register(engine: &mut Engine) {
+    engine
+        .register_type_with_name::<Mouse>("Mouse")
+        .register_get_set("focus", Mouse::get_focus_policy, Mouse::set_focus_policy)
+        .register_get_set("auto_raise", Mouse::get_auto_raise, Mouse::set_auto_raise)
+        .register_get_set(
+            "auto_raise_delay",
+            Mouse::get_auto_raise_delay,
+            Mouse::set_auto_raise_delay,
+        )
+        .register_fn("bind", Mouse::bind);
+}
@@ -0,0 +1,211 @@
+use std::{cell::RefCell, rc::Rc};
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map};
+use smithay::{output::Mode, utils::Transform};
+
+use crate::output_layout::OutputLayout;
+
+fn transform_from_name(name: &str) -> Option<Transform> {
+    Some(match name {
+        "normal" => Transform::Normal,
+        "90" => Transform::_90,
+        "180" => Transform::_180,
+        "270" => Transform::_270,
+        "flipped" => Transform::Flipped,
+        "flipped-90" => Transform::Flipped90,
+        "flipped-180" => Transform::Flipped180,
+        "flipped-270" => Transform::Flipped270,
+        _ => return None,
+    })
+}
+
+fn transform_name(transform: Transform) -> &'static str {
+    match transform {
+        Transform::Normal => "normal",
+        Transform::_90 => "90",
+        Transform::_180 => "180",
+        Transform::_270 => "270",
+        Transform::Flipped => "flipped",
+        Transform::Flipped90 => "flipped-90",
+        Transform::Flipped180 => "flipped-180",
+        Transform::Flipped270 => "flipped-270",
+    }
+}
+
+fn get_pair(map: &Map, key: &str, name: &str) -> Result<Option<(i32, i32)>, Box<EvalAltResult>> {
+    let value = match map.get(key) {
+        Some(value) => value.clone(),
+        None => return Ok(None),
+    };
+
+    let pair = value
+        .into_typed_array::<i64>()
+        .map_err(|ty| format!("Output {} `{}` should be an array, got {}", name, key, ty))?;
+
+    match pair.as_slice() {
+        [a, b] => Ok(Some((*a as i32, *b as i32))),
+        _ => Err(format!("Output {} `{}` should have exactly two elements", name, key).into()),
+    }
+}
+
+fn get_int(map: &Map, key: &str, name: &str) -> Result<Option<i64>, Box<EvalAltResult>> {
+    map.get(key)
+        .map(|value| {
+            value
+                .as_int()
+                .map_err(|ty| format!("Output {} `{}` should be an int, got {}", name, key, ty))
+        })
+        .transpose()
+        .map_err(Into::into)
+}
+
+fn layout_from_map(map: Map) -> Result<OutputLayout, Box<EvalAltResult>> {
+    let name = map
+        .get("name")
+        .ok_or("Output is missing a `name`")?
+        .clone()
+        .into_string()
+        .map_err(|ty| format!("Output `name` should be a string, got {}", ty))?;
+
+    let mut layout = OutputLayout::new(name);
+    let name = layout.name.as_str();
+
+    let refresh = get_int(&map, "refresh", name)?;
+
+    layout.mode = match get_pair(&map, "resolution", name)? {
+        Some((w, h)) => Some(Mode {
+            size: (w, h).into(),
+            refresh: refresh.unwrap_or(0) as i32,
+        }),
+        None if refresh.is_some() => {
+            return Err(format!("Output {} has `refresh` but no `resolution`", name).into());
+        }
+        None => None,
+    };
+
+    layout.position = get_pair(&map, "position", name)?.map(Into::into);
+
+    layout.scale = match get_int(&map, "scale", name)? {
+        Some(scale) if scale < 1 => {
+            return Err(format!("Output {} `scale` should be at least 1", name).into());
+        }
+        scale => scale.map(|scale| scale as i32),
+    };
+
+    layout.transform = match map.get("transform") {
+        Some(transform) => {
+            let transform = transform.clone().into_string().map_err(|ty| {
+                format!("Output {} `transform` should be a string, got {}", name, ty)
+            })?;
+
+            let transform = transform_from_name(&transform)
+                .ok_or_else(|| format!("Output {} has unknown `transform`: {}", name, transform))?;
+
+            Some(transform)
+        }
+        None => None,
+    };
+
+    Ok(layout)
+}
+
+fn layout_to_map(layout: &OutputLayout) -> Map {
+    let mut map = Map::new();
+    map.insert("name".into(), layout.name.clone().into());
+
+    if let Some(mode) = layout.mode {
+        map.insert(
+            "resolution".into(),
+            vec![
+                Dynamic::from(mode.size.w as i64),
+                Dynamic::from(mode.size.h as i64),
+            ]
+            .into(),
+        );
+
+        if mode.refresh != 0 {
+            map.insert("refresh".into(), (mode.refresh as i64).into());
+        }
+    }
+
+    if let Some(position) = layout.position {
+        map.insert(
+            "position".into(),
+            vec![
+                Dynamic::from(position.x as i64),
+                Dynamic::from(position.y as i64),
+            ]
+            .into(),
+        );
+    }
+
+    if let Some(scale) = layout.scale {
+        map.insert("scale".into(), (scale as i64).into());
+    }
+
+    if let Some(transform) = layout.transform {
+        map.insert("transform".into(), transform_name(transform).into());
+    }
+
+    map
+}
+
+/// `Outputs` script global
+///
+/// ```rhai
+/// Outputs.layout = [
+///     #{
+///         name: "DP-1",
+///         resolution: [2560, 1440],
+///         refresh: 60 * 1000,
+///         position: [0, 0],
+///         transform: "90",
+///     },
+///     #{
+///         name: "eDP-1",
+///         position: [0, 2560],
+///         scale: 2,
+///     },
+/// ];
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Outputs {
+    layout: Rc<RefCell<Vec<OutputLayout>>>,
+}
+
+impl Outputs {
+    /// Configured outputs, in the order they should be layed out
+    pub fn layout(&self) -> Vec<OutputLayout> {
+        self.layout.borrow().clone()
+    }
+
+    fn get_layout(&mut self) -> Array {
+        self.layout
+            .borrow()
+            .iter()
+            .map(|o| layout_to_map(o).into())
+            .collect()
+    }
+
+    fn set_layout(&mut self, layout: Array) -> Result<(), Box<EvalAltResult>> {
+        let layout = layout
+            .into_iter()
+            .map(|output| {
+                let map = output
+                    .try_cast::<Map>()
+                    .ok_or("`Outputs.layout` should be an array of object maps")?;
+                layout_from_map(map)
+            })
+            .collect::<Result<_, _>>()?;
+
+        *self.layout.borrow_mut() = layout;
+
+        Ok(())
+    }
+}
+
+pub fn register(engine: &mut Engine) {
+    engine
+        .register_type_with_name::<Outputs>("Outputs")
+        .register_get_set("layout", Outputs::get_layout, Outputs::set_layout);
+}
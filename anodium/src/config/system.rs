@@ -0,0 +1,23 @@
+use rhai::Engine;
+
+/// `System` script global
+#[derive(Debug, Clone)]
+pub struct System;
+
+impl System {
+    fn exec(&mut self, command: &str) {
+        if let Err(err) = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .spawn()
+        {
+            slog_scope::error!("Failed to spawn `{}`: {}", command, err);
+        }
+    }
+}
+
+pub fn register(engine: &mut Engine) {
+    engine
+        .register_type_with_name::<System>("System")
+        .register_fn("exec", System::exec);
+}
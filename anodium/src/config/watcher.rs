@@ -0,0 +1,81 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use inotify::{Inotify, WatchMask};
+use slog_scope::{error, info};
+use smithay::reexports::calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction};
+
+use super::ConfigVM;
+use crate::{CalloopData, State};
+
+/// Watch the config file and reload it every time it changes on disk
+///
+/// The parent directory is watched instead of the file itself,
+/// as most editors save by replacing the file, which would drop the inotify watch.
+pub fn init_config_watcher(
+    handle: &LoopHandle<'static, CalloopData>,
+    path: PathBuf,
+) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Config path is not a file"))?
+        .to_os_string();
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let mut inotify = Inotify::init()?;
+    inotify.add_watch(
+        &dir,
+        WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::CREATE,
+    )?;
+
+    let mut buffer = [0; 1024];
+
+    handle
+        .insert_source(
+            Generic::new(inotify, Interest::READ, Mode::Level),
+            move |_, inotify, data| {
+                let changed = match inotify.read_events(&mut buffer) {
+                    Ok(mut events) => events.any(|event| event.name == Some(file_name.as_os_str())),
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => false,
+                    Err(err) => return Err(err),
+                };
+
+                if changed {
+                    reload(&mut data.state, &path);
+                }
+
+                Ok(PostAction::Continue)
+            },
+        )
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err.error))?;
+
+    Ok(())
+}
+
+/// Re-evaluate the config, old config is kept if the new one fails to load
+fn reload(state: &mut State, path: &Path) {
+    match ConfigVM::load(path) {
+        Ok(config) => {
+            info!("Config {:?} reloaded", path);
+
+            state
+                .keybindings
+                .set_table(&state._loop_handle, config.keyboard().keybindings());
+            state.mousebindings.set_bindings(config.mouse().bindings());
+            state.config = config;
+            state.apply_output_layout();
+        }
+        Err(err) => {
+            error!(
+                "Failed to reload config {:?}, keeping the old one: {}",
+                path, err
+            );
+        }
+    }
+}
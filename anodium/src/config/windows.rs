@@ -0,0 +1,173 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use rhai::{Engine, EvalAltResult, ImmutableString, Map};
+
+use crate::{
+    positioning::Placement,
+    window_rules::{RuleActions, WindowMatch, WindowRule},
+};
+
+const MATCH_KEYS: [&str; 4] = ["app_id", "title", "class", "name"];
+
+fn get_string(map: &Map, key: &str) -> Result<Option<String>, Box<EvalAltResult>> {
+    map.get(key)
+        .map(|value| {
+            value.clone().into_string().map_err(|ty| {
+                format!("Window rule `{}` should be a string, got {}", key, ty).into()
+            })
+        })
+        .transpose()
+}
+
+fn get_bool(map: &Map, key: &str) -> Result<Option<bool>, Box<EvalAltResult>> {
+    map.get(key)
+        .map(|value| {
+            value
+                .as_bool()
+                .map_err(|ty| format!("Window rule `{}` should be a bool, got {}", key, ty).into())
+        })
+        .transpose()
+}
+
+fn get_pair(map: &Map, key: &str) -> Result<Option<(i32, i32)>, Box<EvalAltResult>> {
+    let value = match map.get(key) {
+        Some(value) => value.clone(),
+        None => return Ok(None),
+    };
+
+    let pair = value
+        .into_typed_array::<i64>()
+        .map_err(|ty| format!("Window rule `{}` should be an array, got {}", key, ty))?;
+
+    match pair.as_slice() {
+        [a, b] => Ok(Some((*a as i32, *b as i32))),
+        _ => Err(format!("Window rule `{}` should have exactly two elements", key).into()),
+    }
+}
+
+fn rule_from_map(map: Map) -> Result<WindowRule, Box<EvalAltResult>> {
+    let matcher = WindowMatch {
+        app_id: get_string(&map, "app_id")?,
+        title: get_string(&map, "title")?,
+        class: get_string(&map, "class")?,
+        name: get_string(&map, "name")?,
+    };
+
+    if MATCH_KEYS.iter().all(|key| !map.contains_key(*key)) {
+        return Err(format!(
+            "Window rule should match on at least one of: {}",
+            MATCH_KEYS.join(", ")
+        )
+        .into());
+    }
+
+    let workspace = match map.get("workspace") {
+        Some(workspace) => {
+            let workspace = workspace
+                .as_int()
+                .map_err(|ty| format!("Window rule `workspace` should be an int, got {}", ty))?;
+
+            if workspace < 1 {
+                return Err("Window rule `workspace` should be at least 1".into());
+            }
+
+            Some(workspace as usize - 1)
+        }
+        None => None,
+    };
+
+    let opacity = match map.get("opacity") {
+        Some(opacity) => {
+            let opacity = opacity
+                .as_float()
+                .or_else(|_| opacity.as_int().map(|opacity| opacity as f64))
+                .map_err(|ty| format!("Window rule `opacity` should be a number, got {}", ty))?;
+
+            if !(0.0..=1.0).contains(&opacity) {
+                return Err("Window rule `opacity` should be between 0.0 and 1.0".into());
+            }
+
+            Some(opacity as f32)
+        }
+        None => None,
+    };
+
+    let actions = RuleActions {
+        floating: get_bool(&map, "floating")?,
+        workspace,
+        output: get_string(&map, "output")?,
+        size: get_pair(&map, "size")?.map(Into::into),
+        position: get_pair(&map, "position")?.map(Into::into),
+        opacity,
+        no_focus: get_bool(&map, "no_focus")?,
+        fullscreen: get_bool(&map, "fullscreen")?,
+    };
+
+    Ok(WindowRule { matcher, actions })
+}
+
+/// `Windows` script global
+///
+/// ```rhai
+/// // One of "center", "cascade", "smart", "under-pointer", "center-on-parent"
+/// Windows.placement = "smart";
+///
+/// // Match on `app_id` and `title` of Wayland windows, or `class` and `name` of X11 windows,
+/// // `*` matches any text
+/// Windows.rule(#{ app_id: "org.telegram.*", workspace: 9 });
+/// Windows.rule(#{
+///     title: "Picture-in-Picture",
+///     floating: true,
+///     size: [480, 270],
+///     position: [20, 20],
+///     opacity: 0.9,
+///     no_focus: true,
+/// });
+/// Windows.rule(#{ class: "mpv", output: "HDMI-A-1", fullscreen: true });
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Windows {
+    placement: Rc<Cell<Placement>>,
+    rules: Rc<RefCell<Vec<WindowRule>>>,
+}
+
+impl Windows {
+    /// Where new floating windows are placed
+    pub fn placement(&self) -> Placement {
+        self.placement.get()
+    }
+
+    /// Window rules, in the order they were declared
+    pub fn rules(&self) -> Vec<WindowRule> {
+        self.rules.borrow().clone()
+    }
+
+    fn get_placement(&mut self) -> ImmutableString {
+        self.placement.get().name().into()
+    }
+
+    fn set_placement(&mut self, name: ImmutableString) -> Result<(), Box<EvalAltResult>> {
+        let placement = Placement::from_name(&name)
+            .ok_or_else(|| format!("Unknown window placement: {}", name))?;
+
+        self.placement.set(placement);
+
+        Ok(())
+    }
+
+    fn rule(&mut self, rule: Map) -> Result<(), Box<EvalAltResult>> {
+        self.rules.borrow_mut().push(rule_from_map(rule)?);
+
+        Ok(())
+    }
+}
+
+pub fn register(engine: &mut Engine) {
+    engine
+        .register_type_with_name::<Windows>("Windows")
+        .register_get_set("placement", Windows::get_placement, Windows::set_placement)
+        .register_fn("rule", Windows::rule);
+}
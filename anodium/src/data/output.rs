@@ -1,8 +1,20 @@
-use smithay::output::Output;
+use std::cell::{Cell, Ref, RefCell, RefMut};
+
+use smithay::{
+    output::Output,
+    utils::{Logical, Rectangle, Transform},
+};
+
+use crate::workspace::Workspaces;
 
 #[derive(Default, Debug)]
 pub struct OutputState {
     fps: fps_ticker::Fps,
+    workspaces: RefCell<Workspaces>,
+    /// Usable area the windows got arranged for the last time
+    usable_area: Cell<Option<Rectangle<i32, Logical>>>,
+    /// Transform from the backend, used unless the config sets one
+    preferred_transform: Cell<Option<Transform>>,
 }
 
 impl OutputState {
@@ -14,4 +26,28 @@ impl OutputState {
     pub fn fps_tick(&self) {
         self.fps.tick();
     }
+
+    pub fn workspaces(&self) -> Ref<'_, Workspaces> {
+        self.workspaces.borrow()
+    }
+
+    pub fn workspaces_mut(&self) -> RefMut<'_, Workspaces> {
+        self.workspaces.borrow_mut()
+    }
+
+    pub fn usable_area(&self) -> Option<Rectangle<i32, Logical>> {
+        self.usable_area.get()
+    }
+
+    pub fn set_usable_area(&self, area: Option<Rectangle<i32, Logical>>) {
+        self.usable_area.set(area);
+    }
+
+    pub fn preferred_transform(&self) -> Option<Transform> {
+        self.preferred_transform.get()
+    }
+
+    pub fn set_preferred_transform(&self, transform: Transform) {
+        self.preferred_transform.set(Some(transform));
+    }
 }
@@ -1,20 +1,19 @@
-use std::{
-    cell::{Cell, RefCell},
-    collections::HashSet,
-};
+use std::cell::{Cell, RefCell};
 
 use smithay::{
-    backend::input::KeyState,
+    desktop::Window,
     input::Seat,
     utils::{Logical, Point},
 };
 
-use crate::State;
+use crate::{keybindings::KeyModifiers, State};
 
 #[derive(Debug, Default)]
 pub struct SeatState {
     pointer_pos: Cell<Point<f64, Logical>>,
-    pressed_keys: RefCell<HashSet<u32>>,
+    modifiers: Cell<KeyModifiers>,
+    /// Windows in the order they were focused, most recent first
+    focus_history: RefCell<Vec<Window>>,
 }
 
 impl SeatState {
@@ -31,15 +30,31 @@ impl SeatState {
         self.pointer_pos.set(pointer_pos);
     }
 
-    pub fn update_pressed_keys(&self, keysym: u32, state: KeyState) {
-        if let KeyState::Pressed = state {
-            self.pressed_keys.borrow_mut().insert(keysym);
-        } else {
-            self.pressed_keys.borrow_mut().remove(&keysym);
-        }
+    /// Modifiers that were active during the last keyboard event
+    pub fn modifiers(&self) -> KeyModifiers {
+        self.modifiers.get()
+    }
+
+    pub fn set_modifiers(&self, modifiers: KeyModifiers) {
+        self.modifiers.set(modifiers);
+    }
+
+    /// Windows that had keyboard focus, most recently focused first
+    pub fn focus_history(&self) -> Vec<Window> {
+        let mut history = self.focus_history.borrow_mut();
+        history.retain(|window| window.toplevel().alive());
+        history.clone()
+    }
+
+    /// Most recently focused window, even if it got destroyed since
+    pub fn last_focused(&self) -> Option<Window> {
+        self.focus_history.borrow().first().cloned()
     }
 
-    pub fn is_key_pressed(&self, keysym: u32) -> bool {
-        self.pressed_keys.borrow().get(&keysym).is_some()
+    /// Move the window to the front of the focus history
+    pub fn push_focus(&self, window: &Window) {
+        let mut history = self.focus_history.borrow_mut();
+        history.retain(|w| w != window && w.toplevel().alive());
+        history.insert(0, window.clone());
     }
 }
@@ -5,7 +5,7 @@ use smithay::{
         wayland_protocols::xdg::shell::server::xdg_toplevel,
         wayland_server::protocol::wl_surface::WlSurface,
     },
-    utils::{Logical, Rectangle},
+    utils::{Logical, Point, Rectangle},
     wayland::compositor,
 };
 
@@ -24,6 +24,28 @@ bitflags::bitflags! {
     }
 }
 
+impl ResizeEdge {
+    /// Corner of the window closest to the pointer, based on the quadrant the pointer is in
+    pub fn from_quadrant(window: Rectangle<i32, Logical>, pointer: Point<f64, Logical>) -> Self {
+        let center_x = window.loc.x as f64 + window.size.w as f64 / 2.0;
+        let center_y = window.loc.y as f64 + window.size.h as f64 / 2.0;
+
+        let horizontal = if pointer.x < center_x {
+            Self::LEFT
+        } else {
+            Self::RIGHT
+        };
+
+        let vertical = if pointer.y < center_y {
+            Self::TOP
+        } else {
+            Self::BOTTOM
+        };
+
+        horizontal | vertical
+    }
+}
+
 impl From<xdg_toplevel::ResizeEdge> for ResizeEdge {
     #[inline]
     fn from(x: xdg_toplevel::ResizeEdge) -> Self {
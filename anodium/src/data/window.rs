@@ -0,0 +1,205 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use smithay::{
+    desktop::Window,
+    output::Output,
+    utils::{Logical, Point, Rectangle},
+};
+
+use crate::{
+    animation::{Animation, Frame, Snapshot},
+    render::WindowLook,
+};
+
+/// Source of [`WindowState::id`]
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Maximized, fullscreen and minimized state of a window
+#[derive(Debug, Default)]
+pub struct WindowState {
+    /// Unique for the whole run of the compositor, unlike protocol ids of surfaces
+    id: usize,
+
+    /// Geometry from before the window got maximized or fullscreened
+    saved_geometry: Cell<Option<Rectangle<i32, Logical>>>,
+    /// The window was tiled before it got maximized, fullscreened or minimized
+    was_tiled: Cell<bool>,
+
+    maximized: Cell<bool>,
+    fullscreen: RefCell<Option<Output>>,
+    /// Fullscreen got requested before the window was placed on an output
+    fullscreen_requested: Cell<bool>,
+    minimized: Cell<bool>,
+    /// Dropped onto a snap zone, its previous size is the saved geometry
+    snapped: Cell<bool>,
+
+    /// Set by window rules, the window is never tiled automatically
+    floating: Cell<bool>,
+    /// Set by window rules, the window never gets keyboard focus
+    no_focus: Cell<bool>,
+    /// Set by window rules, `None` is opaque
+    opacity: Cell<Option<f32>>,
+    /// Set by window rules, location relative to the output used instead of the placement policy
+    initial_position: Cell<Option<Point<i32, Logical>>>,
+
+    /// Negotiated with xdg-decoration, titlebar and borders are drawn by us
+    server_side_decorations: Cell<bool>,
+
+    /// Running animation, dropped once it ends
+    animation: RefCell<Option<Animation>>,
+    /// Where the window got drawn last time, `None` until its first frame
+    drawn_frame: Cell<Option<Frame>>,
+    /// Look of the last frame on each output, by output name
+    drawn_looks: RefCell<HashMap<String, WindowLook>>,
+    /// Textures from the last frame, kept for animations of disappearing windows
+    snapshot: RefCell<Option<Snapshot>>,
+}
+
+impl WindowState {
+    pub fn for_window(window: &Window) -> &Self {
+        window.user_data().insert_if_missing(|| Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            ..Default::default()
+        });
+        window.user_data().get::<Self>().unwrap()
+    }
+
+    /// Base of the ids of all render elements of the window
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Save the geometry, unless there is one saved already
+    pub fn save_geometry(&self, geometry: Rectangle<i32, Logical>) {
+        if self.saved_geometry.get().is_none() {
+            self.saved_geometry.set(Some(geometry));
+        }
+    }
+
+    pub fn take_saved_geometry(&self) -> Option<Rectangle<i32, Logical>> {
+        self.saved_geometry.take()
+    }
+
+    pub fn was_tiled(&self) -> bool {
+        self.was_tiled.get()
+    }
+
+    pub fn set_was_tiled(&self, was_tiled: bool) {
+        self.was_tiled.set(was_tiled);
+    }
+
+    pub fn is_maximized(&self) -> bool {
+        self.maximized.get()
+    }
+
+    pub fn set_maximized(&self, maximized: bool) {
+        self.maximized.set(maximized);
+    }
+
+    pub fn fullscreen_output(&self) -> Option<Output> {
+        self.fullscreen.borrow().clone()
+    }
+
+    pub fn set_fullscreen_output(&self, output: Option<Output>) {
+        *self.fullscreen.borrow_mut() = output;
+    }
+
+    pub fn set_fullscreen_requested(&self, requested: bool) {
+        self.fullscreen_requested.set(requested);
+    }
+
+    pub fn take_fullscreen_requested(&self) -> bool {
+        self.fullscreen_requested.take()
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        self.minimized.get()
+    }
+
+    pub fn set_minimized(&self, minimized: bool) {
+        self.minimized.set(minimized);
+    }
+
+    pub fn is_snapped(&self) -> bool {
+        self.snapped.get()
+    }
+
+    pub fn set_snapped(&self, snapped: bool) {
+        self.snapped.set(snapped);
+    }
+
+    pub fn is_floating(&self) -> bool {
+        self.floating.get()
+    }
+
+    pub fn set_floating(&self, floating: bool) {
+        self.floating.set(floating);
+    }
+
+    pub fn is_no_focus(&self) -> bool {
+        self.no_focus.get()
+    }
+
+    pub fn set_no_focus(&self, no_focus: bool) {
+        self.no_focus.set(no_focus);
+    }
+
+    pub fn opacity(&self) -> f32 {
+        self.opacity.get().unwrap_or(1.0)
+    }
+
+    pub fn set_opacity(&self, opacity: Option<f32>) {
+        self.opacity
+            .set(opacity.map(|opacity| opacity.clamp(0.0, 1.0)));
+    }
+
+    pub fn set_initial_position(&self, position: Option<Point<i32, Logical>>) {
+        self.initial_position.set(position);
+    }
+
+    pub fn take_initial_position(&self) -> Option<Point<i32, Logical>> {
+        self.initial_position.take()
+    }
+
+    pub fn server_side_decorations(&self) -> bool {
+        self.server_side_decorations.get()
+    }
+
+    pub fn set_server_side_decorations(&self, server_side: bool) {
+        self.server_side_decorations.set(server_side);
+    }
+
+    pub fn animation(&self) -> Option<Animation> {
+        self.animation.borrow().clone()
+    }
+
+    pub fn set_animation(&self, animation: Option<Animation>) {
+        *self.animation.borrow_mut() = animation;
+    }
+
+    pub fn drawn_frame(&self) -> Option<Frame> {
+        self.drawn_frame.get()
+    }
+
+    pub fn set_drawn_frame(&self, frame: Frame) {
+        self.drawn_frame.set(Some(frame));
+    }
+
+    /// Remember the look the window got drawn with on the output, `true` if it changed
+    pub fn update_drawn_look(&self, output: &Output, look: WindowLook) -> bool {
+        let previous = self.drawn_looks.borrow_mut().insert(output.name(), look);
+        previous != Some(look)
+    }
+
+    pub fn snapshot(&self) -> Option<Snapshot> {
+        self.snapshot.borrow().clone()
+    }
+
+    pub fn set_snapshot(&self, snapshot: Option<Snapshot>) {
+        *self.snapshot.borrow_mut() = snapshot;
+    }
+}
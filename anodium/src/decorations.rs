@@ -0,0 +1,477 @@
+//! Server-side decorations, titlebars and borders drawn by the compositor
+//!
+//! Decorations and the focus border are drawn outside of the window geometry, so the location
+//! of a window in the [`Space`](smithay::desktop::Space) stays the location of its content.
+
+use std::rc::Rc;
+
+use anodium_framework::quad::{QuadElement, QuadPipeline, QuadStyle};
+use smithay::{
+    backend::renderer::gles2::{Gles2Error, Gles2Frame, Gles2Renderer},
+    desktop::{
+        space::{RenderElement, RenderZindex, SpaceOutputTuple},
+        Window,
+    },
+    utils::{Logical, Physical, Point, Rectangle, Scale, Size, Transform},
+};
+
+use crate::{animation::Frame, data::window::WindowState, State};
+
+/// Colors and sizes of decorations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecorationTheme {
+    /// Draw decorations for clients that don't insist on drawing their own
+    pub server_side: bool,
+    pub titlebar_height: i32,
+    pub border_width: i32,
+    pub focused_color: [f32; 4],
+    pub unfocused_color: [f32; 4],
+    pub close_color: [f32; 4],
+    pub maximize_color: [f32; 4],
+    pub minimize_color: [f32; 4],
+}
+
+impl Default for DecorationTheme {
+    fn default() -> Self {
+        Self {
+            server_side: true,
+            titlebar_height: 24,
+            border_width: 1,
+            focused_color: [26.0 / 255.0, 95.0 / 255.0, 205.0 / 255.0, 1.0],
+            unfocused_color: [0.23, 0.23, 0.23, 1.0],
+            close_color: [0.88, 0.42, 0.46, 1.0],
+            maximize_color: [0.6, 0.76, 0.47, 1.0],
+            minimize_color: [0.9, 0.75, 0.48, 1.0],
+        }
+    }
+}
+
+/// Border drawn around every window, telling apart the focused one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BorderTheme {
+    /// `0` disables the border
+    pub width: i32,
+    pub corner_radius: i32,
+    pub focused_color: [f32; 4],
+    pub unfocused_color: [f32; 4],
+}
+
+impl Default for BorderTheme {
+    fn default() -> Self {
+        Self {
+            width: 2,
+            corner_radius: 0,
+            focused_color: [26.0 / 255.0, 95.0 / 255.0, 205.0 / 255.0, 1.0],
+            unfocused_color: [0.23, 0.23, 0.23, 1.0],
+        }
+    }
+}
+
+/// Part of the decorations under the pointer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecorationPart {
+    Titlebar,
+    Close,
+    Maximize,
+    Minimize,
+}
+
+/// Rectangles of the decorations of a single window, in global coordinates
+#[derive(Debug, Clone)]
+struct DecorationLayout {
+    /// Window geometry and its decorations
+    outer: Rectangle<i32, Logical>,
+    titlebar: Rectangle<i32, Logical>,
+    borders: [Rectangle<i32, Logical>; 4],
+    buttons: [(DecorationPart, Rectangle<i32, Logical>); 3],
+}
+
+impl DecorationLayout {
+    /// `window` is the window geometry in global coordinates
+    fn new(theme: &DecorationTheme, window: Rectangle<i32, Logical>) -> Self {
+        let border = theme.border_width;
+        let titlebar_height = theme.titlebar_height;
+
+        let outer = Rectangle::from_loc_and_size(
+            (
+                window.loc.x - border,
+                window.loc.y - titlebar_height - border,
+            ),
+            (
+                window.size.w + border * 2,
+                window.size.h + titlebar_height + border * 2,
+            ),
+        );
+
+        let titlebar = Rectangle::from_loc_and_size(
+            (window.loc.x, window.loc.y - titlebar_height),
+            (window.size.w, titlebar_height),
+        );
+
+        let borders = [
+            // Top
+            Rectangle::from_loc_and_size(outer.loc, (outer.size.w, border)),
+            // Bottom
+            Rectangle::from_loc_and_size(
+                (outer.loc.x, outer.loc.y + outer.size.h - border),
+                (outer.size.w, border),
+            ),
+            // Left
+            Rectangle::from_loc_and_size(
+                (outer.loc.x, outer.loc.y + border),
+                (border, outer.size.h - border * 2),
+            ),
+            // Right
+            Rectangle::from_loc_and_size(
+                (outer.loc.x + outer.size.w - border, outer.loc.y + border),
+                (border, outer.size.h - border * 2),
+            ),
+        ];
+
+        // Square buttons at the right end of the titlebar, close is the rightmost one
+        let size = titlebar_height * 3 / 5;
+        let margin = (titlebar_height - size) / 2;
+        let button = |id: i32| {
+            let x = titlebar.loc.x + titlebar.size.w - (size + margin) * (id + 1);
+            Rectangle::from_loc_and_size((x, titlebar.loc.y + margin), (size, size))
+        };
+
+        let buttons = [
+            (DecorationPart::Close, button(0)),
+            (DecorationPart::Maximize, button(1)),
+            (DecorationPart::Minimize, button(2)),
+        ];
+
+        Self {
+            outer,
+            titlebar,
+            borders,
+            buttons,
+        }
+    }
+
+    fn part_under(&self, point: Point<f64, Logical>) -> Option<DecorationPart> {
+        let contains = |rect: &Rectangle<i32, Logical>| rect.to_f64().contains(point);
+
+        if let Some((part, _)) = self.buttons.iter().find(|(_, rect)| contains(rect)) {
+            return Some(*part);
+        }
+
+        // Borders are too thin to grab, they move the window as well
+        if contains(&self.titlebar) || self.borders.iter().any(contains) {
+            Some(DecorationPart::Titlebar)
+        } else {
+            None
+        }
+    }
+}
+
+pub struct DecorationElement {
+    pipeline: Rc<QuadPipeline>,
+    id: usize,
+    layout: DecorationLayout,
+    /// Rectangles to fill, in the drawing order
+    quads: Vec<(Rectangle<i32, Logical>, [f32; 4])>,
+    alpha: f32,
+    damaged: bool,
+    output_geometry: Rectangle<f64, Physical>,
+}
+
+impl DecorationElement {
+    /// `output_size` is the physical size of the rendered output
+    fn new(
+        pipeline: Rc<QuadPipeline>,
+        window: &Window,
+        layout: DecorationLayout,
+        theme: &DecorationTheme,
+        focused: bool,
+        alpha: f32,
+        output_size: Size<f64, Physical>,
+    ) -> Self {
+        let color = if focused {
+            theme.focused_color
+        } else {
+            theme.unfocused_color
+        };
+
+        let mut quads = vec![(layout.titlebar, color)];
+        quads.extend(layout.borders.iter().map(|rect| (*rect, color)));
+
+        quads.extend(layout.buttons.iter().map(|(part, rect)| {
+            let color = match part {
+                DecorationPart::Close => theme.close_color,
+                DecorationPart::Maximize => theme.maximize_color,
+                DecorationPart::Minimize => theme.minimize_color,
+                DecorationPart::Titlebar => color,
+            };
+
+            (*rect, color)
+        }));
+
+        // Kept apart from the id of the window itself
+        let id = WindowState::for_window(window).id() | (1 << (usize::BITS - 1));
+
+        Self {
+            pipeline,
+            id,
+            layout,
+            quads,
+            alpha,
+            damaged: true,
+            output_geometry: Rectangle::from_loc_and_size((0.0, 0.0), output_size),
+        }
+    }
+}
+
+impl DecorationElement {
+    /// Did focus, geometry or alpha change since the last frame
+    pub fn with_damaged(mut self, damaged: bool) -> Self {
+        self.damaged = damaged;
+        self
+    }
+}
+
+impl RenderElement<Gles2Renderer> for DecorationElement {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn location(&self, scale: impl Into<Scale<f64>>) -> Point<f64, Physical> {
+        self.layout.outer.loc.to_f64().to_physical(scale)
+    }
+
+    fn geometry(&self, scale: impl Into<Scale<f64>>) -> Rectangle<i32, Physical> {
+        self.layout.outer.to_physical_precise_round(scale)
+    }
+
+    fn accumulated_damage(
+        &self,
+        scale: impl Into<Scale<f64>>,
+        _: Option<SpaceOutputTuple<'_, '_>>,
+    ) -> Vec<Rectangle<i32, Physical>> {
+        if !self.damaged {
+            return Vec::new();
+        }
+
+        vec![Rectangle::from_loc_and_size((0, 0), self.layout.outer.size)
+            .to_physical_precise_up(scale)]
+    }
+
+    fn opaque_regions(
+        &self,
+        _scale: impl Into<Scale<f64>>,
+    ) -> Option<Vec<Rectangle<i32, Physical>>> {
+        None
+    }
+
+    fn z_index(&self) -> u8 {
+        RenderZindex::Shell as u8
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Gles2Renderer,
+        _frame: &mut Gles2Frame,
+        scale: impl Into<Scale<f64>>,
+        location: Point<f64, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        _log: &slog::Logger,
+    ) -> Result<(), Gles2Error> {
+        let scale = scale.into();
+
+        renderer.with_context(|_, gl| {
+            for (rect, color) in self.quads.iter() {
+                let offset = (rect.loc - self.layout.outer.loc)
+                    .to_f64()
+                    .to_physical(scale);
+                let quad_rect = Rectangle::from_loc_and_size(
+                    self.output_geometry.loc + location + offset,
+                    rect.size.to_f64().to_physical(scale),
+                );
+
+                for damage in damage {
+                    self.pipeline.render_clipped(
+                        self.output_geometry,
+                        quad_rect,
+                        damage.to_f64(),
+                        Transform::Flipped180,
+                        gl,
+                        QuadStyle::solid(*color).with_alpha(self.alpha),
+                    );
+                }
+            }
+        })
+    }
+}
+
+impl State {
+    /// Are titlebar and borders of the window drawn by the compositor
+    pub fn is_decorated(&self, window: &Window) -> bool {
+        let window_state = WindowState::for_window(window);
+
+        // Fullscreen windows cover the whole output
+        window_state.server_side_decorations() && window_state.fullscreen_output().is_none()
+    }
+
+    /// Space taken around the window geometry by decorations and the border,
+    /// as left, top, right and bottom
+    fn frame_insets(&self, window: &Window) -> [i32; 4] {
+        // Fullscreen windows cover the whole output
+        if WindowState::for_window(window)
+            .fullscreen_output()
+            .is_some()
+        {
+            return [0; 4];
+        }
+
+        let mut insets = [self.config.borders().theme().width; 4];
+
+        if self.is_decorated(window) {
+            let theme = self.config.decorations().theme();
+
+            for inset in insets.iter_mut() {
+                *inset += theme.border_width;
+            }
+
+            insets[1] += theme.titlebar_height;
+        }
+
+        insets
+    }
+
+    /// Area left for the window itself, once its decorations and border are placed inside of `rect`
+    pub fn inside_frame(
+        &self,
+        window: &Window,
+        rect: Rectangle<i32, Logical>,
+    ) -> Rectangle<i32, Logical> {
+        let [left, top, right, bottom] = self.frame_insets(window);
+
+        Rectangle::from_loc_and_size(
+            (rect.loc.x + left, rect.loc.y + top),
+            (
+                (rect.size.w - left - right).max(1),
+                (rect.size.h - top - bottom).max(1),
+            ),
+        )
+    }
+
+    fn decoration_layout(&self, window: &Window) -> Option<DecorationLayout> {
+        if !self.is_decorated(window) {
+            return None;
+        }
+
+        let loc = self.space.window_location(window)?;
+        let rect = Rectangle::from_loc_and_size(loc, window.geometry().size);
+
+        Some(DecorationLayout::new(
+            &self.config.decorations().theme(),
+            rect,
+        ))
+    }
+
+    /// Decorated window and the part of its decorations under the pointer
+    ///
+    /// Windows stacked above the decorations take precedence.
+    pub fn decoration_under(&self, point: Point<f64, Logical>) -> Option<(Window, DecorationPart)> {
+        for window in self.space.windows().rev() {
+            let loc = match self.space.window_location(window) {
+                Some(loc) => loc,
+                None => continue,
+            };
+            let rect = Rectangle::from_loc_and_size(loc, window.geometry().size);
+
+            if rect.to_f64().contains(point) {
+                return None;
+            }
+
+            let part = self
+                .decoration_layout(window)
+                .and_then(|layout| layout.part_under(point));
+
+            if let Some(part) = part {
+                return Some((window.clone(), part));
+            }
+        }
+
+        None
+    }
+
+    /// Decorations of the window drawn in `frame`, if it has any
+    pub fn decoration_element(
+        &self,
+        pipeline: Rc<QuadPipeline>,
+        window: &Window,
+        frame: Frame,
+        focused: bool,
+        output_size: Size<f64, Physical>,
+    ) -> Option<DecorationElement> {
+        if !self.is_decorated(window) {
+            return None;
+        }
+
+        let theme = self.config.decorations().theme();
+        let rect = frame.rect.to_i32_round();
+
+        Some(DecorationElement::new(
+            pipeline,
+            window,
+            DecorationLayout::new(&theme, rect),
+            &theme,
+            focused,
+            frame.alpha,
+            output_size,
+        ))
+    }
+
+    /// Border around the window drawn in `frame` and its decorations, unless borders are disabled
+    pub fn border_element(
+        &self,
+        pipeline: Rc<QuadPipeline>,
+        window: &Window,
+        frame: Frame,
+        focused: bool,
+        output_size: Size<f64, Physical>,
+    ) -> Option<QuadElement> {
+        let theme = self.config.borders().theme();
+
+        if theme.width <= 0
+            || WindowState::for_window(window)
+                .fullscreen_output()
+                .is_some()
+        {
+            return None;
+        }
+
+        let mut rect = frame.rect.to_i32_round();
+
+        if self.is_decorated(window) {
+            rect = DecorationLayout::new(&self.config.decorations().theme(), rect).outer;
+        }
+
+        let width = theme.width;
+        let rect = Rectangle::from_loc_and_size(
+            (rect.loc.x - width, rect.loc.y - width),
+            (rect.size.w + width * 2, rect.size.h + width * 2),
+        );
+
+        let color = if focused {
+            theme.focused_color
+        } else {
+            theme.unfocused_color
+        };
+
+        let style = QuadStyle::border(color, width as f32)
+            .with_corner_radius(theme.corner_radius as f32)
+            .with_alpha(frame.alpha);
+
+        // Kept apart from the ids of the window and its decorations
+        let id = WindowState::for_window(window).id() | (1 << (usize::BITS - 2));
+
+        Some(
+            QuadElement::new(pipeline, rect, style, output_size)
+                .with_id(id)
+                .with_z_index(RenderZindex::Shell as u8),
+        )
+    }
+}
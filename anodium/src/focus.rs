@@ -0,0 +1,214 @@
+//! Keyboard focus following the pointer
+
+use std::time::Duration;
+
+use smithay::{
+    desktop::{Kind, Window},
+    reexports::calloop::timer::{TimeoutAction, Timer},
+    reexports::wayland_server::Resource,
+    utils::{Logical, Point, SERIAL_COUNTER},
+};
+
+use crate::{
+    data::{output::OutputState, seat::SeatState, window::WindowState},
+    layers::UPPER_LAYERS,
+    State,
+};
+
+/// How the keyboard focus follows the pointer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusPolicy {
+    /// Windows get focused by clicking them
+    Click,
+    /// Windows get focused once the pointer enters them, focus stays when it leaves to the desktop
+    Sloppy,
+    /// Like [`FocusPolicy::Sloppy`], but leaving to the desktop clears the focus
+    Strict,
+}
+
+impl Default for FocusPolicy {
+    fn default() -> Self {
+        Self::Click
+    }
+}
+
+impl FocusPolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "click" => Self::Click,
+            "sloppy" => Self::Sloppy,
+            "strict" => Self::Strict,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Click => "click",
+            Self::Sloppy => "sloppy",
+            Self::Strict => "strict",
+        }
+    }
+}
+
+impl State {
+    /// Apply the configured [`FocusPolicy`] after the pointer moved to `position`
+    pub fn focus_follows_mouse(&mut self, position: Point<f64, Logical>) {
+        let mouse = self.config.mouse();
+        let policy = mouse.focus_policy();
+        let auto_raise = mouse.auto_raise().then(|| mouse.auto_raise_delay());
+
+        if policy == FocusPolicy::Click {
+            return;
+        }
+
+        let pointer = self.seat.get_pointer().unwrap();
+        let keyboard = self.seat.get_keyboard().unwrap();
+
+        // Moves, resizes, popup grabs and the switcher keep their focus
+        if pointer.is_grabbed() || keyboard.is_grabbed() || self.switcher.is_some() {
+            return;
+        }
+
+        let focused = self.focused_window();
+
+        // Focus given to something else than a toplevel, eg. a popup, stays
+        if focused.is_none() && keyboard.current_focus().is_some() {
+            return;
+        }
+
+        // Windows covered by panels are not hovered
+        if self.layer_under(position, &UPPER_LAYERS).is_some() {
+            return;
+        }
+
+        let under = self
+            .space
+            .window_under(position)
+            .cloned()
+            .or_else(|| self.decoration_under(position).map(|(window, _)| window));
+
+        match under {
+            Some(window) => {
+                if Some(&window) == focused.as_ref()
+                    || WindowState::for_window(&window).is_no_focus()
+                {
+                    return;
+                }
+
+                self.focus_window_without_raise(&window);
+
+                match auto_raise {
+                    Some(delay) => self.schedule_auto_raise(window, delay),
+                    None => self.cancel_auto_raise(),
+                }
+            }
+            None if policy == FocusPolicy::Strict => {
+                self.cancel_auto_raise();
+
+                if focused.is_some() {
+                    self.unfocus_windows();
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Raise the window once it stayed focused for `delay`
+    fn schedule_auto_raise(&mut self, window: Window, delay: Duration) {
+        self.cancel_auto_raise();
+
+        if delay.is_zero() {
+            self.auto_raise(&window);
+            return;
+        }
+
+        self.auto_raise_timer = self
+            ._loop_handle
+            .insert_source(Timer::from_duration(delay), move |_, _, data| {
+                data.state.auto_raise_timer = None;
+                data.state.auto_raise(&window);
+
+                TimeoutAction::Drop
+            })
+            .ok();
+    }
+
+    fn cancel_auto_raise(&mut self) {
+        if let Some(token) = self.auto_raise_timer.take() {
+            self._loop_handle.remove(token);
+        }
+    }
+
+    fn auto_raise(&mut self, window: &Window) {
+        if self.focused_window().as_ref() != Some(window) {
+            return;
+        }
+
+        self.space.raise_window(window, true);
+        self.raise_floating_windows();
+    }
+
+    /// Windows of the workspace the focus was on, if the focused window or popup got destroyed
+    ///
+    /// Has to be called before destroyed windows are removed from workspaces.
+    pub fn lost_focus_workspace(&self) -> Option<Vec<Window>> {
+        let keyboard = self.seat.get_keyboard().unwrap();
+
+        if keyboard.current_focus()?.alive() {
+            return None;
+        }
+
+        let windows = SeatState::for_seat(&self.seat)
+            .last_focused()
+            .and_then(|last| {
+                let output = self.output_for_window(&last)?;
+                let workspaces = OutputState::for_output(&output).workspaces();
+                let workspace = workspaces.iter().find(|ws| ws.contains(&last))?;
+
+                let windows: Vec<Window> = workspace.windows().cloned().collect();
+                Some(windows)
+            })
+            .unwrap_or_default();
+
+        Some(windows)
+    }
+
+    /// Focus the most recently focused window out of `windows` that is still visible
+    ///
+    /// Windows that never had focus are tried last, from top to bottom.
+    pub fn restore_focus(&mut self, windows: &[Window]) {
+        let history = SeatState::for_seat(&self.seat).focus_history();
+
+        let window = history
+            .iter()
+            .chain(windows.iter().rev())
+            .find(|window| {
+                windows.contains(window)
+                    && window.toplevel().alive()
+                    && self.space.window_location(window).is_some()
+                    && !WindowState::for_window(window).is_no_focus()
+            })
+            .cloned();
+
+        match window {
+            Some(window) => self.focus_window(&window),
+            None => self.unfocus_windows(),
+        }
+    }
+
+    /// Deactivate all windows and clear the keyboard focus
+    pub fn unfocus_windows(&mut self) {
+        for window in self.space.windows() {
+            window.set_activated(false);
+
+            // TODO: Remove once smithay supports xwayland
+            if let Kind::Xdg(_) = window.toplevel() {
+                window.configure();
+            }
+        }
+
+        let keyboard = self.seat.get_keyboard().unwrap();
+        keyboard.set_focus(self, None, SERIAL_COUNTER.next_serial());
+    }
+}
@@ -5,15 +5,55 @@ use smithay::{
         PointerInnerHandle,
     },
     reexports::wayland_server::protocol::wl_surface::WlSurface,
-    utils::{Logical, Point},
+    utils::{Logical, Point, Rectangle},
 };
 
 use crate::State;
 
+/// Distance at which window edges stick to output borders and other windows
+const SNAP_DISTANCE: i32 = 16;
+/// Width of the drop zones along output borders
+const ZONE_SIZE: f64 = 8.0;
+/// Length of the corner drop zones, measured along the output borders
+const CORNER_SIZE: f64 = 64.0;
+
 pub struct MoveSurfaceGrab {
-    pub start_data: PointerGrabStartData<State>,
-    pub window: Window,
-    pub initial_window_location: Point<i32, Logical>,
+    start_data: PointerGrabStartData<State>,
+    window: Window,
+    initial_window_location: Point<i32, Logical>,
+
+    /// Area the window gets snapped to when dropped
+    snap_zone: Option<Rectangle<i32, Logical>>,
+}
+
+impl MoveSurfaceGrab {
+    pub fn start(
+        state: &mut State,
+        start_data: PointerGrabStartData<State>,
+        window: Window,
+        initial_window_location: Point<i32, Logical>,
+    ) -> Self {
+        let mut initial_window_location = initial_window_location;
+
+        // Dragging a snapped window brings its previous size back,
+        // keep the pointer at the same relative position of the window
+        if let Some(size) = state.unsnap_window(&window) {
+            let current = window.geometry().size;
+
+            if current.w > 0 {
+                let offset = start_data.location.x - initial_window_location.x as f64;
+                let offset = offset * size.w as f64 / current.w as f64;
+                initial_window_location.x = (start_data.location.x - offset).round() as i32;
+            }
+        }
+
+        Self {
+            start_data,
+            window,
+            initial_window_location,
+            snap_zone: None,
+        }
+    }
 }
 
 impl PointerGrab<State> for MoveSurfaceGrab {
@@ -28,9 +68,21 @@ impl PointerGrab<State> for MoveSurfaceGrab {
 
         let delta = event.location - self.start_data.location;
         let new_location = self.initial_window_location.to_f64() + delta;
+
+        let rect =
+            Rectangle::from_loc_and_size(new_location.to_i32_round(), self.window.geometry().size);
+        let new_location = snap_to_edges(state, &self.window, rect);
+
         state
             .space
-            .map_window(&self.window, new_location.to_i32_round(), None, true);
+            .map_window(&self.window, new_location, None, true);
+
+        let snap_zone = drop_zone(state, event.location);
+
+        if snap_zone != self.snap_zone {
+            self.snap_zone = snap_zone;
+            state.snap_preview = snap_zone;
+        }
     }
 
     fn button(
@@ -41,13 +93,16 @@ impl PointerGrab<State> for MoveSurfaceGrab {
     ) {
         handle.button(state, event);
 
-        // The button is a button code as defined in the
-        // Linux kernel's linux/input-event-codes.h header file, e.g. BTN_LEFT.
-        const BTN_LEFT: u32 = 0x110;
-
-        if !handle.current_pressed().contains(&BTN_LEFT) {
-            // No more buttons are pressed, release the grab.
+        if !handle.current_pressed().contains(&self.start_data.button) {
+            // The button that started the grab got released
             handle.unset_grab(state, event.serial, event.time);
+
+            state.snap_preview = None;
+
+            match self.snap_zone.take() {
+                Some(zone) => state.snap_window(&self.window, zone),
+                None => state.floating_window_dropped(&self.window),
+            }
         }
     }
 
@@ -64,3 +119,283 @@ impl PointerGrab<State> for MoveSurfaceGrab {
         &self.start_data
     }
 }
+
+/// Location of the window after sticking its edges to output borders and neighbouring windows
+fn snap_to_edges(
+    state: &State,
+    window: &Window,
+    rect: Rectangle<i32, Logical>,
+) -> Point<i32, Logical> {
+    // Edges the window can stick to, both sides of each target rectangle
+    let mut targets: Vec<Rectangle<i32, Logical>> = state
+        .space
+        .outputs()
+        .filter_map(|output| state.usable_area(output))
+        .collect();
+
+    targets.extend(
+        state
+            .space
+            .windows()
+            .filter(|w| *w != window)
+            .filter_map(|w| {
+                let loc = state.space.window_location(w)?;
+                Some(Rectangle::from_loc_and_size(loc, w.geometry().size))
+            }),
+    );
+
+    snapped_location(rect, &targets)
+}
+
+/// Location of `rect` after sticking its edges to the closest edges of `targets`
+fn snapped_location(
+    rect: Rectangle<i32, Logical>,
+    targets: &[Rectangle<i32, Logical>],
+) -> Point<i32, Logical> {
+    let mut best_x: Option<i32> = None;
+    let mut best_y: Option<i32> = None;
+
+    for target in targets {
+        // Only stick to edges the window is actually next to
+        let overlaps_y = rect.loc.y < target.loc.y + target.size.h + SNAP_DISTANCE
+            && target.loc.y < rect.loc.y + rect.size.h + SNAP_DISTANCE;
+        let overlaps_x = rect.loc.x < target.loc.x + target.size.w + SNAP_DISTANCE
+            && target.loc.x < rect.loc.x + rect.size.w + SNAP_DISTANCE;
+
+        if overlaps_y {
+            for edge in [target.loc.x, target.loc.x + target.size.w] {
+                for offset in [edge - rect.loc.x, edge - (rect.loc.x + rect.size.w)] {
+                    if closer(offset, best_x) {
+                        best_x = Some(offset);
+                    }
+                }
+            }
+        }
+
+        if overlaps_x {
+            for edge in [target.loc.y, target.loc.y + target.size.h] {
+                for offset in [edge - rect.loc.y, edge - (rect.loc.y + rect.size.h)] {
+                    if closer(offset, best_y) {
+                        best_y = Some(offset);
+                    }
+                }
+            }
+        }
+    }
+
+    rect.loc + Point::from((best_x.unwrap_or(0), best_y.unwrap_or(0)))
+}
+
+fn closer(offset: i32, best: Option<i32>) -> bool {
+    offset.abs() <= SNAP_DISTANCE && best.map(|best| offset.abs() < best.abs()).unwrap_or(true)
+}
+
+/// Half or quarter of the output the pointer is at the edge or corner of
+fn drop_zone(state: &State, pointer: Point<f64, Logical>) -> Option<Rectangle<i32, Logical>> {
+    let output = state.space.output_under(pointer).next()?;
+    let area = state.usable_area(output)?;
+    let geometry = state.space.output_geometry(output)?;
+
+    let outputs: Vec<Rectangle<i32, Logical>> = state
+        .space
+        .outputs()
+        .filter_map(|output| state.space.output_geometry(output))
+        .collect();
+
+    zone_at(pointer, geometry, area, &outputs)
+}
+
+/// Drop zone of the output with `geometry` and usable `area`, `outputs` are geometries of all outputs
+fn zone_at(
+    pointer: Point<f64, Logical>,
+    geometry: Rectangle<i32, Logical>,
+    area: Rectangle<i32, Logical>,
+    outputs: &[Rectangle<i32, Logical>],
+) -> Option<Rectangle<i32, Logical>> {
+    let x = pointer.x - geometry.loc.x as f64;
+    let y = pointer.y - geometry.loc.y as f64;
+    let w = geometry.size.w as f64;
+    let h = geometry.size.h as f64;
+
+    // Borders shared with another output are not drop zones, the pointer just crosses them
+    let outer = |dx: f64, dy: f64| {
+        let beyond = pointer + Point::from((dx, dy));
+        !outputs
+            .iter()
+            .any(|output| output.to_f64().contains(beyond))
+    };
+
+    let left = x < ZONE_SIZE && outer(-ZONE_SIZE, 0.0);
+    let right = x >= w - ZONE_SIZE && outer(ZONE_SIZE, 0.0);
+    let top = y < ZONE_SIZE && outer(0.0, -ZONE_SIZE);
+    let bottom = y >= h - ZONE_SIZE && outer(0.0, ZONE_SIZE);
+
+    if !(left || right || top || bottom) {
+        return None;
+    }
+
+    // Corner zones extend along the borders
+    let near_left = x < CORNER_SIZE;
+    let near_right = x >= w - CORNER_SIZE;
+    let near_top = y < CORNER_SIZE;
+    let near_bottom = y >= h - CORNER_SIZE;
+
+    let half_w = area.size.w / 2;
+    let half_h = area.size.h / 2;
+
+    let (left_half, right_half) = (
+        Rectangle::from_loc_and_size(area.loc, (half_w, area.size.h)),
+        Rectangle::from_loc_and_size(
+            (area.loc.x + half_w, area.loc.y),
+            (area.size.w - half_w, area.size.h),
+        ),
+    );
+
+    let top_of = |rect: Rectangle<i32, Logical>| {
+        Rectangle::from_loc_and_size(rect.loc, (rect.size.w, half_h))
+    };
+    let bottom_of = |rect: Rectangle<i32, Logical>| {
+        Rectangle::from_loc_and_size(
+            (rect.loc.x, rect.loc.y + half_h),
+            (rect.size.w, rect.size.h - half_h),
+        )
+    };
+
+    let zone = if (left && near_top) || (top && near_left) {
+        top_of(left_half)
+    } else if (right && near_top) || (top && near_right) {
+        top_of(right_half)
+    } else if (left && near_bottom) || (bottom && near_left) {
+        bottom_of(left_half)
+    } else if (right && near_bottom) || (bottom && near_right) {
+        bottom_of(right_half)
+    } else if left {
+        left_half
+    } else if right {
+        right_half
+    } else if top {
+        top_of(area)
+    } else {
+        bottom_of(area)
+    };
+
+    Some(zone)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+        Rectangle::from_loc_and_size((x, y), (w, h))
+    }
+
+    /// Two 1920x1080 outputs next to each other
+    fn dual_outputs() -> Vec<Rectangle<i32, Logical>> {
+        vec![rect(0, 0, 1920, 1080), rect(1920, 0, 1920, 1080)]
+    }
+
+    fn zone(x: f64, y: f64) -> Option<Rectangle<i32, Logical>> {
+        let outputs = dual_outputs();
+        let pointer = Point::from((x, y));
+        let geometry = *outputs
+            .iter()
+            .find(|output| output.to_f64().contains(pointer))
+            .unwrap();
+
+        zone_at(pointer, geometry, geometry, &outputs)
+    }
+
+    #[test]
+    fn zone_halves_at_outer_edges() {
+        assert_eq!(zone(2.0, 500.0), Some(rect(0, 0, 960, 1080)));
+        assert_eq!(zone(3835.0, 500.0), Some(rect(2880, 0, 960, 1080)));
+        assert_eq!(zone(500.0, 2.0), Some(rect(0, 0, 1920, 540)));
+        assert_eq!(zone(500.0, 1078.0), Some(rect(0, 540, 1920, 540)));
+    }
+
+    #[test]
+    fn zone_quarters_at_outer_corners() {
+        assert_eq!(zone(2.0, 2.0), Some(rect(0, 0, 960, 540)));
+        assert_eq!(zone(2.0, 1030.0), Some(rect(0, 540, 960, 540)));
+        assert_eq!(zone(3835.0, 40.0), Some(rect(2880, 0, 960, 540)));
+        assert_eq!(zone(3800.0, 1078.0), Some(rect(2880, 540, 960, 540)));
+    }
+
+    #[test]
+    fn no_zone_at_shared_edge() {
+        assert_eq!(zone(1915.0, 500.0), None);
+        assert_eq!(zone(1922.0, 500.0), None);
+    }
+
+    #[test]
+    fn zone_quarters_next_to_shared_edge() {
+        // The shared border is not a zone, but the outer one along it still has corners
+        assert_eq!(zone(1915.0, 2.0), Some(rect(960, 0, 960, 540)));
+        assert_eq!(zone(1922.0, 1078.0), Some(rect(1920, 540, 960, 540)));
+        // Corners only extend along the outer border
+        assert_eq!(zone(1915.0, 40.0), None);
+    }
+
+    #[test]
+    fn zones_use_usable_area() {
+        let outputs = dual_outputs();
+        // Panel at the top of the first output
+        let area = rect(0, 30, 1920, 1050);
+
+        assert_eq!(
+            zone_at((2.0, 2.0).into(), outputs[0], area, &outputs),
+            Some(rect(0, 30, 960, 525))
+        );
+        assert_eq!(
+            zone_at((2.0, 500.0).into(), outputs[0], area, &outputs),
+            Some(rect(0, 30, 960, 1050))
+        );
+    }
+
+    #[test]
+    fn snap_to_output_edges() {
+        let output = [rect(0, 0, 1920, 1080)];
+
+        assert_eq!(
+            snapped_location(rect(10, 300, 400, 300), &output),
+            (0, 300).into()
+        );
+        assert_eq!(
+            snapped_location(rect(1510, 770, 400, 300), &output),
+            (1520, 780).into()
+        );
+        // Too far away to stick
+        assert_eq!(
+            snapped_location(rect(20, 300, 400, 300), &output),
+            (20, 300).into()
+        );
+    }
+
+    #[test]
+    fn snap_to_closest_edge() {
+        let targets = [rect(0, 0, 1920, 1080), rect(1000, 200, 500, 500)];
+
+        // 10px from the window on the right, 12px from its left edge when overlapping it
+        assert_eq!(
+            snapped_location(rect(590, 300, 400, 300), &targets),
+            (600, 300).into()
+        );
+        // Closer to the output edge than to the other window
+        assert_eq!(
+            snapped_location(rect(4, 300, 990, 300), &targets),
+            (0, 300).into()
+        );
+    }
+
+    #[test]
+    fn snap_only_to_adjacent_windows() {
+        let targets = [rect(0, 0, 1920, 1080), rect(1000, 200, 500, 200)];
+
+        // Next to the window horizontally, but far below it
+        assert_eq!(
+            snapped_location(rect(590, 700, 400, 300), &targets),
+            (590, 700).into()
+        );
+    }
+}
@@ -9,12 +9,11 @@ use smithay::{
         wayland_server::protocol::wl_surface::WlSurface,
     },
     utils::{Logical, Point, Rectangle, Size},
-    wayland::{compositor, shell::xdg::SurfaceCachedState},
 };
 
 use crate::{
     data::surface::{ResizeEdge, ResizeSurfaceState},
-    State,
+    tiling, State,
 };
 
 pub struct ResizeSurfaceGrab {
@@ -36,6 +35,14 @@ impl ResizeSurfaceGrab {
     ) -> Self {
         let initial_rect = initial_window_rect;
 
+        if let Kind::Xdg(xdg) = window.toplevel() {
+            xdg.with_pending_state(|state| {
+                state.states.set(xdg_toplevel::State::Resizing);
+            });
+
+            xdg.send_configure();
+        }
+
         ResizeSurfaceState::for_surface(window.toplevel().wl_surface(), |state| {
             *state = ResizeSurfaceState::Resizing {
                 edges,
@@ -53,6 +60,23 @@ impl ResizeSurfaceGrab {
     }
 }
 
+impl ResizeSurfaceGrab {
+    /// Location of the window at the last size, the edges opposite to the grabbed ones stay in place
+    #[cfg(feature = "xwayland")]
+    fn window_location(&self) -> Point<i32, Logical> {
+        let mut location = self.initial_rect.loc;
+
+        if self.edges.intersects(ResizeEdge::LEFT) {
+            location.x += self.initial_rect.size.w - self.last_window_size.w;
+        }
+        if self.edges.intersects(ResizeEdge::TOP) {
+            location.y += self.initial_rect.size.h - self.last_window_size.h;
+        }
+
+        location
+    }
+}
+
 impl PointerGrab<State> for ResizeSurfaceGrab {
     fn motion(
         &mut self,
@@ -84,11 +108,7 @@ impl PointerGrab<State> for ResizeSurfaceGrab {
             new_window_height = (self.initial_rect.size.h as f64 + delta.y) as i32;
         }
 
-        let (min_size, max_size) =
-            compositor::with_states(self.window.toplevel().wl_surface(), |states| {
-                let data = states.cached_state.current::<SurfaceCachedState>();
-                (data.min_size, data.max_size)
-            });
+        let (min_size, max_size) = tiling::size_constraints(&self.window);
 
         let min_width = min_size.w.max(1);
         let min_height = min_size.h.max(1);
@@ -101,13 +121,24 @@ impl PointerGrab<State> for ResizeSurfaceGrab {
             new_window_height.max(min_height).min(max_height),
         ));
 
-        if let Kind::Xdg(xdg) = self.window.toplevel() {
-            xdg.with_pending_state(|state| {
-                state.states.set(xdg_toplevel::State::Resizing);
-                state.size = Some(self.last_window_size);
-            });
+        match self.window.toplevel() {
+            Kind::Xdg(xdg) => {
+                xdg.with_pending_state(|state| {
+                    state.states.set(xdg_toplevel::State::Resizing);
+                    state.size = Some(self.last_window_size);
+                });
 
-            xdg.send_configure();
+                xdg.send_configure();
+            }
+            #[cfg(feature = "xwayland")]
+            Kind::X11(_) => {
+                crate::xwayland::resize_window(
+                    state,
+                    &self.window,
+                    Some(self.window_location()),
+                    self.last_window_size,
+                );
+            }
         }
     }
 
@@ -119,12 +150,8 @@ impl PointerGrab<State> for ResizeSurfaceGrab {
     ) {
         handle.button(state, event);
 
-        // The button is a button code as defined in the
-        // Linux kernel's linux/input-event-codes.h header file, e.g. BTN_LEFT.
-        const BTN_LEFT: u32 = 0x110;
-
-        if !handle.current_pressed().contains(&BTN_LEFT) {
-            // No more buttons are pressed, release the grab.
+        if !handle.current_pressed().contains(&self.start_data.button) {
+            // The button that started the grab got released
             handle.unset_grab(state, event.serial, event.time);
 
             if let Kind::Xdg(xdg) = self.window.toplevel() {
@@ -134,14 +161,14 @@ impl PointerGrab<State> for ResizeSurfaceGrab {
                 });
 
                 xdg.send_configure();
-
-                ResizeSurfaceState::for_surface(xdg.wl_surface(), |state| {
-                    *state = ResizeSurfaceState::WaitingForLastCommit {
-                        edges: self.edges,
-                        initial_rect: self.initial_rect,
-                    };
-                });
             }
+
+            ResizeSurfaceState::for_surface(self.window.toplevel().wl_surface(), |state| {
+                *state = ResizeSurfaceState::WaitingForLastCommit {
+                    edges: self.edges,
+                    initial_rect: self.initial_rect,
+                };
+            });
         }
     }
 
@@ -0,0 +1,26 @@
+use anodium_protocol::{
+    delegate_anodium_protocol,
+    server::{AnodiumProtocol, AnodiumProtocolHandler},
+};
+
+use crate::State;
+
+impl AnodiumProtocolHandler for State {
+    fn anodium_protocol(&mut self) -> &mut AnodiumProtocol {
+        &mut self.anodium_protocol
+    }
+}
+
+delegate_anodium_protocol!(State);
+
+impl State {
+    /// Tell status bars what changed since the last call, should be called once per loop iteration
+    pub fn refresh_anodium_protocol(&mut self) {
+        let outputs = self.describe_workspaces();
+
+        self.anodium_protocol
+            .set_outputs::<State>(&self.display, outputs);
+        self.anodium_protocol
+            .set_binding_mode(self.keybindings.active_mode());
+    }
+}
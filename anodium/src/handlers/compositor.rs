@@ -20,6 +20,8 @@ impl CompositorHandler for State {
         on_commit_buffer_handler(surface);
 
         self.space.commit(surface);
+        self.popups.commit(surface);
+        self.layer_commit(surface);
         resize_grab::handle_commit(&mut self.space, surface);
 
         #[cfg(feature = "xwayland")]
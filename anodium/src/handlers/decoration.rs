@@ -0,0 +1,77 @@
+use smithay::{
+    delegate_xdg_decoration,
+    desktop::WindowSurfaceType,
+    reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode as DecorationMode,
+    wayland::{
+        compositor,
+        shell::xdg::{decoration::XdgDecorationHandler, ToplevelSurface, XdgToplevelSurfaceData},
+    },
+};
+
+use crate::{data::window::WindowState, State};
+
+impl XdgDecorationHandler for State {
+    fn new_decoration(&mut self, toplevel: ToplevelSurface) {
+        self.set_decoration_mode(&toplevel, DecorationMode::ServerSide);
+    }
+
+    fn request_mode(&mut self, toplevel: ToplevelSurface, mode: DecorationMode) {
+        self.set_decoration_mode(&toplevel, mode);
+    }
+
+    fn unset_mode(&mut self, toplevel: ToplevelSurface) {
+        self.set_decoration_mode(&toplevel, DecorationMode::ServerSide);
+    }
+}
+
+impl State {
+    /// Answer the decoration mode preferred by the client, unless server-side ones are disabled
+    fn set_decoration_mode(&mut self, toplevel: &ToplevelSurface, mode: DecorationMode) {
+        let mode = if self.config.decorations().theme().server_side {
+            mode
+        } else {
+            DecorationMode::ClientSide
+        };
+
+        toplevel.with_pending_state(|state| {
+            state.decoration_mode = Some(mode);
+        });
+
+        let window = self
+            .space
+            .window_for_surface(toplevel.wl_surface(), WindowSurfaceType::TOPLEVEL)
+            .cloned();
+
+        if let Some(window) = window.as_ref() {
+            WindowState::for_window(window)
+                .set_server_side_decorations(mode == DecorationMode::ServerSide);
+        }
+
+        let initial_configure_sent = compositor::with_states(toplevel.wl_surface(), |states| {
+            states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap()
+                .initial_configure_sent
+        });
+
+        // The initial configure is going to carry the mode
+        if !initial_configure_sent {
+            return;
+        }
+
+        // Decorations take space from tiled windows
+        let output = window
+            .filter(|window| self.is_tiled(window))
+            .and_then(|window| self.output_for_window(&window));
+
+        match output {
+            Some(output) => self.arrange_output(&output),
+            None => toplevel.send_configure(),
+        }
+    }
+}
+
+delegate_xdg_decoration!(State);
@@ -4,15 +4,19 @@ use smithay::{
         AbsolutePositionEvent, ButtonState, Event, InputEvent, KeyState, KeyboardKeyEvent,
         PointerButtonEvent, PointerMotionEvent,
     },
-    desktop::{self, WindowSurfaceType},
-    input::{
-        keyboard::{keysyms as xkb, FilterResult},
-        pointer::{ButtonEvent, Focus, GrabStartData, MotionEvent, PointerHandle},
-    },
-    utils::{Logical, Point, SERIAL_COUNTER},
+    desktop,
+    input::pointer::{ButtonEvent, Focus, GrabStartData, MotionEvent, PointerHandle},
+    utils::{Logical, Point, Rectangle, Serial, SERIAL_COUNTER},
 };
 
-use crate::{data::seat::SeatState, grabs::MoveSurfaceGrab, CalloopData, State};
+use crate::{
+    data::{seat::SeatState, surface::ResizeEdge, window::WindowState},
+    decorations::DecorationPart,
+    grabs::{MoveSurfaceGrab, ResizeSurfaceGrab},
+    layers::{LOWER_LAYERS, UPPER_LAYERS},
+    mousebindings::{MouseAction, BTN_LEFT},
+    CalloopData, State,
+};
 
 impl InputHandler for CalloopData {
     fn process_input_event<I: smithay::backend::input::InputBackend>(
@@ -31,42 +35,56 @@ impl InputHandler for CalloopData {
             InputEvent::Keyboard { event } => {
                 let keyboard = self.state.seat.get_keyboard().unwrap();
 
+                let keycode = event.key_code();
                 let key_state = event.state();
 
-                keyboard.input::<(), _>(
+                let binding = keyboard.input(
                     &mut self.state,
-                    event.key_code(),
-                    event.state(),
+                    keycode,
+                    key_state,
                     SERIAL_COUNTER.next_serial(),
                     event.time(),
                     |state, modifiers, handle| {
                         let keysym = handle.modified_sym();
 
-                        SeatState::for_seat(&state.seat).update_pressed_keys(keysym, key_state);
-
-                        if keysym == xkb::KEY_Escape {
-                            state.loop_signal.stop();
-                        }
+                        SeatState::for_seat(&state.seat).set_modifiers(modifiers.into());
 
-                        if keysym == xkb::KEY_t
-                            && modifiers.alt
-                            && event.state() == KeyState::Pressed
-                        {
-                            std::process::Command::new("weston-terminal").spawn().ok();
+                        // Match both the translated keysym and the raw ones,
+                        // so that eg. `shift + 1` still matches the `1` key
+                        let mut keysyms = handle.raw_syms().to_vec();
+                        keysyms.push(keysym);
 
-                            FilterResult::Intercept(())
-                        } else if keysym == xkb::KEY_g
-                            && modifiers.alt
-                            && event.state() == KeyState::Pressed
-                        {
-                            std::process::Command::new("gtk4-demo").spawn().ok();
-
-                            FilterResult::Intercept(())
-                        } else {
-                            FilterResult::Forward
-                        }
+                        state.keybindings.on_key(
+                            &state._loop_handle,
+                            keycode,
+                            key_state,
+                            modifiers,
+                            &keysyms,
+                        )
                     },
                 );
+
+                if key_state == KeyState::Released {
+                    self.state
+                        .keybindings
+                        .on_key_released(&self.state._loop_handle, keycode);
+                }
+
+                // Actions are executed outside of the input filter,
+                // as they are free to change the keyboard focus
+                if let Some(Some(binding)) = binding {
+                    if binding.repeat {
+                        self.state.keybindings.start_repeat(
+                            &self.state._loop_handle,
+                            keycode,
+                            binding.action.clone(),
+                        );
+                    }
+
+                    self.state.run_action(binding.action);
+                }
+
+                self.state.update_switcher();
             }
             InputEvent::PointerMotion { event } => {
                 let pointer = self.state.seat.get_pointer().unwrap();
@@ -74,20 +92,25 @@ impl InputHandler for CalloopData {
 
                 let mut position = seat_state.pointer_pos() + event.delta();
 
-                let max_x = self.state.space.outputs().fold(0, |acc, o| {
-                    acc + self.state.space.output_geometry(o).unwrap().size.w
-                });
+                // Outputs don't have to form a rectangle, so if the pointer left all of them
+                // keep it on the output it was on before
+                if self.state.space.output_under(position).next().is_none() {
+                    let space = &self.state.space;
 
-                let max_y = self
-                    .state
-                    .space
-                    .outputs()
-                    .next()
-                    .map(|o| self.state.space.output_geometry(o).unwrap().size.h)
-                    .unwrap_or_default();
+                    let geometry = space
+                        .output_under(seat_state.pointer_pos())
+                        .next()
+                        .or_else(|| space.outputs().next())
+                        .and_then(|o| space.output_geometry(o));
 
-                position.x = position.x.max(0.0).min(max_x as f64 - 1.0);
-                position.y = position.y.max(0.0).min(max_y as f64 - 1.0);
+                    if let Some(geometry) = geometry {
+                        let (x, y) = (geometry.loc.x as f64, geometry.loc.y as f64);
+                        let (w, h) = (geometry.size.w as f64, geometry.size.h as f64);
+
+                        position.x = position.x.max(x).min(x + w - 1.0);
+                        position.y = position.y.max(y).min(y + h - 1.0);
+                    }
+                }
 
                 seat_state.set_pointer_pos(position);
                 self.state.pointer_motion(pointer, position, event.time());
@@ -116,55 +139,74 @@ impl InputHandler for CalloopData {
 
                 let seat_state = SeatState::for_seat(&self.state.seat);
                 let pointer_pos = seat_state.pointer_pos();
-                let is_alt_pressed = seat_state.is_key_pressed(xkb::KEY_Alt_L);
-
-                if ButtonState::Pressed == button_state {
-                    let window_under = self.state.space.window_under(pointer_pos).cloned();
-
-                    if !pointer.is_grabbed() {
-                        if let Some(window) = window_under {
-                            activate_and_brind_to_top(&mut self.state.space, &window);
-
-                            keyboard.set_focus(
-                                &mut self.state,
-                                Some(window.toplevel().wl_surface().clone()),
-                                serial,
-                            );
-
-                            // Check for compositor initiated move grab
-                            if is_alt_pressed {
-                                let start_data = GrabStartData {
-                                    focus: None,
-                                    button,
-                                    location: pointer_pos,
-                                };
-
-                                let initial_window_location =
-                                    self.state.space.window_location(&window).unwrap();
-
-                                let grab = MoveSurfaceGrab {
-                                    start_data,
-                                    window,
-                                    initial_window_location,
-                                };
-
-                                pointer.set_grab(&mut self.state, grab, serial, Focus::Clear);
-
-                                // Return early, we don't want to send button event to this window/surface
-                                return;
-                            }
-                        } else {
-                            self.state.space.windows().for_each(|window| {
-                                window.set_activated(false);
+                let modifiers = seat_state.modifiers();
+
+                match button_state {
+                    ButtonState::Pressed if !pointer.is_grabbed() => {
+                        let decoration_under = self.state.decoration_under(pointer_pos);
+                        let window_under = self
+                            .state
+                            .space
+                            .window_under(pointer_pos)
+                            .cloned()
+                            .or_else(|| {
+                                decoration_under.as_ref().map(|(window, _)| window.clone())
+                            });
 
-                                // TODO: Remove once smithay supports xwayland
-                                if let desktop::Kind::Xdg(_) = window.toplevel() {
-                                    window.configure();
+                        let action = self
+                            .state
+                            .mousebindings
+                            .on_button_pressed(modifiers, button);
+
+                        if let Some(action) = action {
+                            match (action, window_under) {
+                                (MouseAction::Action(action), _) => self.state.run_action(action),
+                                (action, Some(window)) => {
+                                    self.state.run_mouse_action(action, window, button, serial)
                                 }
-                            });
-                            keyboard.set_focus(&mut self.state, None, serial);
+                                (_, None) => self.state.mousebindings.intercept_release(button),
+                            }
+
+                            // Return early, we don't want to send button event to this window/surface
+                            return;
+                        }
+
+                        // Panels and launchers above windows take the click
+                        if let Some(layer) = self.state.layer_under(pointer_pos, &UPPER_LAYERS) {
+                            self.state.focus_layer(&layer);
+                        } else if let Some((window, part)) = decoration_under {
+                            self.state.decoration_pressed(window, part, button, serial);
+                            return;
+                        } else if let Some(window) = window_under {
+                            if WindowState::for_window(&window).is_no_focus() {
+                                self.state.space.raise_window(&window, false);
+                                self.state.raise_floating_windows();
+                            } else {
+                                activate_and_brind_to_top(&mut self.state.space, &window);
+                                self.state.raise_floating_windows();
+
+                                keyboard.set_focus(
+                                    &mut self.state,
+                                    Some(window.toplevel().wl_surface().clone()),
+                                    serial,
+                                );
+                            }
+                        } else if let Some(layer) =
+                            self.state.layer_under(pointer_pos, &LOWER_LAYERS)
+                        {
+                            if !self.state.focus_layer(&layer) {
+                                self.state.unfocus_windows();
+                            }
+                        } else {
+                            self.state.unfocus_windows();
                         }
-                    };
+                    }
+                    ButtonState::Released => {
+                        if self.state.mousebindings.on_button_released(button) {
+                            return;
+                        }
+                    }
+                    _ => {}
                 }
 
                 pointer.button(
@@ -189,6 +231,12 @@ impl InputHandler for CalloopData {
 }
 
 fn activate_and_brind_to_top(space: &mut desktop::Space, window: &desktop::Window) {
+    space.raise_window(window, true);
+    activate(space, window);
+}
+
+/// Make the window the only activated one, without changing the stacking order
+fn activate(space: &desktop::Space, window: &desktop::Window) {
     space.windows().filter(|w| *w != window).for_each(|window| {
         window.set_activated(false);
 
@@ -198,7 +246,6 @@ fn activate_and_brind_to_top(space: &mut desktop::Space, window: &desktop::Windo
         }
     });
 
-    space.raise_window(window, true);
     window.set_activated(true);
 
     // TODO: Remove once smithay supports xwayland
@@ -208,16 +255,155 @@ fn activate_and_brind_to_top(space: &mut desktop::Space, window: &desktop::Windo
 }
 
 impl State {
+    /// Give keyboard focus to the window and raise it
+    pub fn focus_window(&mut self, window: &desktop::Window) {
+        self.unminimize_window(window);
+
+        // Windows excluded from focus by a window rule only get raised
+        if WindowState::for_window(window).is_no_focus() {
+            self.space.raise_window(window, false);
+            self.raise_floating_windows();
+            return;
+        }
+
+        activate_and_brind_to_top(&mut self.space, window);
+        self.raise_floating_windows();
+
+        let keyboard = self.seat.get_keyboard().unwrap();
+        keyboard.set_focus(
+            self,
+            Some(window.toplevel().wl_surface().clone()),
+            SERIAL_COUNTER.next_serial(),
+        );
+    }
+
+    /// Give keyboard focus to the window, but keep it where it is in the stacking order
+    pub fn focus_window_without_raise(&mut self, window: &desktop::Window) {
+        activate(&self.space, window);
+
+        let keyboard = self.seat.get_keyboard().unwrap();
+        keyboard.set_focus(
+            self,
+            Some(window.toplevel().wl_surface().clone()),
+            SERIAL_COUNTER.next_serial(),
+        );
+    }
+
+    /// Run mouse binding action on the window under the pointer
+    fn run_mouse_action(
+        &mut self,
+        action: MouseAction,
+        window: desktop::Window,
+        button: u32,
+        serial: Serial,
+    ) {
+        let pointer = self.seat.get_pointer().unwrap();
+
+        let start_data = GrabStartData {
+            focus: None,
+            button,
+            location: SeatState::for_seat(&self.seat).pointer_pos(),
+        };
+
+        // Tiled windows are placed by the layout, the client never saw the press
+        if action.is_grab() && self.is_tiled(&window) {
+            self.focus_window(&window);
+            self.mousebindings.intercept_release(button);
+            return;
+        }
+
+        match action {
+            MouseAction::Move => {
+                self.focus_window(&window);
+
+                let initial_window_location = self.space.window_location(&window).unwrap();
+
+                let grab =
+                    MoveSurfaceGrab::start(self, start_data, window, initial_window_location);
+
+                pointer.set_grab(self, grab, serial, Focus::Clear);
+            }
+            MouseAction::Resize => {
+                self.focus_window(&window);
+
+                let initial_window_location = self.space.window_location(&window).unwrap();
+                let initial_window_size = window.geometry().size;
+                let initial_window_rect =
+                    Rectangle::from_loc_and_size(initial_window_location, initial_window_size);
+
+                // Resize by the corner closest to the pointer
+                let edges = ResizeEdge::from_quadrant(initial_window_rect, start_data.location);
+
+                let grab = ResizeSurfaceGrab::start(start_data, window, edges, initial_window_rect);
+
+                pointer.set_grab(self, grab, serial, Focus::Clear);
+            }
+            MouseAction::Raise => {
+                self.focus_window(&window);
+            }
+            MouseAction::Lower => {
+                self.lower_window(&window);
+            }
+            MouseAction::Close => {
+                self.close_window(&window);
+            }
+            MouseAction::Action(action) => {
+                self.run_action(action);
+            }
+        }
+    }
+
+    /// Button press on the server-side decorations of the window
+    fn decoration_pressed(
+        &mut self,
+        window: desktop::Window,
+        part: DecorationPart,
+        button: u32,
+        serial: Serial,
+    ) {
+        match (button, part) {
+            (BTN_LEFT, DecorationPart::Titlebar) if !self.is_tiled(&window) => {
+                // The release ends the grab, so it is not intercepted
+                self.run_mouse_action(MouseAction::Move, window, button, serial);
+                return;
+            }
+            (BTN_LEFT, DecorationPart::Close) => self.close_window(&window),
+            (BTN_LEFT, DecorationPart::Maximize) => {
+                if WindowState::for_window(&window).is_maximized() {
+                    self.unmaximize_window(&window);
+                } else {
+                    self.maximize_window(&window);
+                }
+            }
+            (BTN_LEFT, DecorationPart::Minimize) => self.minimize_window(&window),
+            _ => self.focus_window(&window),
+        }
+
+        // Clients never saw the press
+        self.mousebindings.intercept_release(button);
+    }
+
+    /// Put the window below all the other ones
+    pub fn lower_window(&mut self, window: &desktop::Window) {
+        let others: Vec<desktop::Window> = self
+            .space
+            .windows()
+            .filter(|w| *w != window)
+            .cloned()
+            .collect();
+
+        for other in others {
+            self.space.raise_window(&other, false);
+        }
+    }
+
     fn pointer_motion(
         &mut self,
         pointer: PointerHandle<Self>,
         position: Point<f64, Logical>,
         time: u32,
     ) {
-        let under = self
-            .space
-            .surface_under(position, WindowSurfaceType::all())
-            .map(|(_, surface, location)| (surface, location));
+        let under = self.surface_under(position);
 
         pointer.motion(
             self,
@@ -228,5 +414,7 @@ impl State {
                 time,
             },
         );
+
+        self.focus_follows_mouse(position);
     }
 }
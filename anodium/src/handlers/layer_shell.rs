@@ -0,0 +1,48 @@
+use slog_scope::warn;
+use smithay::{
+    delegate_layer_shell,
+    desktop::{layer_map_for_output, LayerSurface},
+    output::Output,
+    reexports::wayland_server::protocol::wl_output::WlOutput,
+    wayland::shell::wlr_layer::{
+        Layer, LayerSurface as WlrLayerSurface, WlrLayerShellHandler, WlrLayerShellState,
+    },
+};
+
+use crate::State;
+
+impl WlrLayerShellHandler for State {
+    fn shell_state(&mut self) -> &mut WlrLayerShellState {
+        &mut self.layer_shell_state
+    }
+
+    fn new_layer_surface(
+        &mut self,
+        surface: WlrLayerSurface,
+        output: Option<WlOutput>,
+        _layer: Layer,
+        namespace: String,
+    ) {
+        // Without an output picked by the client the surface goes where the pointer is
+        let output = output
+            .as_ref()
+            .and_then(Output::from_resource)
+            .or_else(|| self.active_output());
+
+        let output = match output {
+            Some(output) => output,
+            None => {
+                surface.send_close();
+                return;
+            }
+        };
+
+        // Configured once the initial commit arrives, see `State::layer_commit`
+        let layer = LayerSurface::new(surface, namespace);
+        if let Err(err) = layer_map_for_output(&output).map_layer(&self.display, &layer) {
+            warn!("Failed to map layer surface: {}", err);
+        }
+    }
+}
+
+delegate_layer_shell!(State);
@@ -2,8 +2,11 @@ mod backend;
 mod input;
 mod output;
 
+mod anodium_protocol;
 mod compositor;
 mod data_device;
+mod decoration;
 mod dmabuf;
+mod layer_shell;
 mod seat;
 mod xdg;
@@ -1,71 +1,70 @@
+use std::{rc::Rc, time::Instant};
+
 use anodium_backend::{
     utils::cursor::PointerElement, NewOutputDescriptor, OutputHandler, OutputId,
 };
+use anodium_framework::quad::{QuadElement, QuadPipeline};
+use slog_scope::warn;
 use smithay::{
     backend::renderer::gles2::{Gles2Renderer, Gles2Texture},
     delegate_output,
-    desktop::space::SurfaceTree,
+    desktop::{layer_map_for_output, space::SurfaceTree},
     output::{Mode, Output},
+    reexports::wayland_server::backend::GlobalId,
+    utils::{Logical, Point},
 };
 
-use crate::{data::output::OutputState, CalloopData, State};
+use crate::{
+    animation::GhostElement,
+    data::output::OutputState,
+    decorations::DecorationElement,
+    output_layout::{self, OutputLayout},
+    render::WindowElement,
+    CalloopData, State,
+};
 
 smithay::custom_elements! {
     pub CustomElem<=Gles2Renderer>;
     SurfaceTree=SurfaceTree,
     PointerElement=PointerElement,
+    QuadElement=QuadElement,
+    WindowElement=WindowElement,
+    DecorationElement=DecorationElement,
+    GhostElement=GhostElement,
 }
 
+/// Translucent fill of the area a moved window is going to be snapped to
+const SNAP_PREVIEW_COLOR: [f32; 4] = [26.0 / 255.0, 95.0 / 255.0, 205.0 / 255.0, 0.3];
+/// Translucent fill of the window selected in the window switcher
+const SWITCHER_HIGHLIGHT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.2];
+
+/// Element ids of the overlays, kept apart from window ids, those never reach the high bits
+const SNAP_PREVIEW_ID: usize = 1 << (usize::BITS - 4);
+const SWITCHER_HIGHLIGHT_ID: usize = SNAP_PREVIEW_ID + 1;
+
 impl OutputHandler for CalloopData {
     fn output_created(&mut self, desc: NewOutputDescriptor) {
         let output = Output::new(desc.name.clone(), desc.physical_properties, None);
         output.set_preferred(desc.prefered_mode);
 
+        for mode in desc.possible_modes {
+            output.add_mode(mode);
+        }
+
         output.user_data().insert_if_missing(|| desc.id);
+        OutputState::for_output(&output).set_preferred_transform(desc.transform);
 
-        output.create_global::<State>(&self.display.handle());
+        let global = output.create_global::<State>(&self.display.handle());
+        output.user_data().insert_if_missing(|| global);
 
         output.change_current_state(Some(desc.prefered_mode), Some(desc.transform), None, None);
+        self.state.map_output(&output, (0, 0));
 
-        // if let Some(c) = layout.find_output(&desc.name) {
-        //     output.change_current_state(Some(c.mode()), Some(desc.transform), None, None);
-        //     self.backend
-        //         .update_mode(output.user_data().get::<OutputId>().unwrap(), &c.mode());
-        // } else {
-        //     output.change_current_state(Some(desc.prefered_mode), Some(desc.transform), None, None);
-        // }
-
-        let outputs: Vec<_> = self
-            .state
-            .space
-            .outputs()
-            .cloned()
-            .chain(std::iter::once(output))
-            .collect();
-
-        let mut x = 0;
-
-        // // Map all configured outputs first
-        // for desc in layout.iter() {
-        //     if let Some(id) = outputs.iter().position(|o| o.name() == desc.name()) {
-        //         let output = outputs.remove(id);
+        self.state.apply_output_layout();
 
-        //         let location = (x, 0).into();
-        //         self.space.map_output(&output, 1.0, location);
-
-        //         output.change_current_state(None, None, None, Some(location));
-
-        //         x += desc.mode().size.w;
-        //     }
-        // }
-
-        // Put unconfigured outputs on the end
-        for output in outputs.into_iter().rev() {
-            let location = (x, 0).into();
-            self.state.space.map_output(&output, location);
-            output.change_current_state(None, None, None, Some(location));
-
-            x += output.current_mode().unwrap().size.w;
+        // Windows left behind when the last output got unplugged
+        for workspaces in std::mem::take(&mut self.state.orphaned_workspaces) {
+            self.state.adopt_workspaces(workspaces, &output);
         }
     }
 
@@ -78,11 +77,40 @@ impl OutputHandler for CalloopData {
 
         if let Some(output) = output {
             output.change_current_state(Some(mode), None, None, None);
+            layer_map_for_output(output).arrange(&self.display.handle());
         }
     }
 
-    fn output_removed(&mut self, _output: &OutputId) {
-        // TODO:
+    fn output_removed(&mut self, output_id: &OutputId) {
+        let output = self
+            .state
+            .space
+            .outputs()
+            .find(|o| o.user_data().get::<OutputId>() == Some(output_id))
+            .cloned();
+
+        let output = match output {
+            Some(output) => output,
+            None => return,
+        };
+
+        for layer in layer_map_for_output(&output).layers() {
+            layer.layer_surface().send_close();
+        }
+
+        let workspaces = self.state.take_workspaces(&output);
+
+        self.state.unmap_output(&output);
+        if let Some(global) = output.user_data().get::<GlobalId>() {
+            self.display.handle().remove_global::<State>(global.clone());
+        }
+
+        self.state.apply_output_layout();
+
+        match self.state.active_output() {
+            Some(target) => self.state.adopt_workspaces(workspaces, &target),
+            None => self.state.orphaned_workspaces.push(workspaces),
+        }
     }
 
     fn output_render(
@@ -95,7 +123,67 @@ impl OutputHandler for CalloopData {
         Option<Vec<smithay::utils::Rectangle<i32, smithay::utils::Physical>>>,
         smithay::backend::SwapBuffersError,
     > {
+        let output = self
+            .state
+            .space
+            .outputs()
+            .find(|o| o.user_data().get::<OutputId>() == Some(output_id))
+            .unwrap()
+            .clone();
+
         let mut elems: Vec<CustomElem> = Vec::new();
+        let now = Instant::now();
+
+        let output_size = output
+            .current_mode()
+            .map(|mode| mode.size.to_f64())
+            .unwrap_or_default();
+
+        // Windows are always drawn by us, see `render`
+        let pipeline = quad_pipeline(renderer);
+        for window in self
+            .state
+            .window_elements(&output, pipeline, output_size, now)
+        {
+            if let Some(border) = window.border {
+                elems.push(border.into());
+            }
+
+            if let Some(decoration) = window.decoration {
+                elems.push(decoration.into());
+            }
+
+            elems.push(window.window.into());
+        }
+
+        for ghost in self.state.ghost_elements(now) {
+            elems.push(ghost.into());
+        }
+
+        // Below the pointer, elements are drawn in order
+        let overlays = [
+            (self.state.snap_preview, SNAP_PREVIEW_COLOR, SNAP_PREVIEW_ID),
+            (
+                self.state.switcher_highlight(),
+                SWITCHER_HIGHLIGHT_COLOR,
+                SWITCHER_HIGHLIGHT_ID,
+            ),
+        ];
+
+        for (rect, color, id) in overlays {
+            let rect = match rect {
+                Some(rect) => rect,
+                None => continue,
+            };
+
+            if let Some(pipeline) = quad_pipeline(renderer) {
+                elems.push(
+                    QuadElement::new(pipeline, rect, color, output_size)
+                        .with_id(id)
+                        .into(),
+                );
+            }
+        }
 
         let location = self
             .state
@@ -115,24 +203,20 @@ impl OutputHandler for CalloopData {
             elems.push(PointerElement::new(texture.clone(), location, false).into());
         }
 
-        let output = self
-            .state
-            .space
-            .outputs()
-            .find(|o| o.user_data().get::<OutputId>() == Some(output_id))
-            .unwrap()
-            .clone();
-
         let output_state = OutputState::for_output(&output);
         // let egui = output_state.egui_frame(&output, &self.start_time);
         // elems.push(egui.into());
 
         let render_result = self
             .state
-            .space
+            .render_space
             .render_output(renderer, &output, age, [0.1, 0.1, 0.1, 1.0], &elems)
             .unwrap();
 
+        // Textures are imported by now
+        let windows: Vec<_> = self.state.space.windows().cloned().collect();
+        self.state.capture_snapshots(renderer, &windows);
+
         if render_result.is_some() {
             output_state.fps_tick();
         }
@@ -163,7 +247,7 @@ impl OutputHandler for CalloopData {
 
         for output in self.state.space.outputs() {
             if output.user_data().get::<OutputId>() == Some(output_id) {
-                let map = smithay::desktop::layer_map_for_output(output);
+                let map = layer_map_for_output(output);
                 for layer in map.layers() {
                     layer.send_frame(time);
                 }
@@ -177,6 +261,115 @@ impl OutputHandler for CalloopData {
     }
 }
 
+/// Pipeline shared by all solid color elements drawn with the renderer, created on the first use
+///
+/// Every GPU has a renderer of its own, programs can't be shared between their contexts.
+fn quad_pipeline(renderer: &mut Gles2Renderer) -> Option<Rc<QuadPipeline>> {
+    let user_data = renderer.egl_context().user_data();
+
+    if let Some(pipeline) = user_data.get::<Rc<QuadPipeline>>() {
+        return Some(pipeline.clone());
+    }
+
+    let pipeline = renderer
+        .with_context(|_, gl| Rc::new(QuadPipeline::new(gl)))
+        .ok()?;
+
+    renderer
+        .egl_context()
+        .user_data()
+        .insert_if_missing(|| pipeline.clone());
+
+    Some(pipeline)
+}
+
+impl State {
+    /// Place the output in the layout, both for window management and rendering
+    fn map_output(&mut self, output: &Output, location: impl Into<Point<i32, Logical>>) {
+        let location = location.into();
+        self.space.map_output(output, location);
+        self.render_space.map_output(output, location);
+    }
+
+    fn unmap_output(&mut self, output: &Output) {
+        self.space.unmap_output(output);
+        self.render_space.unmap_output(output);
+    }
+
+    /// Apply configured modes, transforms, scales and positions to all outputs
+    ///
+    /// Configured outputs are placed first, in the order of the config,
+    /// unconfigured outputs are put after them.
+    pub fn apply_output_layout(&mut self) {
+        let mut outputs: Vec<_> = self.space.outputs().cloned().collect();
+
+        let mut ordered: Vec<(Output, Option<OutputLayout>)> = Vec::new();
+
+        for layout in self.config.outputs().layout() {
+            if let Some(id) = outputs.iter().position(|o| o.name() == layout.name()) {
+                ordered.push((outputs.remove(id), Some(layout)));
+            }
+        }
+
+        ordered.extend(outputs.into_iter().rev().map(|output| (output, None)));
+
+        let mut x = 0;
+
+        for (output, layout) in ordered {
+            let configured_mode = layout.as_ref().and_then(|layout| {
+                let mode = layout.select_mode(&output.modes());
+
+                if let (None, Some(requested)) = (mode, layout.mode) {
+                    warn!(
+                        "Output {} does not support mode {:?}, available modes: {:?}",
+                        layout.name(),
+                        requested,
+                        output.modes()
+                    );
+                }
+
+                mode
+            });
+
+            let mode = configured_mode
+                .or_else(|| output.preferred_mode())
+                .or_else(|| output.current_mode());
+
+            let transform = layout
+                .as_ref()
+                .and_then(|layout| layout.transform)
+                .or_else(|| OutputState::for_output(&output).preferred_transform())
+                .unwrap_or_else(|| output.current_transform());
+
+            let scale = layout.as_ref().and_then(|layout| layout.scale).unwrap_or(1);
+
+            let location = layout
+                .as_ref()
+                .and_then(|layout| layout.position)
+                .unwrap_or_else(|| (x, 0).into());
+
+            if mode != output.current_mode() {
+                if let (Some(mode), Some(id)) = (mode, output.user_data().get::<OutputId>()) {
+                    self.backend.update_mode(id, &mode);
+                }
+            }
+
+            output.change_current_state(mode, Some(transform), Some(scale), Some(location));
+            self.map_output(&output, location);
+            // Layer surfaces get resized to the new mode
+            layer_map_for_output(&output).arrange(&self.display);
+
+            let size = mode
+                .map(|mode| output_layout::logical_size(mode, transform, scale))
+                .unwrap_or_default();
+
+            x = x.max(location.x + size.w);
+        }
+
+        self.arrange_all_outputs();
+    }
+}
+
 //
 // Wl Output & Xdg Output
 //
@@ -1,11 +1,12 @@
 use smithay::{
     delegate_seat,
+    desktop::WindowSurfaceType,
     input::{Seat, SeatHandler, SeatState},
     reexports::wayland_server::{protocol::wl_surface::WlSurface, Resource},
     wayland::data_device,
 };
 
-use crate::State;
+use crate::{data, State};
 
 impl SeatHandler for State {
     type KeyboardFocus = WlSurface;
@@ -18,6 +19,15 @@ impl SeatHandler for State {
     fn focus_changed(&mut self, seat: &Seat<Self>, focused: Option<&Self::KeyboardFocus>) {
         let focus = focused.and_then(|s| self.display.get_client(s.id()).ok());
         data_device::set_data_device_focus(&self.display, seat, focus);
+
+        let window = focused.and_then(|surface| {
+            self.space
+                .window_for_surface(surface, WindowSurfaceType::TOPLEVEL)
+        });
+
+        if let Some(window) = window {
+            data::seat::SeatState::for_seat(seat).push_focus(window);
+        }
     }
 
     fn cursor_image(
@@ -9,10 +9,11 @@ use smithay::{
         pointer::{Focus, GrabStartData as PointerGrabStartData},
         Seat,
     },
+    output::Output,
     reexports::{
         wayland_protocols::xdg::shell::server::xdg_toplevel,
         wayland_server::{
-            protocol::{wl_seat, wl_surface::WlSurface},
+            protocol::{wl_output, wl_seat, wl_surface::WlSurface},
             Resource,
         },
     },
@@ -23,9 +24,8 @@ use smithay::{
 };
 
 use crate::{
-    data::seat::SeatState,
     grabs::{MoveSurfaceGrab, ResizeSurfaceGrab},
-    positioning, State,
+    State,
 };
 
 impl XdgShellHandler for State {
@@ -40,6 +40,10 @@ impl XdgShellHandler for State {
         self.space.map_window(&window, (0, 0), None, false);
 
         fn on_initial_commit(state: &mut State, window: Window, surface: &WlSurface) {
+            // In the tiling mode this already sets the initial size
+            state.add_window_to_workspace(&window);
+            state.apply_initial_window_state(&window);
+
             // Send initial configure
             window.configure();
 
@@ -48,9 +52,13 @@ impl XdgShellHandler for State {
                     with_renderer_surface_state(surface, |data| data.wl_buffer().is_some());
 
                 if buffer_attached {
-                    // Window got mapped so we can position it
-                    let pointer_pos = SeatState::for_seat(&state.seat).pointer_pos();
-                    positioning::position_window_center(&mut state.space, window, pointer_pos);
+                    // Window got mapped so we can position it, tiled, maximized
+                    // and fullscreen windows are already placed
+                    if !state.is_tiled(&window) && !state.has_fixed_geometry(&window) {
+                        state.place_window(&window);
+                    }
+
+                    state.animate_open(&window);
                 } else {
                     // Wait for nex commit
                     state
@@ -73,9 +81,9 @@ impl XdgShellHandler for State {
     fn new_popup(&mut self, surface: PopupSurface, positioner: PositionerState) {
         let wl_surface = surface.wl_surface().clone();
 
+        let geometry = self.popup_geometry(&surface, positioner);
         surface.with_pending_state(|state| {
-            // TODO: Proper positioning
-            state.geometry = positioner.get_geometry();
+            state.geometry = geometry;
         });
 
         self.popups
@@ -88,6 +96,21 @@ impl XdgShellHandler for State {
             });
     }
 
+    fn reposition_request(
+        &mut self,
+        surface: PopupSurface,
+        positioner: PositionerState,
+        token: u32,
+    ) {
+        let geometry = self.popup_geometry(&surface, positioner);
+        surface.with_pending_state(|state| {
+            state.geometry = geometry;
+            state.positioner = positioner;
+        });
+
+        surface.send_repositioned(token);
+    }
+
     fn grab(&mut self, surface: PopupSurface, seat: wl_seat::WlSeat, serial: Serial) {
         let seat: Seat<Self> = Seat::from_resource(&seat).unwrap();
 
@@ -136,13 +159,15 @@ impl XdgShellHandler for State {
                 .window_for_surface(wl_surface, WindowSurfaceType::TOPLEVEL)
                 .unwrap()
                 .clone();
+
+            // Tiled windows are placed by the layout
+            if self.is_tiled(&window) {
+                return;
+            }
+
             let initial_window_location = self.space.window_location(&window).unwrap();
 
-            let grab = MoveSurfaceGrab {
-                start_data,
-                window,
-                initial_window_location,
-            };
+            let grab = MoveSurfaceGrab::start(self, start_data, window, initial_window_location);
 
             pointer.set_grab(self, grab, serial, Focus::Clear);
         }
@@ -167,14 +192,13 @@ impl XdgShellHandler for State {
                 .window_for_surface(wl_surface, WindowSurfaceType::TOPLEVEL)
                 .unwrap()
                 .clone();
-            let initial_window_location = self.space.window_location(&window).unwrap();
-            let initial_window_size = window.geometry().size;
 
-            surface.with_pending_state(|state| {
-                state.states.set(xdg_toplevel::State::Resizing);
-            });
+            if self.is_tiled(&window) {
+                return;
+            }
 
-            surface.send_configure();
+            let initial_window_location = self.space.window_location(&window).unwrap();
+            let initial_window_size = window.geometry().size;
 
             let grab = ResizeSurfaceGrab::start(
                 start_data,
@@ -186,11 +210,60 @@ impl XdgShellHandler for State {
             pointer.set_grab(self, grab, serial, Focus::Clear);
         }
     }
+
+    fn maximize_request(&mut self, surface: ToplevelSurface) {
+        match self.window_for_toplevel(&surface) {
+            Some(window) => self.maximize_window(&window),
+            // The client expects a configure, even if the request gets ignored
+            None => surface.send_configure(),
+        }
+    }
+
+    fn unmaximize_request(&mut self, surface: ToplevelSurface) {
+        match self.window_for_toplevel(&surface) {
+            Some(window) => self.unmaximize_window(&window),
+            None => surface.send_configure(),
+        }
+    }
+
+    fn fullscreen_request(
+        &mut self,
+        surface: ToplevelSurface,
+        output: Option<wl_output::WlOutput>,
+    ) {
+        let output = output.as_ref().and_then(Output::from_resource);
+
+        match self.window_for_toplevel(&surface) {
+            Some(window) => self.fullscreen_window(&window, output),
+            None => surface.send_configure(),
+        }
+    }
+
+    fn unfullscreen_request(&mut self, surface: ToplevelSurface) {
+        match self.window_for_toplevel(&surface) {
+            Some(window) => self.unfullscreen_window(&window),
+            None => surface.send_configure(),
+        }
+    }
+
+    fn minimize_request(&mut self, surface: ToplevelSurface) {
+        if let Some(window) = self.window_for_toplevel(&surface) {
+            self.minimize_window(&window);
+        }
+    }
 }
 
 // Xdg Shell
 delegate_xdg_shell!(State);
 
+impl State {
+    fn window_for_toplevel(&self, surface: &ToplevelSurface) -> Option<Window> {
+        self.space
+            .window_for_surface(surface.wl_surface(), WindowSurfaceType::TOPLEVEL)
+            .cloned()
+    }
+}
+
 fn check_grab(
     seat: &Seat<State>,
     surface: &WlSurface,
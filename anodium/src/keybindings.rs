@@ -0,0 +1,629 @@
+use std::{collections::HashMap, time::Duration};
+
+use smithay::{
+    backend::input::KeyState,
+    input::keyboard::{keysyms as xkb, FilterResult, ModifiersState},
+    reexports::calloop::{
+        timer::{TimeoutAction, Timer},
+        LoopHandle, RegistrationToken,
+    },
+};
+
+use crate::{actions::Action, CalloopData};
+
+/// Delay before a held key starts repeating, in milliseconds
+pub const REPEAT_DELAY: i32 = 200;
+/// Number of repeats per second
+pub const REPEAT_RATE: i32 = 25;
+
+/// Mode that is active when no other mode was entered
+pub const DEFAULT_MODE: &str = "default";
+
+bitflags::bitflags! {
+    #[derive(Default)]
+    pub struct KeyModifiers: u32 {
+        const CTRL  = 0b0001;
+        const ALT   = 0b0010;
+        const SHIFT = 0b0100;
+        const LOGO  = 0b1000;
+    }
+}
+
+impl KeyModifiers {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_lowercase().as_str() {
+            "ctrl" | "control" => Self::CTRL,
+            "alt" => Self::ALT,
+            "shift" => Self::SHIFT,
+            "logo" | "super" | "meta" => Self::LOGO,
+            _ => return None,
+        })
+    }
+}
+
+impl From<&ModifiersState> for KeyModifiers {
+    fn from(state: &ModifiersState) -> Self {
+        let mut modifiers = Self::empty();
+        modifiers.set(Self::CTRL, state.ctrl);
+        modifiers.set(Self::ALT, state.alt);
+        modifiers.set(Self::SHIFT, state.shift);
+        modifiers.set(Self::LOGO, state.logo);
+        modifiers
+    }
+}
+
+fn is_modifier_key(keysym: u32) -> bool {
+    (xkb::KEY_Shift_L..=xkb::KEY_Hyper_R).contains(&keysym)
+        || keysym == xkb::KEY_ISO_Level3_Shift
+        || keysym == xkb::KEY_Mode_switch
+}
+
+/// When should the keybinding action run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    Press,
+    Release,
+}
+
+/// Single step of a keybinding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyCombo {
+    pub modifiers: KeyModifiers,
+    pub keysym: u32,
+}
+
+impl KeyCombo {
+    fn matches(&self, modifiers: KeyModifiers, keysyms: &[u32]) -> bool {
+        self.modifiers == modifiers && keysyms.contains(&self.keysym)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Keybinding {
+    /// Keys that have to be pressed one after another, usually just one
+    pub keys: Vec<KeyCombo>,
+    pub trigger: Trigger,
+    /// Repeat the action while the key is held
+    pub repeat: bool,
+    pub action: Action,
+}
+
+/// All keybindings grouped by mode
+#[derive(Debug, Clone)]
+pub struct KeybindingTable {
+    pub modes: HashMap<String, Vec<Keybinding>>,
+    /// Time after which an unfinished chord gets dropped
+    pub chord_timeout: Duration,
+}
+
+impl Default for KeybindingTable {
+    fn default() -> Self {
+        Self {
+            modes: HashMap::new(),
+            chord_timeout: Duration::from_millis(1000),
+        }
+    }
+}
+
+/// Maps modifiers and keysyms to actions
+///
+/// Keys that were consumed by a binding are remembered by keycode,
+/// so that their release is not forwarded to clients either.
+#[derive(Debug)]
+pub struct Keybindings {
+    table: KeybindingTable,
+    active_mode: String,
+
+    /// Keys of the chord that is currently being typed
+    chord: Vec<KeyCombo>,
+    chord_timeout: Option<RegistrationToken>,
+
+    intercepted: HashMap<u32, Option<Keybinding>>,
+    repeat: Option<(u32, RegistrationToken)>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self {
+            table: Default::default(),
+            active_mode: DEFAULT_MODE.into(),
+            chord: Vec::new(),
+            chord_timeout: None,
+            intercepted: HashMap::new(),
+            repeat: None,
+        }
+    }
+}
+
+impl Keybindings {
+    /// Replace the binding table, eg. after config reload
+    pub fn set_table(&mut self, handle: &LoopHandle<'static, CalloopData>, table: KeybindingTable) {
+        self.table = table;
+        self.reset_chord(handle);
+
+        if !self.table.modes.contains_key(&self.active_mode) {
+            self.active_mode = DEFAULT_MODE.into();
+        }
+    }
+
+    /// Name of the currently active binding mode
+    pub fn active_mode(&self) -> &str {
+        &self.active_mode
+    }
+
+    pub fn set_mode(&mut self, handle: &LoopHandle<'static, CalloopData>, mode: &str) {
+        if mode != DEFAULT_MODE && !self.table.modes.contains_key(mode) {
+            slog_scope::warn!("Unknown binding mode: {}", mode);
+            return;
+        }
+
+        self.reset_chord(handle);
+        self.active_mode = mode.into();
+    }
+
+    /// Should be called from the keyboard input filter
+    ///
+    /// Returns the binding whose action should run, if any
+    pub fn on_key(
+        &mut self,
+        handle: &LoopHandle<'static, CalloopData>,
+        keycode: u32,
+        state: KeyState,
+        modifiers: &ModifiersState,
+        keysyms: &[u32],
+    ) -> FilterResult<Option<Keybinding>> {
+        match state {
+            KeyState::Pressed => {
+                if keysyms.iter().copied().all(is_modifier_key) {
+                    return FilterResult::Forward;
+                }
+
+                self.on_key_pressed(handle, keycode, KeyModifiers::from(modifiers), keysyms)
+            }
+            KeyState::Released => match self.intercepted.remove(&keycode) {
+                Some(Some(binding)) if binding.trigger == Trigger::Release => {
+                    FilterResult::Intercept(Some(binding))
+                }
+                Some(_) => FilterResult::Intercept(None),
+                None => FilterResult::Forward,
+            },
+        }
+    }
+
+    fn on_key_pressed(
+        &mut self,
+        handle: &LoopHandle<'static, CalloopData>,
+        keycode: u32,
+        modifiers: KeyModifiers,
+        keysyms: &[u32],
+    ) -> FilterResult<Option<Keybinding>> {
+        let depth = self.chord.len();
+
+        let candidates: Vec<&Keybinding> = self
+            .table
+            .modes
+            .get(&self.active_mode)
+            .into_iter()
+            .flatten()
+            .filter(|binding| {
+                binding.keys.len() > depth
+                    && binding.keys.starts_with(&self.chord)
+                    && binding.keys[depth].matches(modifiers, keysyms)
+            })
+            .collect();
+
+        if candidates.is_empty() {
+            if depth == 0 {
+                return FilterResult::Forward;
+            }
+
+            // The chord got broken, maybe this key starts a new one
+            self.reset_chord(handle);
+            return self.on_key_pressed(handle, keycode, modifiers, keysyms);
+        }
+
+        let step = candidates[0].keys[depth];
+        let is_prefix = candidates
+            .iter()
+            .any(|binding| binding.keys.len() > depth + 1);
+
+        if is_prefix {
+            // Wait for the next key, if a binding matches exactly it runs after timeout
+            self.chord.push(step);
+            self.restart_chord_timeout(handle);
+            self.intercepted.insert(keycode, None);
+
+            return FilterResult::Intercept(None);
+        }
+
+        let binding = candidates[0].clone();
+
+        self.reset_chord(handle);
+        self.intercepted.insert(keycode, Some(binding.clone()));
+
+        match binding.trigger {
+            Trigger::Press => FilterResult::Intercept(Some(binding)),
+            Trigger::Release => FilterResult::Intercept(None),
+        }
+    }
+
+    fn restart_chord_timeout(&mut self, handle: &LoopHandle<'static, CalloopData>) {
+        if let Some(token) = self.chord_timeout.take() {
+            handle.remove(token);
+        }
+
+        let timer = Timer::from_duration(self.table.chord_timeout);
+
+        self.chord_timeout = handle
+            .insert_source(timer, |_, _, data| {
+                data.state.keybindings.chord_timeout = None;
+
+                if let Some(binding) = data.state.keybindings.chord_timed_out() {
+                    data.state.run_action(binding.action);
+                }
+
+                TimeoutAction::Drop
+            })
+            .ok();
+    }
+
+    /// Drop the unfinished chord, returning binding that matches it exactly, if any
+    fn chord_timed_out(&mut self) -> Option<Keybinding> {
+        let chord = std::mem::take(&mut self.chord);
+
+        self.table
+            .modes
+            .get(&self.active_mode)?
+            .iter()
+            .find(|binding| binding.keys == chord)
+            .cloned()
+    }
+
+    fn reset_chord(&mut self, handle: &LoopHandle<'static, CalloopData>) {
+        self.chord.clear();
+
+        if let Some(token) = self.chord_timeout.take() {
+            handle.remove(token);
+        }
+    }
+
+    /// Start repeating `action` until the key with given keycode gets released
+    pub fn start_repeat(
+        &mut self,
+        handle: &LoopHandle<'static, CalloopData>,
+        keycode: u32,
+        action: Action,
+    ) {
+        self.stop_repeat(handle);
+
+        let delay = Duration::from_millis(REPEAT_DELAY as u64);
+        let interval = Duration::from_millis(1000 / REPEAT_RATE as u64);
+
+        let token = handle
+            .insert_source(Timer::from_duration(delay), move |_, _, data| {
+                data.state.run_action(action.clone());
+                TimeoutAction::ToDuration(interval)
+            })
+            .ok();
+
+        self.repeat = token.map(|token| (keycode, token));
+    }
+
+    /// Stop repeating, if the repeated key is the one that got released
+    pub fn on_key_released(&mut self, handle: &LoopHandle<'static, CalloopData>, keycode: u32) {
+        if matches!(self.repeat, Some((repeated, _)) if repeated == keycode) {
+            self.stop_repeat(handle);
+        }
+    }
+
+    fn stop_repeat(&mut self, handle: &LoopHandle<'static, CalloopData>) {
+        if let Some((_, token)) = self.repeat.take() {
+            handle.remove(token);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smithay::reexports::calloop::EventLoop;
+
+    use super::*;
+
+    const ALT: KeyModifiers = KeyModifiers::ALT;
+    const NONE: KeyModifiers = KeyModifiers::empty();
+
+    /// What the filter decided, with the command of the binding that should run
+    #[derive(Debug, PartialEq)]
+    enum Outcome {
+        Forward,
+        Intercept,
+        Run(String),
+    }
+
+    fn combo(modifiers: KeyModifiers, keysym: u32) -> KeyCombo {
+        KeyCombo { modifiers, keysym }
+    }
+
+    fn binding(keys: Vec<KeyCombo>, command: &str) -> Keybinding {
+        Keybinding {
+            keys,
+            trigger: Trigger::Press,
+            repeat: false,
+            action: Action::Spawn(command.into()),
+        }
+    }
+
+    fn keybindings(
+        handle: &LoopHandle<'static, CalloopData>,
+        modes: Vec<(&str, Vec<Keybinding>)>,
+    ) -> Keybindings {
+        let mut keybindings = Keybindings::default();
+        keybindings.set_table(
+            handle,
+            KeybindingTable {
+                modes: modes
+                    .into_iter()
+                    .map(|(name, bindings)| (name.to_string(), bindings))
+                    .collect(),
+                ..Default::default()
+            },
+        );
+        keybindings
+    }
+
+    fn command(binding: &Keybinding) -> String {
+        match &binding.action {
+            Action::Spawn(command) => command.clone(),
+            action => panic!("Unexpected action: {:?}", action),
+        }
+    }
+
+    /// Press and release a key, the keycode is derived from the keysym
+    fn tap(
+        keybindings: &mut Keybindings,
+        handle: &LoopHandle<'static, CalloopData>,
+        modifiers: KeyModifiers,
+        keysym: u32,
+    ) -> Outcome {
+        let state = ModifiersState {
+            ctrl: modifiers.contains(KeyModifiers::CTRL),
+            alt: modifiers.contains(KeyModifiers::ALT),
+            shift: modifiers.contains(KeyModifiers::SHIFT),
+            logo: modifiers.contains(KeyModifiers::LOGO),
+            ..Default::default()
+        };
+
+        let pressed = keybindings.on_key(handle, keysym, KeyState::Pressed, &state, &[keysym]);
+        let released = keybindings.on_key(handle, keysym, KeyState::Released, &state, &[keysym]);
+
+        // Release of a consumed key never reaches clients
+        assert_eq!(
+            matches!(pressed, FilterResult::Forward),
+            matches!(released, FilterResult::Forward)
+        );
+
+        match pressed {
+            FilterResult::Forward => Outcome::Forward,
+            FilterResult::Intercept(None) => Outcome::Intercept,
+            FilterResult::Intercept(Some(binding)) => Outcome::Run(command(&binding)),
+        }
+    }
+
+    #[test]
+    fn chord() {
+        let event_loop = EventLoop::<CalloopData>::try_new().unwrap();
+        let handle = event_loop.handle();
+
+        let mut keybindings = keybindings(
+            &handle,
+            vec![(
+                DEFAULT_MODE,
+                vec![binding(
+                    vec![combo(ALT, xkb::KEY_space), combo(NONE, xkb::KEY_t)],
+                    "terminal",
+                )],
+            )],
+        );
+
+        assert_eq!(
+            tap(&mut keybindings, &handle, NONE, xkb::KEY_t),
+            Outcome::Forward
+        );
+
+        assert_eq!(
+            tap(&mut keybindings, &handle, ALT, xkb::KEY_space),
+            Outcome::Intercept
+        );
+        assert!(keybindings.chord_timeout.is_some());
+
+        assert_eq!(
+            tap(&mut keybindings, &handle, NONE, xkb::KEY_t),
+            Outcome::Run("terminal".into())
+        );
+        assert!(keybindings.chord.is_empty());
+        assert!(keybindings.chord_timeout.is_none());
+    }
+
+    #[test]
+    fn broken_chord() {
+        let event_loop = EventLoop::<CalloopData>::try_new().unwrap();
+        let handle = event_loop.handle();
+
+        let mut keybindings = keybindings(
+            &handle,
+            vec![(
+                DEFAULT_MODE,
+                vec![
+                    binding(
+                        vec![combo(ALT, xkb::KEY_space), combo(NONE, xkb::KEY_t)],
+                        "terminal",
+                    ),
+                    binding(vec![combo(ALT, xkb::KEY_q)], "quit"),
+                ],
+            )],
+        );
+
+        // Unbound key drops the chord and goes to the client
+        tap(&mut keybindings, &handle, ALT, xkb::KEY_space);
+        assert_eq!(
+            tap(&mut keybindings, &handle, NONE, xkb::KEY_x),
+            Outcome::Forward
+        );
+        assert!(keybindings.chord.is_empty());
+        assert_eq!(
+            tap(&mut keybindings, &handle, NONE, xkb::KEY_t),
+            Outcome::Forward
+        );
+
+        // Key that starts another binding runs it
+        tap(&mut keybindings, &handle, ALT, xkb::KEY_space);
+        assert_eq!(
+            tap(&mut keybindings, &handle, ALT, xkb::KEY_q),
+            Outcome::Run("quit".into())
+        );
+        assert!(keybindings.chord.is_empty());
+    }
+
+    #[test]
+    fn chord_timeout() {
+        let event_loop = EventLoop::<CalloopData>::try_new().unwrap();
+        let handle = event_loop.handle();
+
+        let mut keybindings = keybindings(
+            &handle,
+            vec![(
+                DEFAULT_MODE,
+                vec![
+                    binding(vec![combo(ALT, xkb::KEY_space)], "launcher"),
+                    binding(
+                        vec![combo(ALT, xkb::KEY_space), combo(NONE, xkb::KEY_t)],
+                        "terminal",
+                    ),
+                    binding(
+                        vec![
+                            combo(ALT, xkb::KEY_space),
+                            combo(NONE, xkb::KEY_w),
+                            combo(NONE, xkb::KEY_v),
+                        ],
+                        "split",
+                    ),
+                ],
+            )],
+        );
+
+        // A binding that is also a prefix waits for the timeout
+        assert_eq!(
+            tap(&mut keybindings, &handle, ALT, xkb::KEY_space),
+            Outcome::Intercept
+        );
+        assert_eq!(
+            keybindings.chord_timed_out().as_ref().map(command),
+            Some("launcher".into())
+        );
+        assert!(keybindings.chord.is_empty());
+        assert_eq!(
+            tap(&mut keybindings, &handle, NONE, xkb::KEY_t),
+            Outcome::Forward
+        );
+
+        // Unfinished chord without an exact match just gets dropped
+        tap(&mut keybindings, &handle, ALT, xkb::KEY_space);
+        assert_eq!(
+            tap(&mut keybindings, &handle, NONE, xkb::KEY_w),
+            Outcome::Intercept
+        );
+        assert!(keybindings.chord_timed_out().is_none());
+        assert!(keybindings.chord.is_empty());
+        assert_eq!(
+            tap(&mut keybindings, &handle, NONE, xkb::KEY_v),
+            Outcome::Forward
+        );
+    }
+
+    #[test]
+    fn mode_switching() {
+        let event_loop = EventLoop::<CalloopData>::try_new().unwrap();
+        let handle = event_loop.handle();
+
+        let mut keybindings = keybindings(
+            &handle,
+            vec![
+                (
+                    DEFAULT_MODE,
+                    vec![
+                        binding(vec![combo(ALT, xkb::KEY_r)], "resize"),
+                        binding(
+                            vec![combo(ALT, xkb::KEY_space), combo(NONE, xkb::KEY_t)],
+                            "terminal",
+                        ),
+                    ],
+                ),
+                (
+                    "resize",
+                    vec![binding(vec![combo(NONE, xkb::KEY_l)], "grow")],
+                ),
+            ],
+        );
+
+        assert_eq!(keybindings.active_mode(), DEFAULT_MODE);
+        assert_eq!(
+            tap(&mut keybindings, &handle, NONE, xkb::KEY_l),
+            Outcome::Forward
+        );
+
+        // Switching drops the unfinished chord
+        tap(&mut keybindings, &handle, ALT, xkb::KEY_space);
+        keybindings.set_mode(&handle, "resize");
+        assert_eq!(keybindings.active_mode(), "resize");
+        assert!(keybindings.chord.is_empty());
+        assert!(keybindings.chord_timeout.is_none());
+
+        // Only bindings of the active mode apply
+        assert_eq!(
+            tap(&mut keybindings, &handle, NONE, xkb::KEY_l),
+            Outcome::Run("grow".into())
+        );
+        assert_eq!(
+            tap(&mut keybindings, &handle, ALT, xkb::KEY_r),
+            Outcome::Forward
+        );
+
+        keybindings.set_mode(&handle, "unknown");
+        assert_eq!(keybindings.active_mode(), "resize");
+
+        // Reloaded config without the mode falls back to the default one
+        keybindings.set_table(&handle, KeybindingTable::default());
+        assert_eq!(keybindings.active_mode(), DEFAULT_MODE);
+    }
+
+    #[test]
+    fn reload_drops_chord() {
+        let event_loop = EventLoop::<CalloopData>::try_new().unwrap();
+        let handle = event_loop.handle();
+
+        let chord = vec![combo(ALT, xkb::KEY_space), combo(NONE, xkb::KEY_t)];
+        let mut keybindings = keybindings(
+            &handle,
+            vec![(DEFAULT_MODE, vec![binding(chord.clone(), "terminal")])],
+        );
+
+        tap(&mut keybindings, &handle, ALT, xkb::KEY_space);
+        assert!(keybindings.chord_timeout.is_some());
+
+        keybindings.set_table(
+            &handle,
+            KeybindingTable {
+                modes: [(DEFAULT_MODE.to_string(), vec![binding(chord, "browser")])].into(),
+                ..Default::default()
+            },
+        );
+        assert!(keybindings.chord.is_empty());
+        assert!(keybindings.chord_timeout.is_none());
+
+        // The chord starts over with the new table
+        assert_eq!(
+            tap(&mut keybindings, &handle, NONE, xkb::KEY_t),
+            Outcome::Forward
+        );
+    }
+}
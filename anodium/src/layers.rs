@@ -0,0 +1,208 @@
+//! Layer shell surfaces, eg. panels, docks, wallpapers and launchers
+//!
+//! Layer surfaces live in the layer map of their output, the [`Space`](smithay::desktop::Space)
+//! draws background and bottom layers below windows and top and overlay layers above them.
+//! Exclusive zones shrink the usable area of the output, see [`State::usable_area`].
+
+use smithay::{
+    desktop::{layer_map_for_output, LayerSurface, WindowSurfaceType},
+    output::Output,
+    reexports::wayland_server::protocol::wl_surface::WlSurface,
+    utils::{Logical, Point, SERIAL_COUNTER},
+    wayland::{
+        compositor::with_states,
+        shell::wlr_layer::{
+            KeyboardInteractivity, Layer, LayerSurfaceCachedState, LayerSurfaceData,
+        },
+    },
+};
+
+use crate::{
+    data::{output::OutputState, window::WindowState},
+    State,
+};
+
+/// Layers drawn above windows, topmost first
+pub const UPPER_LAYERS: [Layer; 2] = [Layer::Overlay, Layer::Top];
+/// Layers drawn below windows, topmost first
+pub const LOWER_LAYERS: [Layer; 2] = [Layer::Bottom, Layer::Background];
+
+fn keyboard_interactivity(layer: &LayerSurface) -> KeyboardInteractivity {
+    with_states(layer.wl_surface(), |states| {
+        states
+            .cached_state
+            .current::<LayerSurfaceCachedState>()
+            .keyboard_interactivity
+    })
+}
+
+impl State {
+    pub fn output_for_layer(&self, surface: &WlSurface) -> Option<Output> {
+        self.space
+            .outputs()
+            .find(|output| {
+                layer_map_for_output(output)
+                    .layer_for_surface(surface, WindowSurfaceType::TOPLEVEL)
+                    .is_some()
+            })
+            .cloned()
+    }
+
+    /// Should be called on every commit
+    pub fn layer_commit(&mut self, surface: &WlSurface) {
+        let output = match self.output_for_layer(surface) {
+            Some(output) => output,
+            None => return,
+        };
+
+        let initial_configure_sent = with_states(surface, |states| {
+            states
+                .data_map
+                .get::<LayerSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap()
+                .initial_configure_sent
+        });
+
+        let layer = {
+            let mut map = layer_map_for_output(&output);
+            map.arrange(&self.display);
+            map.layer_for_surface(surface, WindowSurfaceType::TOPLEVEL)
+                .cloned()
+        };
+
+        if let Some(layer) = layer.filter(|_| !initial_configure_sent) {
+            layer.layer_surface().send_configure();
+        }
+
+        self.update_usable_area(&output);
+    }
+
+    /// Should be called every loop iteration, after [`Space::refresh`](smithay::desktop::Space::refresh)
+    pub fn refresh_layers(&mut self) {
+        let outputs: Vec<Output> = self.space.outputs().cloned().collect();
+
+        for output in outputs {
+            layer_map_for_output(&output).cleanup(&self.display);
+            self.update_usable_area(&output);
+        }
+
+        // Exclusive surfaces keep the keyboard focus for as long as they are mapped
+        let keyboard = self.seat.get_keyboard().unwrap();
+        if let Some(surface) = self.exclusive_layer_surface() {
+            if keyboard.current_focus().as_ref() != Some(&surface) {
+                keyboard.set_focus(self, Some(surface), SERIAL_COUNTER.next_serial());
+            }
+        }
+    }
+
+    /// Arrange windows again, if exclusive zones of layer surfaces changed
+    fn update_usable_area(&mut self, output: &Output) {
+        let area = self.usable_area(output);
+        let output_state = OutputState::for_output(output);
+
+        if output_state.usable_area() == area {
+            return;
+        }
+
+        output_state.set_usable_area(area);
+
+        self.arrange_output(output);
+
+        let maximized: Vec<_> = output_state
+            .workspaces()
+            .active()
+            .windows()
+            .filter(|window| {
+                let window_state = WindowState::for_window(window);
+                window_state.is_maximized() && !window_state.is_minimized()
+            })
+            .cloned()
+            .collect();
+
+        // Maximizing again fits windows into the new area
+        for window in maximized {
+            self.maximize_window(&window);
+        }
+    }
+
+    /// Mapped surface of the top or overlay layer that wants all keyboard input
+    fn exclusive_layer_surface(&self) -> Option<WlSurface> {
+        self.space.outputs().find_map(|output| {
+            let map = layer_map_for_output(output);
+
+            UPPER_LAYERS.iter().find_map(|layer| {
+                map.layers_on(*layer)
+                    .find(|layer| {
+                        layer.alive()
+                            && keyboard_interactivity(layer) == KeyboardInteractivity::Exclusive
+                    })
+                    .map(|layer| layer.wl_surface().clone())
+            })
+        })
+    }
+
+    /// Topmost layer surface out of `layers` under the pointer
+    pub fn layer_under(
+        &self,
+        position: Point<f64, Logical>,
+        layers: &[Layer],
+    ) -> Option<LayerSurface> {
+        let output = self.space.output_under(position).next()?;
+        let output_loc = self.space.output_geometry(output)?.loc.to_f64();
+        let map = layer_map_for_output(output);
+
+        layers
+            .iter()
+            .find_map(|layer| map.layer_under(*layer, position - output_loc).cloned())
+    }
+
+    /// Surface under the pointer out of `layers`, with its location
+    pub fn layer_surface_under(
+        &self,
+        position: Point<f64, Logical>,
+        layers: &[Layer],
+    ) -> Option<(WlSurface, Point<i32, Logical>)> {
+        let layer = self.layer_under(position, layers)?;
+        let output = self.output_for_layer(layer.wl_surface())?;
+        let output_loc = self.space.output_geometry(&output)?.loc;
+        let layer_loc = layer_map_for_output(&output).layer_geometry(&layer)?.loc + output_loc;
+
+        layer
+            .surface_under(position - layer_loc.to_f64(), WindowSurfaceType::all())
+            .map(|(surface, location)| (surface, location + layer_loc))
+    }
+
+    /// Surface under the pointer, layer surfaces included
+    pub fn surface_under(
+        &self,
+        position: Point<f64, Logical>,
+    ) -> Option<(WlSurface, Point<i32, Logical>)> {
+        self.layer_surface_under(position, &UPPER_LAYERS)
+            .or_else(|| {
+                self.space
+                    .surface_under(position, WindowSurfaceType::all())
+                    .map(|(_, surface, location)| (surface, location))
+            })
+            .or_else(|| self.layer_surface_under(position, &LOWER_LAYERS))
+    }
+
+    /// Give keyboard focus to the layer surface, unless it does not take keyboard input
+    ///
+    /// Returns whether the focus changed.
+    pub fn focus_layer(&mut self, layer: &LayerSurface) -> bool {
+        if keyboard_interactivity(layer) == KeyboardInteractivity::None {
+            return false;
+        }
+
+        let keyboard = self.seat.get_keyboard().unwrap();
+        keyboard.set_focus(
+            self,
+            Some(layer.wl_surface().clone()),
+            SERIAL_COUNTER.next_serial(),
+        );
+
+        true
+    }
+}
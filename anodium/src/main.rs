@@ -4,7 +4,11 @@ use std::{ffi::OsString, os::unix::prelude::AsRawFd, sync::Arc, time::Instant};
 
 use anodium_backend::BackendState;
 use anodium_framework::pointer_icon::PointerIcon;
+use anodium_protocol::server::AnodiumProtocol;
 use clap::StructOpt;
+use config::ConfigVM;
+use keybindings::Keybindings;
+use mousebindings::Mousebindings;
 use on_commit::OnCommitDispatcher;
 use slog::Drain;
 use smithay::{
@@ -13,25 +17,50 @@ use smithay::{
     reexports::{
         calloop::{
             generic::Generic, EventLoop, Interest, LoopHandle, LoopSignal, Mode, PostAction,
+            RegistrationToken,
         },
         wayland_server::{
             backend::{ClientData, ClientId, DisconnectReason},
             Display, DisplayHandle,
         },
     },
+    utils::{Logical, Rectangle},
     wayland::{
-        compositor::CompositorState, data_device::DataDeviceState, dmabuf::DmabufState,
-        output::OutputManagerState, shell::xdg::XdgShellState, shm::ShmState,
+        compositor::CompositorState,
+        data_device::DataDeviceState,
+        dmabuf::DmabufState,
+        output::OutputManagerState,
+        shell::{
+            wlr_layer::WlrLayerShellState,
+            xdg::{decoration::XdgDecorationState, XdgShellState},
+        },
+        shm::ShmState,
         socket::ListeningSocketSource,
     },
 };
 
+mod actions;
+mod animation;
 mod cli;
+mod config;
 mod data;
+mod decorations;
+mod focus;
 mod grabs;
 mod handlers;
+mod keybindings;
+mod layers;
+mod mousebindings;
 mod on_commit;
+mod output_layout;
+mod popups;
 mod positioning;
+mod render;
+mod switcher;
+mod tiling;
+mod window_rules;
+mod window_state;
+mod workspace;
 #[cfg(feature = "xwayland")]
 mod xwayland;
 
@@ -42,6 +71,8 @@ pub struct CalloopData {
 
 pub struct State {
     space: desktop::Space,
+    /// Renders outputs, it holds no windows, they are drawn as custom elements, see [`render`]
+    render_space: desktop::Space,
     popups: PopupManager,
 
     display: DisplayHandle,
@@ -54,15 +85,33 @@ pub struct State {
 
     commit_dispatcher: OnCommitDispatcher,
 
+    config: ConfigVM,
+    keybindings: Keybindings,
+    mousebindings: Mousebindings,
+
     compositor_state: CompositorState,
     xdg_shell_state: XdgShellState,
+    _xdg_decoration_state: XdgDecorationState,
+    layer_shell_state: WlrLayerShellState,
     shm_state: ShmState,
     _output_manager_state: OutputManagerState,
     seat_state: SeatState<Self>,
     data_device_state: DataDeviceState,
     dmabuf_state: DmabufState,
+    /// Workspaces and the keybinding mode for status bars
+    anodium_protocol: AnodiumProtocol,
 
     pointer_icon: PointerIcon,
+    /// Area a moved window is going to be snapped to
+    snap_preview: Option<Rectangle<i32, Logical>>,
+    /// Open Alt+Tab switcher
+    switcher: Option<switcher::WindowSwitcher>,
+    /// Pending raise of a window focused by the pointer
+    auto_raise_timer: Option<RegistrationToken>,
+    /// Disappearing windows that are still animating
+    ghosts: Vec<animation::Ghost>,
+    /// Workspaces of unplugged outputs, adopted by the next output that shows up
+    orphaned_workspaces: Vec<workspace::Workspaces>,
 
     backend: BackendState,
 
@@ -150,7 +199,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let opt = cli::AnodiumCliOptions::parse();
 
+    let config = ConfigVM::load(&opt.config).unwrap_or_else(|err| {
+        slog_scope::error!("Failed to load config {:?}: {}", opt.config, err);
+        ConfigVM::empty()
+    });
+
     let mut event_loop = EventLoop::<CalloopData>::try_new()?;
+
+    let mut keybindings = Keybindings::default();
+    keybindings.set_table(&event_loop.handle(), config.keyboard().keybindings());
+
+    let mut mousebindings = Mousebindings::default();
+    mousebindings.set_bindings(config.mouse().bindings());
+
     let mut display = Display::new()?;
 
     let socket_name = init_wayland_listener(&mut display, &mut event_loop, slog_scope::logger());
@@ -160,17 +221,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let dh = display.handle();
     let compositor_state = CompositorState::new::<State, _>(&dh, slog_scope::logger());
     let xdg_shell_state = XdgShellState::new::<State, _>(&dh, slog_scope::logger());
+    let xdg_decoration_state = XdgDecorationState::new::<State, _>(&dh, slog_scope::logger());
+    let layer_shell_state = WlrLayerShellState::new::<State, _>(&dh, slog_scope::logger());
     let shm_state = ShmState::new::<State, _>(&dh, vec![], slog_scope::logger());
     let output_manager_state = OutputManagerState::new_with_xdg_output::<State>(&dh);
     let mut seat_state = SeatState::<State>::new();
     let data_device_state = DataDeviceState::new::<State, _>(&dh, slog_scope::logger());
 
     let dmabuf_state = DmabufState::new();
+    let anodium_protocol = AnodiumProtocol::new::<State>(&dh);
 
     let mut seat = seat_state.new_wl_seat(&display.handle(), "seat0", slog_scope::logger());
 
     seat.add_pointer();
-    seat.add_keyboard(Default::default(), 200, 25)?;
+    seat.add_keyboard(
+        Default::default(),
+        keybindings::REPEAT_DELAY,
+        keybindings::REPEAT_RATE,
+    )?;
 
     #[cfg(feature = "xwayland")]
     let xwayland_state =
@@ -178,6 +246,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let state = State {
         space: desktop::Space::new(slog_scope::logger()),
+        render_space: desktop::Space::new(slog_scope::logger()),
         popups: PopupManager::new(slog_scope::logger()),
         display: display.handle(),
 
@@ -189,15 +258,28 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         commit_dispatcher: Default::default(),
 
+        config,
+        keybindings,
+        mousebindings,
+
         compositor_state,
         xdg_shell_state,
+        _xdg_decoration_state: xdg_decoration_state,
+        layer_shell_state,
         shm_state,
         _output_manager_state: output_manager_state,
         seat_state,
         data_device_state,
         dmabuf_state,
+        anodium_protocol,
 
         pointer_icon,
+        snap_preview: None,
+        switcher: None,
+        auto_raise_timer: None,
+        ghosts: Vec::new(),
+        orphaned_workspaces: Vec::new(),
+
         backend: BackendState::default(),
 
         socket_name,
@@ -207,6 +289,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut data = CalloopData { state, display };
 
+    if let Err(err) = config::init_config_watcher(&event_loop.handle(), opt.config.clone()) {
+        slog_scope::error!("Failed to watch config {:?}: {}", opt.config, err);
+    }
+
     anodium_backend::init(
         &mut event_loop,
         &data.display.handle(),
@@ -216,6 +302,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     event_loop.run(None, &mut data, |data| {
         data.state.space.refresh(&data.display.handle());
+        data.state.refresh_layers();
+        data.state.refresh_workspaces();
+        data.state.refresh_anodium_protocol();
         data.state.popups.cleanup();
         data.display.flush_clients().unwrap();
     })?;
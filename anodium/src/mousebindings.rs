@@ -0,0 +1,94 @@
+use std::collections::HashSet;
+
+use crate::{actions::Action, keybindings::KeyModifiers};
+
+// Button codes as defined in the Linux kernel's linux/input-event-codes.h header file
+pub const BTN_LEFT: u32 = 0x110;
+pub const BTN_RIGHT: u32 = 0x111;
+pub const BTN_MIDDLE: u32 = 0x112;
+pub const BTN_SIDE: u32 = 0x113;
+pub const BTN_EXTRA: u32 = 0x114;
+
+pub fn button_from_name(name: &str) -> Option<u32> {
+    Some(match name.to_lowercase().as_str() {
+        "left" => BTN_LEFT,
+        "right" => BTN_RIGHT,
+        "middle" => BTN_MIDDLE,
+        "side" | "back" => BTN_SIDE,
+        "extra" | "forward" => BTN_EXTRA,
+        _ => return None,
+    })
+}
+
+/// What to do with the window under the pointer
+#[derive(Debug, Clone)]
+pub enum MouseAction {
+    /// Move the window until the button gets released
+    Move,
+    /// Resize the window until the button gets released
+    Resize,
+    Raise,
+    Lower,
+    Close,
+    /// Any other action, eg. a config callback
+    Action(Action),
+}
+
+impl MouseAction {
+    /// Does the action start a pointer grab
+    pub fn is_grab(&self) -> bool {
+        matches!(self, Self::Move | Self::Resize)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Mousebinding {
+    pub modifiers: KeyModifiers,
+    pub button: u32,
+    pub action: MouseAction,
+}
+
+/// Maps modifiers and pointer buttons to actions
+///
+/// Buttons consumed by a binding are remembered,
+/// so that their release is not forwarded to clients either.
+#[derive(Debug, Default)]
+pub struct Mousebindings {
+    bindings: Vec<Mousebinding>,
+    intercepted: HashSet<u32>,
+}
+
+impl Mousebindings {
+    pub fn set_bindings(&mut self, bindings: Vec<Mousebinding>) {
+        self.bindings = bindings;
+    }
+
+    /// Should be called on button press, returns the action to run, if any
+    pub fn on_button_pressed(
+        &mut self,
+        modifiers: KeyModifiers,
+        button: u32,
+    ) -> Option<MouseAction> {
+        let binding = self
+            .bindings
+            .iter()
+            .find(|binding| binding.modifiers == modifiers && binding.button == button)?;
+
+        // Grabs take care of the release themselves
+        if !binding.action.is_grab() {
+            self.intercepted.insert(button);
+        }
+
+        Some(binding.action.clone())
+    }
+
+    /// Don't forward the release of the button, eg. when a grab could not be started
+    pub fn intercept_release(&mut self, button: u32) {
+        self.intercepted.insert(button);
+    }
+
+    /// Should be called on button release, returns `true` if the release should not be forwarded
+    pub fn on_button_released(&mut self, button: u32) -> bool {
+        self.intercepted.remove(&button)
+    }
+}
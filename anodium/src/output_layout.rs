@@ -0,0 +1,155 @@
+use smithay::{
+    output::Mode,
+    utils::{Logical, Point, Size, Transform},
+};
+
+/// Desired state of a single output
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputLayout {
+    pub name: String,
+    /// Requested mode, refresh of `0` means the highest available refresh rate
+    pub mode: Option<Mode>,
+    /// Position in the global compositor space,
+    /// if unset the output is placed to the right of previous outputs
+    pub position: Option<Point<i32, Logical>>,
+    pub scale: Option<i32>,
+    pub transform: Option<Transform>,
+}
+
+impl OutputLayout {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            mode: None,
+            position: None,
+            scale: None,
+            transform: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Find a mode matching the requested one among modes supported by the output
+    ///
+    /// Returns `None` if no mode was requested, or the output does not support it
+    pub fn select_mode(&self, possible_modes: &[Mode]) -> Option<Mode> {
+        let requested = self.mode?;
+
+        let candidates = possible_modes
+            .iter()
+            .filter(|mode| mode.size == requested.size);
+
+        if requested.refresh == 0 {
+            candidates.max_by_key(|mode| mode.refresh).copied()
+        } else {
+            candidates
+                .min_by_key(|mode| (mode.refresh - requested.refresh).abs())
+                .copied()
+        }
+    }
+}
+
+/// Size of the output in the global compositor space
+pub fn logical_size(mode: Mode, transform: Transform, scale: i32) -> Size<i32, Logical> {
+    let size = transform.transform_size(mode.size);
+    let scale = scale.max(1);
+
+    (size.w / scale, size.h / scale).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode(w: i32, h: i32, refresh: i32) -> Mode {
+        Mode {
+            size: (w, h).into(),
+            refresh,
+        }
+    }
+
+    fn possible_modes() -> Vec<Mode> {
+        vec![
+            mode(2560, 1440, 143_912),
+            mode(2560, 1440, 59_951),
+            mode(1920, 1080, 74_973),
+            mode(1920, 1080, 60_000),
+            mode(1920, 1080, 59_940),
+            mode(1920, 1080, 50_000),
+        ]
+    }
+
+    fn select(requested: Option<Mode>) -> Option<Mode> {
+        let layout = OutputLayout {
+            mode: requested,
+            ..OutputLayout::new("DP-1".into())
+        };
+
+        layout.select_mode(&possible_modes())
+    }
+
+    #[test]
+    fn no_requested_mode() {
+        assert_eq!(select(None), None);
+    }
+
+    #[test]
+    fn unsupported_size() {
+        assert_eq!(select(Some(mode(1280, 720, 60_000))), None);
+    }
+
+    #[test]
+    fn highest_refresh_by_default() {
+        assert_eq!(
+            select(Some(mode(1920, 1080, 0))),
+            Some(mode(1920, 1080, 74_973))
+        );
+        assert_eq!(
+            select(Some(mode(2560, 1440, 0))),
+            Some(mode(2560, 1440, 143_912))
+        );
+    }
+
+    #[test]
+    fn nearest_refresh() {
+        assert_eq!(
+            select(Some(mode(1920, 1080, 60_000))),
+            Some(mode(1920, 1080, 60_000))
+        );
+        assert_eq!(
+            select(Some(mode(1920, 1080, 59_900))),
+            Some(mode(1920, 1080, 59_940))
+        );
+        assert_eq!(
+            select(Some(mode(2560, 1440, 60_000))),
+            Some(mode(2560, 1440, 59_951))
+        );
+        assert_eq!(
+            select(Some(mode(1920, 1080, 144_000))),
+            Some(mode(1920, 1080, 74_973))
+        );
+        assert_eq!(
+            select(Some(mode(1920, 1080, 30_000))),
+            Some(mode(1920, 1080, 50_000))
+        );
+    }
+
+    #[test]
+    fn logical_size_of_rotated_scaled_output() {
+        assert_eq!(
+            logical_size(mode(2560, 1440, 0), Transform::Normal, 2),
+            (1280, 720).into()
+        );
+        assert_eq!(
+            logical_size(mode(1920, 1080, 0), Transform::_90, 1),
+            (1080, 1920).into()
+        );
+        // Scale below one is treated as one
+        assert_eq!(
+            logical_size(mode(1920, 1080, 0), Transform::Normal, 0),
+            (1920, 1080).into()
+        );
+    }
+}
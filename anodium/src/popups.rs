@@ -0,0 +1,470 @@
+//! Positioning of xdg popups
+//!
+//! Popups are placed according to their positioner, relative to the window geometry of
+//! their parent. When that would put them outside of the output, constraint adjustments
+//! requested by the client are applied in the order of the protocol: flip, slide, resize.
+
+use smithay::{
+    desktop::{layer_map_for_output, WindowSurfaceType},
+    reexports::{
+        wayland_protocols::xdg::shell::server::xdg_positioner::{
+            Anchor, ConstraintAdjustment, Gravity,
+        },
+        wayland_server::protocol::wl_surface::WlSurface,
+    },
+    utils::{Logical, Point, Rectangle},
+    wayland::{
+        compositor::with_states,
+        shell::xdg::{PopupSurface, PositionerState, XdgPopupSurfaceData},
+    },
+};
+
+use crate::State;
+
+fn flip_anchor_x(anchor: Anchor) -> Anchor {
+    match anchor {
+        Anchor::Left => Anchor::Right,
+        Anchor::Right => Anchor::Left,
+        Anchor::TopLeft => Anchor::TopRight,
+        Anchor::TopRight => Anchor::TopLeft,
+        Anchor::BottomLeft => Anchor::BottomRight,
+        Anchor::BottomRight => Anchor::BottomLeft,
+        anchor => anchor,
+    }
+}
+
+fn flip_anchor_y(anchor: Anchor) -> Anchor {
+    match anchor {
+        Anchor::Top => Anchor::Bottom,
+        Anchor::Bottom => Anchor::Top,
+        Anchor::TopLeft => Anchor::BottomLeft,
+        Anchor::BottomLeft => Anchor::TopLeft,
+        Anchor::TopRight => Anchor::BottomRight,
+        Anchor::BottomRight => Anchor::TopRight,
+        anchor => anchor,
+    }
+}
+
+fn flip_gravity_x(gravity: Gravity) -> Gravity {
+    match gravity {
+        Gravity::Left => Gravity::Right,
+        Gravity::Right => Gravity::Left,
+        Gravity::TopLeft => Gravity::TopRight,
+        Gravity::TopRight => Gravity::TopLeft,
+        Gravity::BottomLeft => Gravity::BottomRight,
+        Gravity::BottomRight => Gravity::BottomLeft,
+        gravity => gravity,
+    }
+}
+
+fn flip_gravity_y(gravity: Gravity) -> Gravity {
+    match gravity {
+        Gravity::Top => Gravity::Bottom,
+        Gravity::Bottom => Gravity::Top,
+        Gravity::TopLeft => Gravity::BottomLeft,
+        Gravity::BottomLeft => Gravity::TopLeft,
+        Gravity::TopRight => Gravity::BottomRight,
+        Gravity::BottomRight => Gravity::TopRight,
+        gravity => gravity,
+    }
+}
+
+/// Span of a rectangle on one axis
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: i32,
+    len: i32,
+}
+
+impl Span {
+    fn end(self) -> i32 {
+        self.start + self.len
+    }
+
+    fn fits_in(self, target: Span) -> bool {
+        self.start >= target.start && self.end() <= target.end()
+    }
+
+    /// Moved the least distance that gets it inside, the start wins if it is too big
+    fn slid_into(self, target: Span) -> Span {
+        let start = self.start.min(target.end() - self.len).max(target.start);
+        Span { start, ..self }
+    }
+
+    /// Cut down to the part inside, `None` if nothing is left
+    fn clipped_to(self, target: Span) -> Option<Span> {
+        let start = self.start.max(target.start);
+        let end = self.end().min(target.end());
+
+        (end > start).then(|| Span {
+            start,
+            len: end - start,
+        })
+    }
+}
+
+/// Constraint adjustments of one axis
+struct Adjustments {
+    slide: bool,
+    flip: bool,
+    resize: bool,
+}
+
+/// Position a popup on one axis, `flipped` is the span the popup gets with flipped anchor and gravity
+fn unconstrain_span(span: Span, flipped: Span, target: Span, adjustments: Adjustments) -> Span {
+    if span.fits_in(target) {
+        return span;
+    }
+
+    if adjustments.flip && flipped.fits_in(target) {
+        return flipped;
+    }
+
+    let mut span = span;
+
+    if adjustments.slide {
+        span = span.slid_into(target);
+
+        if span.fits_in(target) {
+            return span;
+        }
+    }
+
+    if adjustments.resize {
+        if let Some(clipped) = span.clipped_to(target) {
+            span = clipped;
+        }
+    }
+
+    span
+}
+
+/// Geometry of the popup relative to its parent, kept inside of `target` when the positioner allows it
+///
+/// `target` is relative to the parent as well.
+fn unconstrained_geometry(
+    positioner: PositionerState,
+    target: Rectangle<i32, Logical>,
+) -> Rectangle<i32, Logical> {
+    let geometry = positioner.get_geometry();
+    let adjustment = positioner.constraint_adjustment;
+
+    let flipped_x = PositionerState {
+        anchor_edges: flip_anchor_x(positioner.anchor_edges),
+        gravity: flip_gravity_x(positioner.gravity),
+        offset: (-positioner.offset.x, positioner.offset.y).into(),
+        ..positioner
+    }
+    .get_geometry();
+
+    let flipped_y = PositionerState {
+        anchor_edges: flip_anchor_y(positioner.anchor_edges),
+        gravity: flip_gravity_y(positioner.gravity),
+        offset: (positioner.offset.x, -positioner.offset.y).into(),
+        ..positioner
+    }
+    .get_geometry();
+
+    let x = unconstrain_span(
+        Span {
+            start: geometry.loc.x,
+            len: geometry.size.w,
+        },
+        Span {
+            start: flipped_x.loc.x,
+            len: flipped_x.size.w,
+        },
+        Span {
+            start: target.loc.x,
+            len: target.size.w,
+        },
+        Adjustments {
+            slide: adjustment.contains(ConstraintAdjustment::SlideX),
+            flip: adjustment.contains(ConstraintAdjustment::FlipX),
+            resize: adjustment.contains(ConstraintAdjustment::ResizeX),
+        },
+    );
+
+    let y = unconstrain_span(
+        Span {
+            start: geometry.loc.y,
+            len: geometry.size.h,
+        },
+        Span {
+            start: flipped_y.loc.y,
+            len: flipped_y.size.h,
+        },
+        Span {
+            start: target.loc.y,
+            len: target.size.h,
+        },
+        Adjustments {
+            slide: adjustment.contains(ConstraintAdjustment::SlideY),
+            flip: adjustment.contains(ConstraintAdjustment::FlipY),
+            resize: adjustment.contains(ConstraintAdjustment::ResizeY),
+        },
+    );
+
+    Rectangle::from_loc_and_size((x.start, y.start), (x.len, y.len))
+}
+
+impl State {
+    /// Global location of the window geometry of a popup parent
+    ///
+    /// Parents are toplevels, other popups or layer surfaces.
+    fn popup_parent_origin(&self, parent: &WlSurface) -> Option<Point<i32, Logical>> {
+        if let Some(window) = self
+            .space
+            .window_for_surface(parent, WindowSurfaceType::TOPLEVEL)
+        {
+            return self.space.window_location(window);
+        }
+
+        let popup = with_states(parent, |states| {
+            states.data_map.get::<XdgPopupSurfaceData>().map(|data| {
+                let data = data.lock().unwrap();
+                (data.parent.clone(), data.current.geometry.loc)
+            })
+        });
+
+        if let Some((grandparent, location)) = popup {
+            return Some(self.popup_parent_origin(grandparent.as_ref()?)? + location);
+        }
+
+        let output = self.output_for_layer(parent)?;
+        let output_loc = self.space.output_geometry(&output)?.loc;
+        let map = layer_map_for_output(&output);
+        let layer = map.layer_for_surface(parent, WindowSurfaceType::TOPLEVEL)?;
+
+        Some(map.layer_geometry(layer)?.loc + output_loc)
+    }
+
+    /// Geometry of the popup, kept inside of the output its anchor rectangle is on
+    pub fn popup_geometry(
+        &self,
+        popup: &PopupSurface,
+        positioner: PositionerState,
+    ) -> Rectangle<i32, Logical> {
+        let origin = match popup
+            .get_parent_surface()
+            .and_then(|parent| self.popup_parent_origin(&parent))
+        {
+            Some(origin) => origin,
+            None => return positioner.get_geometry(),
+        };
+
+        let anchor = (origin + positioner.anchor_rect.loc).to_f64();
+        let output = self
+            .space
+            .output_under(anchor)
+            .next()
+            .cloned()
+            .or_else(|| self.active_output());
+
+        let mut target = match output.and_then(|output| self.space.output_geometry(&output)) {
+            Some(geometry) => geometry,
+            None => return positioner.get_geometry(),
+        };
+
+        target.loc -= origin;
+
+        unconstrained_geometry(positioner, target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(start: i32, len: i32) -> Span {
+        Span { start, len }
+    }
+
+    fn only(flip: bool, slide: bool, resize: bool) -> Adjustments {
+        Adjustments {
+            slide,
+            flip,
+            resize,
+        }
+    }
+
+    fn unconstrain(popup: Span, flipped: Span, adjustments: Adjustments) -> (i32, i32) {
+        let span = unconstrain_span(popup, flipped, span(0, 1000), adjustments);
+        (span.start, span.len)
+    }
+
+    #[test]
+    fn span_inside_is_kept() {
+        let popup = span(100, 200);
+        assert_eq!(
+            unconstrain(popup, span(-100, 200), only(true, true, true)),
+            (100, 200)
+        );
+    }
+
+    #[test]
+    fn span_without_adjustments_is_kept() {
+        let popup = span(900, 200);
+        assert_eq!(
+            unconstrain(popup, span(700, 200), only(false, false, false)),
+            (900, 200)
+        );
+    }
+
+    #[test]
+    fn span_flip() {
+        let popup = span(900, 200);
+        assert_eq!(
+            unconstrain(popup, span(700, 200), only(true, false, false)),
+            (700, 200)
+        );
+        // Flipping wins over sliding
+        assert_eq!(
+            unconstrain(popup, span(500, 200), only(true, true, false)),
+            (500, 200)
+        );
+    }
+
+    #[test]
+    fn span_flip_that_does_not_help() {
+        let popup = span(500, 700);
+        let flipped = span(-300, 700);
+
+        // The original span is kept, not the flipped one
+        assert_eq!(
+            unconstrain(popup, flipped, only(true, false, false)),
+            (500, 700)
+        );
+        assert_eq!(
+            unconstrain(popup, flipped, only(true, true, false)),
+            (300, 700)
+        );
+        assert_eq!(
+            unconstrain(popup, flipped, only(true, false, true)),
+            (500, 500)
+        );
+    }
+
+    #[test]
+    fn span_slide() {
+        assert_eq!(
+            unconstrain(span(900, 200), span(0, 0), only(false, true, false)),
+            (800, 200)
+        );
+        assert_eq!(
+            unconstrain(span(-50, 200), span(0, 0), only(false, true, false)),
+            (0, 200)
+        );
+        // Too big to fit, the start stays visible
+        assert_eq!(
+            unconstrain(span(-50, 1200), span(0, 0), only(false, true, false)),
+            (0, 1200)
+        );
+    }
+
+    #[test]
+    fn span_resize() {
+        assert_eq!(
+            unconstrain(span(900, 200), span(0, 0), only(false, false, true)),
+            (900, 100)
+        );
+        assert_eq!(
+            unconstrain(span(-50, 200), span(0, 0), only(false, false, true)),
+            (0, 150)
+        );
+        // Nothing would be left
+        assert_eq!(
+            unconstrain(span(1100, 200), span(0, 0), only(false, false, true)),
+            (1100, 200)
+        );
+    }
+
+    #[test]
+    fn span_slide_then_resize() {
+        let popup = span(-50, 1200);
+        assert_eq!(
+            unconstrain(popup, span(0, 0), only(false, true, true)),
+            (0, 1000)
+        );
+    }
+
+    /// Dropdown menu of a menu item in the bottom right corner of a 1920x1080 output
+    fn menu(adjustment: ConstraintAdjustment) -> PositionerState {
+        PositionerState {
+            rect_size: (300, 400).into(),
+            anchor_rect: Rectangle::from_loc_and_size((1800, 1000), (100, 20)),
+            anchor_edges: Anchor::BottomLeft,
+            gravity: Gravity::BottomRight,
+            constraint_adjustment: adjustment,
+            offset: (0, 4).into(),
+            ..Default::default()
+        }
+    }
+
+    fn screen() -> Rectangle<i32, Logical> {
+        Rectangle::from_loc_and_size((0, 0), (1920, 1080))
+    }
+
+    #[test]
+    fn menu_near_edge_without_adjustments() {
+        let geometry = unconstrained_geometry(menu(ConstraintAdjustment::empty()), screen());
+        assert_eq!(
+            geometry,
+            Rectangle::from_loc_and_size((1800, 1024), (300, 400))
+        );
+    }
+
+    #[test]
+    fn menu_near_edge_flips() {
+        let geometry = unconstrained_geometry(
+            menu(ConstraintAdjustment::FlipX | ConstraintAdjustment::FlipY),
+            screen(),
+        );
+
+        // Opens to the left of the right edge of the item and above it, the offset is flipped too
+        assert_eq!(
+            geometry,
+            Rectangle::from_loc_and_size((1600, 596), (300, 400))
+        );
+    }
+
+    #[test]
+    fn menu_near_edge_slides() {
+        let geometry = unconstrained_geometry(
+            menu(ConstraintAdjustment::SlideX | ConstraintAdjustment::SlideY),
+            screen(),
+        );
+        assert_eq!(
+            geometry,
+            Rectangle::from_loc_and_size((1620, 680), (300, 400))
+        );
+    }
+
+    #[test]
+    fn menu_near_edge_resizes() {
+        let geometry = unconstrained_geometry(
+            menu(ConstraintAdjustment::ResizeX | ConstraintAdjustment::ResizeY),
+            screen(),
+        );
+        assert_eq!(
+            geometry,
+            Rectangle::from_loc_and_size((1800, 1024), (120, 56))
+        );
+    }
+
+    #[test]
+    fn tall_menu_falls_back_to_sliding() {
+        let positioner = PositionerState {
+            rect_size: (300, 1000).into(),
+            anchor_rect: Rectangle::from_loc_and_size((100, 480), (100, 20)),
+            ..menu(ConstraintAdjustment::FlipY | ConstraintAdjustment::SlideY)
+        };
+
+        // Neither below nor above the item fits, so it slides up instead
+        let geometry = unconstrained_geometry(positioner, screen());
+        assert_eq!(
+            geometry,
+            Rectangle::from_loc_and_size((100, 80), (300, 1000))
+        );
+    }
+}
@@ -1,24 +1,237 @@
+//! Initial placement of new floating windows
+
 use smithay::{
-    desktop::{Space, Window},
-    utils::{Logical, Point},
+    desktop::{Kind, Window, WindowSurfaceType},
+    utils::{Logical, Point, Rectangle, Size},
 };
 
-pub fn position_window_center(space: &mut Space, window: Window, pointer_pos: Point<f64, Logical>) {
-    window.refresh();
+use crate::{
+    data::{seat::SeatState, window::WindowState},
+    State,
+};
 
-    let loc = space.output_under(pointer_pos).next().map(|output| {
-        let output = space.output_geometry(output).unwrap();
-        let window = window.geometry();
+/// Offset between windows placed by [`Placement::Cascade`]
+const CASCADE_STEP: i32 = 32;
 
-        let x = output.size.w / 2 - window.size.w / 2;
-        let y = output.size.h / 2 - window.size.h / 2;
+/// Where new floating windows are placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// Center of the output under the pointer
+    Center,
+    /// Diagonally below the previous window
+    Cascade,
+    /// Where it overlaps existing windows the least
+    Smart,
+    /// Centered on the pointer
+    UnderPointer,
+    /// Centered on the parent window, windows without a parent are centered on the output
+    CenterOnParent,
+}
 
-        (output.loc.x + x, output.loc.y + y)
-    });
+impl Default for Placement {
+    fn default() -> Self {
+        Self::Center
+    }
+}
+
+impl Placement {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "center" => Self::Center,
+            "cascade" => Self::Cascade,
+            "smart" => Self::Smart,
+            "under-pointer" => Self::UnderPointer,
+            "center-on-parent" => Self::CenterOnParent,
+            _ => return None,
+        })
+    }
 
-    if let Some(loc) = loc {
-        space.map_window(&window, loc, None, false);
-    } else {
-        space.map_window(&window, (0, 0), None, false);
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Center => "center",
+            Self::Cascade => "cascade",
+            Self::Smart => "smart",
+            Self::UnderPointer => "under-pointer",
+            Self::CenterOnParent => "center-on-parent",
+        }
     }
 }
+
+impl State {
+    /// Place a freshly mapped floating window according to the configured [`Placement`]
+    pub fn place_window(&mut self, window: &Window) {
+        window.refresh();
+
+        let output = self
+            .output_for_window(window)
+            .or_else(|| self.active_output());
+
+        let area = match output.as_ref().and_then(|o| self.usable_area(o)) {
+            Some(area) => area,
+            None => {
+                self.set_window_location(window, (0, 0).into());
+                return;
+            }
+        };
+
+        // Location set by a window rule
+        if let Some(position) = WindowState::for_window(window).take_initial_position() {
+            let output_loc = output
+                .and_then(|o| self.space.output_geometry(&o))
+                .map(|geometry| geometry.loc)
+                .unwrap_or_default();
+
+            self.set_window_location(window, output_loc + position);
+            return;
+        }
+
+        let size = window.geometry().size;
+        let pointer_pos = SeatState::for_seat(&self.seat).pointer_pos();
+
+        let others: Vec<Rectangle<i32, Logical>> = self
+            .space
+            .windows()
+            .filter(|w| *w != window)
+            .filter_map(|w| {
+                let loc = self.space.window_location(w)?;
+                Some(Rectangle::from_loc_and_size(loc, w.geometry().size))
+            })
+            .filter(|rect| rect.overlaps(area))
+            .collect();
+
+        let loc = match self.config.windows().placement() {
+            Placement::Center => center_in(area, size),
+            Placement::Cascade => cascade(area, size, &others),
+            Placement::Smart => smart(area, size, &others),
+            Placement::UnderPointer => {
+                let loc = pointer_pos.to_i32_round() - Point::from((size.w / 2, size.h / 2));
+                clamp_into(area, Rectangle::from_loc_and_size(loc, size))
+            }
+            Placement::CenterOnParent => match self.parent_geometry(window) {
+                Some(parent) => clamp_into(
+                    area,
+                    Rectangle::from_loc_and_size(center_in(parent, size), size),
+                ),
+                None => center_in(area, size),
+            },
+        };
+
+        self.set_window_location(window, loc);
+    }
+
+    /// Geometry of the window the toplevel is a dialog of
+    fn parent_geometry(&self, window: &Window) -> Option<Rectangle<i32, Logical>> {
+        let parent = match window.toplevel() {
+            Kind::Xdg(xdg) => xdg.parent()?,
+            // Transient X11 windows are not tracked
+            #[cfg(feature = "xwayland")]
+            Kind::X11(_) => return None,
+        };
+
+        let parent = self
+            .space
+            .window_for_surface(&parent, WindowSurfaceType::TOPLEVEL)?;
+        let loc = self.space.window_location(parent)?;
+
+        Some(Rectangle::from_loc_and_size(loc, parent.geometry().size))
+    }
+}
+
+fn center_in(area: Rectangle<i32, Logical>, size: Size<i32, Logical>) -> Point<i32, Logical> {
+    let x = area.size.w / 2 - size.w / 2;
+    let y = area.size.h / 2 - size.h / 2;
+
+    (area.loc.x + x, area.loc.y + y).into()
+}
+
+/// Move the rectangle inside of the area, windows bigger than the area stick to its top left corner
+fn clamp_into(area: Rectangle<i32, Logical>, rect: Rectangle<i32, Logical>) -> Point<i32, Logical> {
+    let x = rect
+        .loc
+        .x
+        .min(area.loc.x + area.size.w - rect.size.w)
+        .max(area.loc.x);
+    let y = rect
+        .loc
+        .y
+        .min(area.loc.y + area.size.h - rect.size.h)
+        .max(area.loc.y);
+
+    (x, y).into()
+}
+
+/// First free step of the diagonal, starting over from the top once it leaves the area
+fn cascade(
+    area: Rectangle<i32, Logical>,
+    size: Size<i32, Logical>,
+    others: &[Rectangle<i32, Logical>],
+) -> Point<i32, Logical> {
+    let mut loc = area.loc;
+
+    while others.iter().any(|other| other.loc == loc) {
+        loc = loc + Point::from((CASCADE_STEP, CASCADE_STEP));
+
+        let fits = loc.x + size.w <= area.loc.x + area.size.w
+            && loc.y + size.h <= area.loc.y + area.size.h;
+
+        if !fits {
+            return area.loc;
+        }
+    }
+
+    loc
+}
+
+/// Location with the least overlap with other windows
+///
+/// Candidates are the corners of the area and spots right next to other windows.
+fn smart(
+    area: Rectangle<i32, Logical>,
+    size: Size<i32, Logical>,
+    others: &[Rectangle<i32, Logical>],
+) -> Point<i32, Logical> {
+    if others.is_empty() {
+        return center_in(area, size);
+    }
+
+    let mut xs = vec![area.loc.x, area.loc.x + area.size.w - size.w];
+    let mut ys = vec![area.loc.y, area.loc.y + area.size.h - size.h];
+
+    for other in others {
+        xs.push(other.loc.x + other.size.w);
+        xs.push(other.loc.x - size.w);
+        ys.push(other.loc.y + other.size.h);
+        ys.push(other.loc.y - size.h);
+    }
+
+    let overlap = |loc: Point<i32, Logical>| -> i64 {
+        let rect = Rectangle::from_loc_and_size(loc, size);
+
+        others
+            .iter()
+            .map(|other| {
+                let w = (rect.loc.x + rect.size.w).min(other.loc.x + other.size.w)
+                    - rect.loc.x.max(other.loc.x);
+                let h = (rect.loc.y + rect.size.h).min(other.loc.y + other.size.h)
+                    - rect.loc.y.max(other.loc.y);
+
+                w.max(0) as i64 * h.max(0) as i64
+            })
+            .sum()
+    };
+
+    let mut best: Option<(i64, Point<i32, Logical>)> = None;
+
+    for &y in &ys {
+        for &x in &xs {
+            let loc = clamp_into(area, Rectangle::from_loc_and_size((x, y), size));
+            let overlap = overlap(loc);
+
+            if best.map(|(best, _)| overlap < best).unwrap_or(true) {
+                best = Some((overlap, loc));
+            }
+        }
+    }
+
+    best.map(|(_, loc)| loc).unwrap_or(area.loc)
+}
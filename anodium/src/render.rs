@@ -0,0 +1,327 @@
+//! Windows drawn by us instead of the [`Space`](smithay::desktop::Space)
+//!
+//! The space always draws windows fully opaque and has no idea about server-side decorations,
+//! focus borders or animations. So outputs are rendered by a space of their own that only
+//! holds layer surfaces, windows of [`State::space`] are passed to it as custom elements.
+
+use std::{rc::Rc, time::Instant};
+
+use anodium_framework::quad::{QuadElement, QuadPipeline};
+use smithay::{
+    backend::renderer::{
+        gles2::{Gles2Error, Gles2Frame, Gles2Renderer},
+        utils::{import_surface_tree, RendererSurfaceStateUserData},
+        Frame,
+    },
+    desktop::{
+        space::{RenderElement, RenderZindex, SpaceOutputTuple},
+        PopupManager, Window,
+    },
+    output::Output,
+    reexports::wayland_server::protocol::wl_surface::WlSurface,
+    utils::{Logical, Physical, Point, Rectangle, Scale, Size},
+    wayland::compositor::{with_surface_tree_upward, SubsurfaceCachedState, TraversalAction},
+};
+
+use crate::{animation::Frame, data::window::WindowState, decorations::DecorationElement, State};
+
+/// Everything that decides how a window is drawn, besides its surfaces
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowLook {
+    pub frame: Frame,
+    pub focused: bool,
+    pub opacity: f32,
+}
+
+pub struct WindowElement {
+    window: Window,
+    /// Where the window geometry is drawn, its size differs from the real one while animating
+    frame: Frame,
+    /// The [`WindowLook`] changed since the last frame
+    damaged: bool,
+}
+
+impl WindowElement {
+    pub fn new(window: Window, frame: Frame) -> Self {
+        Self {
+            window,
+            frame,
+            damaged: true,
+        }
+    }
+
+    /// Did the look of the window change since the last frame, commits are tracked either way
+    pub fn with_damaged(mut self, damaged: bool) -> Self {
+        self.damaged = damaged;
+        self
+    }
+
+    fn scale(&self) -> Scale<f64> {
+        self.frame.scale_of(self.window.geometry().size.to_f64())
+    }
+
+    /// Location of the root surface
+    fn surface_location(&self) -> Point<f64, Logical> {
+        let geometry_loc = self.window.geometry().loc.to_f64();
+        let scale = self.scale();
+
+        self.frame.rect.loc - Point::from((geometry_loc.x * scale.x, geometry_loc.y * scale.y))
+    }
+
+    /// Area of the window and its popups, relative to the root surface
+    fn bbox(&self) -> Rectangle<f64, Logical> {
+        let bbox = self.window.bbox_with_popups().to_f64();
+        let scale = self.scale();
+
+        Rectangle::from_loc_and_size(
+            (bbox.loc.x * scale.x, bbox.loc.y * scale.y),
+            (bbox.size.w * scale.x, bbox.size.h * scale.y),
+        )
+    }
+}
+
+impl RenderElement<Gles2Renderer> for WindowElement {
+    fn id(&self) -> usize {
+        WindowState::for_window(&self.window).id()
+    }
+
+    fn location(&self, scale: impl Into<Scale<f64>>) -> Point<f64, Physical> {
+        self.surface_location().to_physical(scale)
+    }
+
+    fn geometry(&self, scale: impl Into<Scale<f64>>) -> Rectangle<i32, Physical> {
+        let mut bbox = self.bbox();
+        bbox.loc += self.surface_location();
+        bbox.to_physical(scale).to_i32_up()
+    }
+
+    fn accumulated_damage(
+        &self,
+        scale: impl Into<Scale<f64>>,
+        for_values: Option<SpaceOutputTuple<'_, '_>>,
+    ) -> Vec<Rectangle<i32, Physical>> {
+        let scale = scale.into();
+
+        // Always collected, so the commits are not reported again later
+        let damage = self.window.accumulated_damage(
+            (0.0, 0.0),
+            scale,
+            for_values.map(|SpaceOutputTuple(space, output)| (space, output)),
+        );
+
+        // Damage of surfaces is unscaled, so animated windows are redrawn as a whole
+        if self.damaged || self.scale() != Scale::from(1.0) {
+            return vec![self.bbox().to_physical(scale).to_i32_up()];
+        }
+
+        damage
+    }
+
+    fn opaque_regions(
+        &self,
+        _scale: impl Into<Scale<f64>>,
+    ) -> Option<Vec<Rectangle<i32, Physical>>> {
+        None
+    }
+
+    fn z_index(&self) -> u8 {
+        RenderZindex::Shell as u8
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Gles2Renderer,
+        frame: &mut Gles2Frame,
+        scale: impl Into<Scale<f64>>,
+        location: Point<f64, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        log: &slog::Logger,
+    ) -> Result<(), Gles2Error> {
+        let output_scale = scale.into();
+        let window_scale = self.scale();
+        let scale = Scale {
+            x: output_scale.x * window_scale.x,
+            y: output_scale.y * window_scale.y,
+        };
+        let alpha = self.frame.alpha;
+        let surface = self.window.toplevel().wl_surface();
+
+        draw_surface_tree(
+            renderer, frame, surface, scale, location, damage, alpha, log,
+        )?;
+
+        let geometry_loc = self.window.geometry().loc;
+
+        for (popup, popup_loc) in PopupManager::popups_for_surface(surface) {
+            let offset = (geometry_loc + popup_loc - popup.geometry().loc)
+                .to_f64()
+                .to_physical(scale);
+
+            draw_surface_tree(
+                renderer,
+                frame,
+                popup.wl_surface(),
+                scale,
+                location + offset,
+                damage,
+                alpha,
+                log,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Same as [`smithay::backend::renderer::utils::draw_surface_tree`], but with an alpha
+///
+/// `damage` is relative to the output, like `location`.
+#[allow(clippy::too_many_arguments)]
+fn draw_surface_tree(
+    renderer: &mut Gles2Renderer,
+    frame: &mut Gles2Frame,
+    surface: &WlSurface,
+    scale: Scale<f64>,
+    location: Point<f64, Physical>,
+    damage: &[Rectangle<i32, Physical>],
+    alpha: f32,
+    log: &slog::Logger,
+) -> Result<(), Gles2Error> {
+    import_surface_tree(renderer, surface, log)?;
+
+    let mut result = Ok(());
+
+    with_surface_tree_upward(
+        surface,
+        location,
+        |_, states, location| {
+            let mut location = *location;
+
+            if states.role == Some("subsurface") {
+                let current = states.cached_state.current::<SubsurfaceCachedState>();
+                location += current.location.to_f64().to_physical(scale);
+            }
+
+            TraversalAction::DoChildren(location)
+        },
+        |_, states, location| {
+            let mut location = *location;
+
+            if states.role == Some("subsurface") {
+                let current = states.cached_state.current::<SubsurfaceCachedState>();
+                location += current.location.to_f64().to_physical(scale);
+            }
+
+            let data = match states.data_map.get::<RendererSurfaceStateUserData>() {
+                Some(data) => data.borrow(),
+                None => return,
+            };
+
+            if let Some(texture) = data.texture(renderer) {
+                let dimensions = data.surface_size().unwrap_or_default();
+                let rect =
+                    Rectangle::from_loc_and_size(location, dimensions.to_f64().to_physical(scale))
+                        .to_i32_round();
+
+                // Relative to the surface, drawing outside of it would blend twice
+                let damage: Vec<_> = damage
+                    .iter()
+                    .filter_map(|damage| damage.intersection(rect))
+                    .map(|damage| Rectangle::from_loc_and_size(damage.loc - rect.loc, damage.size))
+                    .collect();
+
+                if damage.is_empty() {
+                    return;
+                }
+
+                if let Err(err) = frame.render_texture_at(
+                    texture,
+                    location.to_i32_round(),
+                    data.buffer_scale(),
+                    scale,
+                    data.buffer_transform(),
+                    &damage,
+                    alpha,
+                ) {
+                    result = Err(err);
+                }
+            }
+        },
+        |_, _, _| true,
+    );
+
+    result
+}
+
+/// Elements of a single window, in the drawing order
+pub struct WindowElements {
+    pub border: Option<QuadElement>,
+    pub decoration: Option<DecorationElement>,
+    pub window: WindowElement,
+}
+
+impl State {
+    /// Elements of every window of the space at `now`, in the stacking order
+    ///
+    /// Decorations and borders are skipped without the `pipeline`.
+    pub fn window_elements(
+        &self,
+        output: &Output,
+        pipeline: Option<Rc<QuadPipeline>>,
+        output_size: Size<f64, Physical>,
+        now: Instant,
+    ) -> Vec<WindowElements> {
+        let focused = self.focused_window();
+
+        self.space
+            .windows()
+            .filter_map(|window| {
+                let location = self.space.window_location(window)?;
+                let focused = focused.as_ref() == Some(window);
+                let window_state = WindowState::for_window(window);
+
+                let real = Frame::new(Rectangle::from_loc_and_size(
+                    location,
+                    window.geometry().size,
+                ));
+
+                let frame = match window_state.animation() {
+                    Some(animation) if animation.is_done(now) => {
+                        window_state.set_animation(None);
+                        real
+                    }
+                    Some(animation) => animation.frame(now, real),
+                    None => real,
+                };
+
+                window_state.set_drawn_frame(frame);
+
+                // Moves and resizes are damaged by the space, this covers the rest
+                let damaged = window_state.update_drawn_look(
+                    output,
+                    WindowLook {
+                        frame,
+                        focused,
+                        opacity: window_state.opacity(),
+                    },
+                );
+
+                let border = pipeline.clone().and_then(|pipeline| {
+                    self.border_element(pipeline, window, frame, focused, output_size)
+                });
+                let decoration = pipeline.clone().and_then(|pipeline| {
+                    self.decoration_element(pipeline, window, frame, focused, output_size)
+                });
+
+                let alpha = frame.alpha * window_state.opacity();
+
+                Some(WindowElements {
+                    border: border.map(|border| border.with_damaged(damaged)),
+                    decoration: decoration.map(|decoration| decoration.with_damaged(damaged)),
+                    window: WindowElement::new(window.clone(), Frame { alpha, ..frame })
+                        .with_damaged(damaged),
+                })
+            })
+            .collect()
+    }
+}
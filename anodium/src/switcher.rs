@@ -0,0 +1,137 @@
+//! Alt+Tab like window switcher
+//!
+//! A switcher binding with modifiers opens the switcher, every further press moves the selection,
+//! and the selected window gets focused once the modifiers are released.
+
+use smithay::{
+    desktop::Window,
+    utils::{Logical, Rectangle},
+};
+
+use crate::{
+    data::{seat::SeatState, window::WindowState},
+    keybindings::KeyModifiers,
+    State,
+};
+
+/// Which way the selection of the switcher moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwitchDirection {
+    /// Towards less recently focused windows
+    Forward,
+    Backward,
+}
+
+#[derive(Debug)]
+pub struct WindowSwitcher {
+    /// Most recently focused first
+    windows: Vec<Window>,
+    selected: usize,
+    /// Modifiers that keep the switcher open while held
+    modifiers: KeyModifiers,
+}
+
+impl WindowSwitcher {
+    fn step(&mut self, direction: SwitchDirection) {
+        self.windows.retain(|window| window.toplevel().alive());
+
+        let len = self.windows.len();
+        if len == 0 {
+            return;
+        }
+
+        self.selected = match direction {
+            SwitchDirection::Forward => (self.selected + 1) % len,
+            SwitchDirection::Backward => (self.selected + len - 1) % len,
+        };
+    }
+
+    fn selected(&self) -> Option<&Window> {
+        self.windows
+            .get(self.selected)
+            .filter(|window| window.toplevel().alive())
+    }
+}
+
+impl State {
+    /// Windows that can get keyboard focus on the active workspaces, most recently focused first
+    ///
+    /// Windows that never had focus follow in the stacking order, top first.
+    pub fn focus_candidates(&self) -> Vec<Window> {
+        let mut windows: Vec<Window> = self.space.windows().rev().cloned().collect();
+        // Minimized windows get shown once they are focused
+        windows.extend(self.minimized_windows());
+        windows.retain(|window| !WindowState::for_window(window).is_no_focus());
+
+        let history = SeatState::for_seat(&self.seat).focus_history();
+        windows.sort_by_key(|window| {
+            history
+                .iter()
+                .position(|w| w == window)
+                .unwrap_or(usize::MAX)
+        });
+
+        windows
+    }
+
+    /// Open the switcher, or move its selection if it is already open
+    ///
+    /// Without held modifiers there is nothing to wait for, so the window gets focused right away.
+    pub fn switch_window(&mut self, direction: SwitchDirection) {
+        if let Some(switcher) = self.switcher.as_mut() {
+            switcher.step(direction);
+            return;
+        }
+
+        // Shift is commonly used to reverse the direction, it does not keep the switcher open
+        let modifiers = SeatState::for_seat(&self.seat).modifiers() - KeyModifiers::SHIFT;
+
+        let mut switcher = WindowSwitcher {
+            windows: self.focus_candidates(),
+            selected: 0,
+            modifiers,
+        };
+
+        // The focused window is the first one, unless nothing is focused
+        if self.focused_window().is_some() || direction == SwitchDirection::Backward {
+            switcher.step(direction);
+        }
+
+        if modifiers.is_empty() {
+            if let Some(window) = switcher.selected().cloned() {
+                self.focus_window(&window);
+            }
+        } else {
+            self.switcher = Some(switcher);
+        }
+    }
+
+    /// Focus the selected window once the switcher modifiers got released
+    ///
+    /// Should be called after every keyboard event.
+    pub fn update_switcher(&mut self) {
+        let released = match self.switcher.as_ref() {
+            Some(switcher) => {
+                let modifiers = SeatState::for_seat(&self.seat).modifiers();
+                !modifiers.intersects(switcher.modifiers)
+            }
+            None => return,
+        };
+
+        if !released {
+            return;
+        }
+
+        if let Some(window) = self.switcher.take().and_then(|s| s.selected().cloned()) {
+            self.focus_window(&window);
+        }
+    }
+
+    /// Geometry of the window selected in the switcher, if it is visible
+    pub fn switcher_highlight(&self) -> Option<Rectangle<i32, Logical>> {
+        let window = self.switcher.as_ref()?.selected()?;
+        let loc = self.space.window_location(window)?;
+
+        Some(Rectangle::from_loc_and_size(loc, window.geometry().size))
+    }
+}
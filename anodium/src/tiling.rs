@@ -0,0 +1,801 @@
+//! Tiling layout
+//!
+//! Windows are leaves of a tree of containers, every container splits its area
+//! between children either horizontally or vertically.
+//! The area is split evenly, unless a window asks for a bigger minimal size.
+
+use smithay::{
+    desktop::{Kind, Window},
+    reexports::wayland_protocols::xdg::shell::server::xdg_toplevel,
+    utils::{Logical, Point, Rectangle, Size},
+    wayland::{compositor, shell::xdg::SurfaceCachedState},
+};
+
+use crate::State;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    /// Children are placed next to each other
+    Horizontal,
+    /// Children are placed on top of each other
+    Vertical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "left" => Self::Left,
+            "right" => Self::Right,
+            "up" => Self::Up,
+            "down" => Self::Down,
+            _ => return None,
+        })
+    }
+}
+
+/// Minimal and maximal size requested by the window, zero means unbounded
+pub fn size_constraints(window: &Window) -> (Size<i32, Logical>, Size<i32, Logical>) {
+    compositor::with_states(window.toplevel().wl_surface(), |states| {
+        let data = states.cached_state.current::<SurfaceCachedState>();
+        (data.min_size, data.max_size)
+    })
+}
+
+/// Leaf of the tiling tree
+pub trait Tile: Clone + PartialEq {
+    /// Minimal and maximal size, zero means unbounded
+    fn size_constraints(&self) -> (Size<i32, Logical>, Size<i32, Logical>);
+}
+
+impl Tile for Window {
+    fn size_constraints(&self) -> (Size<i32, Logical>, Size<i32, Logical>) {
+        size_constraints(self)
+    }
+}
+
+#[derive(Debug)]
+enum Node<W> {
+    Window(W),
+    Container(Container<W>),
+}
+
+impl<W: Tile> Node<W> {
+    fn min_size(&self) -> Size<i32, Logical> {
+        match self {
+            Node::Window(window) => {
+                let (min, _) = window.size_constraints();
+                (min.w.max(1), min.h.max(1)).into()
+            }
+            Node::Container(container) => container.min_size(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Container<W> {
+    orientation: Orientation,
+    children: Vec<Node<W>>,
+}
+
+impl<W: Tile> Container<W> {
+    fn new(orientation: Orientation) -> Self {
+        Self {
+            orientation,
+            children: Vec::new(),
+        }
+    }
+
+    fn min_size(&self) -> Size<i32, Logical> {
+        let sizes = self.children.iter().map(Node::min_size);
+
+        match self.orientation {
+            Orientation::Horizontal => sizes.fold((0, 0).into(), |acc: Size<i32, Logical>, s| {
+                (acc.w + s.w, acc.h.max(s.h)).into()
+            }),
+            Orientation::Vertical => sizes.fold((0, 0).into(), |acc: Size<i32, Logical>, s| {
+                (acc.w.max(s.w), acc.h + s.h).into()
+            }),
+        }
+    }
+
+    fn contains(&self, window: &W) -> bool {
+        self.children.iter().any(|child| match child {
+            Node::Window(w) => w == window,
+            Node::Container(container) => container.contains(window),
+        })
+    }
+
+    fn windows<'a>(&'a self, out: &mut Vec<&'a W>) {
+        for child in self.children.iter() {
+            match child {
+                Node::Window(w) => out.push(w),
+                Node::Container(container) => container.windows(out),
+            }
+        }
+    }
+
+    fn for_each_window(&mut self, f: &mut dyn FnMut(&mut W)) {
+        for child in self.children.iter_mut() {
+            match child {
+                Node::Window(w) => f(w),
+                Node::Container(container) => container.for_each_window(f),
+            }
+        }
+    }
+
+    /// Insert `window` next to `sibling`, returns the window back if the sibling is not here
+    fn insert_next_to(&mut self, window: W, sibling: &W, split: Orientation) -> Result<(), W> {
+        let id = self
+            .children
+            .iter()
+            .position(|child| matches!(child, Node::Window(w) if w == sibling));
+
+        if let Some(id) = id {
+            if self.children.len() == 1 {
+                self.orientation = split;
+            }
+
+            if self.orientation == split {
+                self.children.insert(id + 1, Node::Window(window));
+            } else {
+                let mut container = Container::new(split);
+                container.children.push(Node::Window(sibling.clone()));
+                container.children.push(Node::Window(window));
+
+                self.children[id] = Node::Container(container);
+            }
+
+            return Ok(());
+        }
+
+        let mut window = window;
+        for child in self.children.iter_mut() {
+            if let Node::Container(container) = child {
+                match container.insert_next_to(window, sibling, split) {
+                    Ok(()) => return Ok(()),
+                    Err(w) => window = w,
+                }
+            }
+        }
+
+        Err(window)
+    }
+
+    fn remove(&mut self, window: &W) -> bool {
+        let id = self
+            .children
+            .iter()
+            .position(|child| matches!(child, Node::Window(w) if w == window));
+
+        let removed = match id {
+            Some(id) => {
+                self.children.remove(id);
+                true
+            }
+            None => self.children.iter_mut().any(|child| match child {
+                Node::Container(container) => container.remove(window),
+                Node::Window(_) => false,
+            }),
+        };
+
+        if removed {
+            self.normalize();
+        }
+
+        removed
+    }
+
+    /// Drop empty containers and flatten the ones with a single child
+    fn normalize(&mut self) {
+        self.children.retain(|child| match child {
+            Node::Container(container) => !container.children.is_empty(),
+            Node::Window(_) => true,
+        });
+
+        for child in self.children.iter_mut() {
+            if let Node::Container(container) = child {
+                if container.children.len() == 1 {
+                    *child = container.children.pop().unwrap();
+                }
+            }
+        }
+    }
+
+    fn arrange(&self, area: Rectangle<i32, Logical>, out: &mut Vec<(W, Rectangle<i32, Logical>)>) {
+        if self.children.is_empty() {
+            return;
+        }
+
+        let (total, mins): (i32, Vec<i32>) = match self.orientation {
+            Orientation::Horizontal => (
+                area.size.w,
+                self.children.iter().map(|c| c.min_size().w).collect(),
+            ),
+            Orientation::Vertical => (
+                area.size.h,
+                self.children.iter().map(|c| c.min_size().h).collect(),
+            ),
+        };
+
+        let mut offset = 0;
+
+        for (child, length) in self.children.iter().zip(distribute(total, &mins)) {
+            let rect = match self.orientation {
+                Orientation::Horizontal => Rectangle::from_loc_and_size(
+                    (area.loc.x + offset, area.loc.y),
+                    (length, area.size.h),
+                ),
+                Orientation::Vertical => Rectangle::from_loc_and_size(
+                    (area.loc.x, area.loc.y + offset),
+                    (area.size.w, length),
+                ),
+            };
+
+            offset += length;
+
+            match child {
+                Node::Window(window) => out.push((window.clone(), fit_window(window, rect))),
+                Node::Container(container) => container.arrange(rect, out),
+            }
+        }
+    }
+}
+
+/// Split `total` into parts as even as possible, while giving every part at least its minimum
+fn distribute(total: i32, mins: &[i32]) -> Vec<i32> {
+    let mut fixed = vec![false; mins.len()];
+
+    loop {
+        let free_count = fixed.iter().filter(|f| !**f).count() as i32;
+        let fixed_total: i32 = mins
+            .iter()
+            .zip(fixed.iter())
+            .filter(|(_, f)| **f)
+            .map(|(min, _)| *min)
+            .sum();
+
+        let share = if free_count > 0 {
+            (total - fixed_total).max(0) / free_count
+        } else {
+            0
+        };
+
+        let mut changed = false;
+        for (min, fixed) in mins.iter().zip(fixed.iter_mut()) {
+            if !*fixed && *min > share {
+                *fixed = true;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            let mut sizes: Vec<i32> = mins
+                .iter()
+                .zip(fixed.iter())
+                .map(|(min, fixed)| if *fixed { *min } else { share })
+                .collect();
+
+            // Give the rounding leftover to the last free part
+            let used: i32 = sizes.iter().sum();
+            if let Some(id) = fixed.iter().rposition(|f| !*f) {
+                sizes[id] += (total - used).max(0);
+            }
+
+            return sizes;
+        }
+    }
+}
+
+/// Shrink the cell to the maximal size of the window, keeping it centered
+fn fit_window<W: Tile>(window: &W, cell: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+    let (_, max) = window.size_constraints();
+    fit_max_size(cell, max)
+}
+
+/// Shrink the cell to `max`, keeping it centered, zero means unlimited
+fn fit_max_size(cell: Rectangle<i32, Logical>, max: Size<i32, Logical>) -> Rectangle<i32, Logical> {
+    let w = if max.w > 0 {
+        cell.size.w.min(max.w)
+    } else {
+        cell.size.w
+    };
+    let h = if max.h > 0 {
+        cell.size.h.min(max.h)
+    } else {
+        cell.size.h
+    };
+
+    Rectangle::from_loc_and_size(
+        (
+            cell.loc.x + (cell.size.w - w) / 2,
+            cell.loc.y + (cell.size.h - h) / 2,
+        ),
+        (w, h),
+    )
+}
+
+/// Tree of tiled windows of a single workspace
+#[derive(Debug)]
+pub struct TilingLayout<W = Window> {
+    root: Container<W>,
+    /// Orientation used when the next window gets inserted
+    split: Orientation,
+}
+
+impl<W: Tile> Default for TilingLayout<W> {
+    fn default() -> Self {
+        Self {
+            root: Container::new(Orientation::Horizontal),
+            split: Orientation::Horizontal,
+        }
+    }
+}
+
+impl<W: Tile> TilingLayout<W> {
+    pub fn set_split(&mut self, split: Orientation) {
+        self.split = split;
+    }
+
+    pub fn contains(&self, window: &W) -> bool {
+        self.root.contains(window)
+    }
+
+    pub fn windows(&self) -> Vec<&W> {
+        let mut out = Vec::new();
+        self.root.windows(&mut out);
+        out
+    }
+
+    /// Insert the window next to `sibling`, or at the end if the sibling is not tiled
+    pub fn insert(&mut self, window: W, sibling: Option<&W>) {
+        self.insert_with_split(window, sibling, self.split);
+    }
+
+    /// Same as [`TilingLayout::insert`], but with explicit split orientation
+    pub fn insert_with_split(&mut self, window: W, sibling: Option<&W>, split: Orientation) {
+        if self.contains(&window) {
+            return;
+        }
+
+        let window = match sibling {
+            Some(sibling) => match self.root.insert_next_to(window, sibling, split) {
+                Ok(()) => return,
+                Err(window) => window,
+            },
+            None => window,
+        };
+
+        self.root.children.push(Node::Window(window));
+    }
+
+    pub fn remove(&mut self, window: &W) -> bool {
+        let removed = self.root.remove(window);
+
+        // Root with a single container child is the same as that child
+        if self.root.children.len() == 1 {
+            if let Node::Container(_) = self.root.children[0] {
+                if let Some(Node::Container(child)) = self.root.children.pop() {
+                    self.root = child;
+                }
+            }
+        }
+
+        removed
+    }
+
+    /// Remove all windows, returning them
+    pub fn clear(&mut self) -> Vec<W> {
+        let windows = self.windows().into_iter().cloned().collect();
+        self.root = Container::new(Orientation::Horizontal);
+        windows
+    }
+
+    /// Swap places of two tiled windows
+    pub fn swap(&mut self, a: &W, b: &W) {
+        if !self.contains(a) || !self.contains(b) {
+            return;
+        }
+
+        self.root.for_each_window(&mut |w| {
+            if w == a {
+                *w = b.clone();
+            } else if w == b {
+                *w = a.clone();
+            }
+        });
+    }
+
+    /// Compute rectangles of all tiled windows inside of `area`
+    pub fn arrange(&self, area: Rectangle<i32, Logical>) -> Vec<(W, Rectangle<i32, Logical>)> {
+        let mut out = Vec::new();
+        self.root.arrange(area, &mut out);
+        out
+    }
+}
+
+/// Closest rectangle in the direction from `from`, that overlaps with it on the other axis
+pub fn neighbour<'a, T>(
+    items: &'a [(T, Rectangle<i32, Logical>)],
+    from: Rectangle<i32, Logical>,
+    direction: Direction,
+) -> Option<&'a T> {
+    let center = |rect: &Rectangle<i32, Logical>| -> Point<i32, Logical> {
+        (rect.loc.x + rect.size.w / 2, rect.loc.y + rect.size.h / 2).into()
+    };
+
+    let from_center = center(&from);
+
+    items
+        .iter()
+        .filter(|(_, rect)| {
+            let c = center(rect);
+
+            let overlaps_x =
+                rect.loc.x < from.loc.x + from.size.w && from.loc.x < rect.loc.x + rect.size.w;
+            let overlaps_y =
+                rect.loc.y < from.loc.y + from.size.h && from.loc.y < rect.loc.y + rect.size.h;
+
+            match direction {
+                Direction::Left => c.x < from_center.x && overlaps_y,
+                Direction::Right => c.x > from_center.x && overlaps_y,
+                Direction::Up => c.y < from_center.y && overlaps_x,
+                Direction::Down => c.y > from_center.y && overlaps_x,
+            }
+        })
+        .min_by_key(|(_, rect)| {
+            let c = center(rect);
+            (c.x - from_center.x).abs() + (c.y - from_center.y).abs()
+        })
+        .map(|(item, _)| item)
+}
+
+impl State {
+    /// Tell the window about its new size, and that it is tiled or not
+    pub fn configure_tiled(&mut self, window: &Window, size: Option<Size<i32, Logical>>) {
+        match window.toplevel() {
+            Kind::Xdg(xdg) => {
+                xdg.with_pending_state(|state| {
+                    let tiled = [
+                        xdg_toplevel::State::TiledLeft,
+                        xdg_toplevel::State::TiledRight,
+                        xdg_toplevel::State::TiledTop,
+                        xdg_toplevel::State::TiledBottom,
+                    ];
+
+                    for s in tiled {
+                        if size.is_some() {
+                            state.states.set(s);
+                        } else {
+                            state.states.unset(s);
+                        }
+                    }
+
+                    if size.is_some() {
+                        state.size = size;
+                    }
+                });
+
+                xdg.send_configure();
+            }
+            #[cfg(feature = "xwayland")]
+            Kind::X11(_) => {
+                if let Some(size) = size {
+                    let location = self.space.window_location(window);
+                    crate::xwayland::resize_window(self, window, location, size);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+        Rectangle::from_loc_and_size((x, y), (w, h))
+    }
+
+    #[test]
+    fn distribute_evenly() {
+        assert_eq!(distribute(900, &[0, 0, 0]), vec![300, 300, 300]);
+    }
+
+    #[test]
+    fn distribute_rounding_leftover_to_last() {
+        assert_eq!(distribute(1000, &[0, 0, 0]), vec![333, 333, 334]);
+    }
+
+    #[test]
+    fn distribute_respects_min_size() {
+        assert_eq!(distribute(900, &[500, 0, 0]), vec![500, 200, 200]);
+        assert_eq!(distribute(900, &[0, 350, 0]), vec![275, 350, 275]);
+    }
+
+    #[test]
+    fn distribute_min_size_fixes_other_parts() {
+        // Fixing the first part shrinks the share below the min of the second one
+        assert_eq!(distribute(1000, &[600, 250, 0]), vec![600, 250, 150]);
+    }
+
+    #[test]
+    fn distribute_overflow() {
+        assert_eq!(distribute(500, &[400, 400]), vec![400, 400]);
+        assert_eq!(distribute(500, &[400, 400, 0]), vec![400, 400, 0]);
+    }
+
+    #[test]
+    fn fit_max_size_centers() {
+        let cell = rect(100, 100, 800, 600);
+
+        assert_eq!(fit_max_size(cell, (0, 0).into()), cell);
+        assert_eq!(
+            fit_max_size(cell, (400, 0).into()),
+            rect(300, 100, 400, 600)
+        );
+        assert_eq!(
+            fit_max_size(cell, (1000, 200).into()),
+            rect(100, 300, 800, 200)
+        );
+    }
+
+    /// Left half, right half split into top and bottom, top split again into left and right
+    ///
+    /// ```text
+    /// +-----+--+--+
+    /// |     |b |c |
+    /// |  a  +--+--+
+    /// |     |  d  |
+    /// +-----+-----+
+    /// ```
+    fn nested() -> Vec<(&'static str, Rectangle<i32, Logical>)> {
+        vec![
+            ("a", rect(0, 0, 1000, 1000)),
+            ("b", rect(1000, 0, 500, 500)),
+            ("c", rect(1500, 0, 500, 500)),
+            ("d", rect(1000, 500, 1000, 500)),
+        ]
+    }
+
+    fn neighbour_of(name: &str, direction: Direction) -> Option<&'static str> {
+        let items = nested();
+        let (_, from) = items.iter().find(|(n, _)| *n == name).unwrap();
+
+        neighbour(&items, *from, direction).copied()
+    }
+
+    #[test]
+    fn neighbour_across_containers() {
+        assert_eq!(neighbour_of("a", Direction::Right), Some("b"));
+        assert_eq!(neighbour_of("b", Direction::Left), Some("a"));
+        assert_eq!(neighbour_of("c", Direction::Left), Some("b"));
+        assert_eq!(neighbour_of("d", Direction::Left), Some("a"));
+    }
+
+    #[test]
+    fn neighbour_inside_nested_container() {
+        assert_eq!(neighbour_of("b", Direction::Right), Some("c"));
+        assert_eq!(neighbour_of("b", Direction::Down), Some("d"));
+        assert_eq!(neighbour_of("c", Direction::Down), Some("d"));
+        // Both are equally close, the first one wins
+        assert_eq!(neighbour_of("d", Direction::Up), Some("b"));
+    }
+
+    #[test]
+    fn neighbour_at_the_edge() {
+        assert_eq!(neighbour_of("a", Direction::Left), None);
+        assert_eq!(neighbour_of("a", Direction::Up), None);
+        assert_eq!(neighbour_of("c", Direction::Right), None);
+        assert_eq!(neighbour_of("d", Direction::Down), None);
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestWindow {
+        name: &'static str,
+        min: Size<i32, Logical>,
+        max: Size<i32, Logical>,
+    }
+
+    impl Tile for TestWindow {
+        fn size_constraints(&self) -> (Size<i32, Logical>, Size<i32, Logical>) {
+            (self.min, self.max)
+        }
+    }
+
+    fn window(name: &'static str) -> TestWindow {
+        TestWindow {
+            name,
+            min: (0, 0).into(),
+            max: (0, 0).into(),
+        }
+    }
+
+    fn layout_of(windows: &[&TestWindow]) -> TilingLayout<TestWindow> {
+        let mut layout = TilingLayout::default();
+        for w in windows {
+            layout.insert((*w).clone(), None);
+        }
+        layout
+    }
+
+    /// Tree written as `H[a V[b c]]`, `H` and `V` being the container orientation
+    fn tree(layout: &TilingLayout<TestWindow>) -> String {
+        fn describe(container: &Container<TestWindow>) -> String {
+            let children: Vec<String> = container
+                .children
+                .iter()
+                .map(|child| match child {
+                    Node::Window(w) => w.name.to_owned(),
+                    Node::Container(container) => describe(container),
+                })
+                .collect();
+
+            let orientation = match container.orientation {
+                Orientation::Horizontal => "H",
+                Orientation::Vertical => "V",
+            };
+
+            format!("{}[{}]", orientation, children.join(" "))
+        }
+
+        describe(&layout.root)
+    }
+
+    #[test]
+    fn insert_without_sibling_appends() {
+        let (a, b, c) = (window("a"), window("b"), window("c"));
+        let layout = layout_of(&[&a, &b, &c]);
+
+        assert_eq!(tree(&layout), "H[a b c]");
+    }
+
+    #[test]
+    fn insert_with_same_split_goes_next_to_sibling() {
+        let (a, b, c) = (window("a"), window("b"), window("c"));
+        let mut layout = layout_of(&[&a, &b]);
+
+        layout.insert(c, Some(&a));
+        assert_eq!(tree(&layout), "H[a c b]");
+    }
+
+    #[test]
+    fn insert_with_other_split_nests_container() {
+        let (a, b, c, d) = (window("a"), window("b"), window("c"), window("d"));
+        let mut layout = layout_of(&[&a, &b]);
+
+        layout.set_split(Orientation::Vertical);
+        layout.insert(c.clone(), Some(&a));
+        assert_eq!(tree(&layout), "H[V[a c] b]");
+
+        layout.set_split(Orientation::Horizontal);
+        layout.insert(d, Some(&c));
+        assert_eq!(tree(&layout), "H[V[a H[c d]] b]");
+    }
+
+    #[test]
+    fn insert_next_to_lonely_window_takes_split() {
+        let (a, b) = (window("a"), window("b"));
+        let mut layout = layout_of(&[&a]);
+
+        layout.set_split(Orientation::Vertical);
+        layout.insert(b, Some(&a));
+        assert_eq!(tree(&layout), "V[a b]");
+    }
+
+    #[test]
+    fn insert_ignores_tiled_window_and_unknown_sibling() {
+        let (a, b, c) = (window("a"), window("b"), window("c"));
+        let mut layout = layout_of(&[&a, &b]);
+
+        layout.insert(a.clone(), Some(&b));
+        assert_eq!(tree(&layout), "H[a b]");
+
+        layout.insert(c, Some(&window("x")));
+        assert_eq!(tree(&layout), "H[a b c]");
+    }
+
+    #[test]
+    fn remove_flattens_single_child_container() {
+        let (a, b, c, d) = (window("a"), window("b"), window("c"), window("d"));
+        let mut layout = layout_of(&[&a, &b]);
+        layout.set_split(Orientation::Vertical);
+        layout.insert(c.clone(), Some(&a));
+        layout.set_split(Orientation::Horizontal);
+        layout.insert(d.clone(), Some(&c));
+
+        assert!(layout.remove(&d));
+        assert_eq!(tree(&layout), "H[V[a c] b]");
+
+        assert!(layout.remove(&c));
+        assert_eq!(tree(&layout), "H[a b]");
+    }
+
+    #[test]
+    fn remove_flattens_root() {
+        let (a, b, c) = (window("a"), window("b"), window("c"));
+        let mut layout = layout_of(&[&a, &b]);
+        layout.set_split(Orientation::Vertical);
+        layout.insert(c, Some(&a));
+
+        assert!(layout.remove(&b));
+        assert_eq!(tree(&layout), "V[a c]");
+    }
+
+    #[test]
+    fn remove_unknown_window() {
+        let (a, b) = (window("a"), window("b"));
+        let mut layout = layout_of(&[&a, &b]);
+
+        assert!(!layout.remove(&window("x")));
+        assert_eq!(tree(&layout), "H[a b]");
+    }
+
+    #[test]
+    fn swap_across_containers() {
+        let (a, b, c) = (window("a"), window("b"), window("c"));
+        let mut layout = layout_of(&[&a, &b]);
+        layout.set_split(Orientation::Vertical);
+        layout.insert(c.clone(), Some(&a));
+
+        layout.swap(&a, &b);
+        assert_eq!(tree(&layout), "H[V[b c] a]");
+
+        // Both have to be tiled
+        layout.swap(&c, &window("x"));
+        assert_eq!(tree(&layout), "H[V[b c] a]");
+    }
+
+    #[test]
+    fn clear_returns_windows_in_order() {
+        let (a, b, c) = (window("a"), window("b"), window("c"));
+        let mut layout = layout_of(&[&a, &b]);
+        layout.set_split(Orientation::Vertical);
+        layout.insert(c.clone(), Some(&a));
+
+        assert_eq!(layout.clear(), vec![a, c, b]);
+        assert_eq!(tree(&layout), "H[]");
+    }
+
+    #[test]
+    fn arrange_nested() {
+        let (a, b, c) = (window("a"), window("b"), window("c"));
+        let mut layout = layout_of(&[&a, &b]);
+        layout.set_split(Orientation::Vertical);
+        layout.insert(c.clone(), Some(&a));
+
+        assert_eq!(
+            layout.arrange(rect(0, 0, 1000, 500)),
+            vec![
+                (a, rect(0, 0, 500, 250)),
+                (c, rect(0, 250, 500, 250)),
+                (b, rect(500, 0, 500, 500)),
+            ]
+        );
+    }
+
+    #[test]
+    fn arrange_respects_size_constraints() {
+        let a = TestWindow {
+            min: (700, 0).into(),
+            ..window("a")
+        };
+        let b = TestWindow {
+            max: (200, 0).into(),
+            ..window("b")
+        };
+        let layout = layout_of(&[&a, &b]);
+
+        assert_eq!(
+            layout.arrange(rect(0, 0, 1000, 500)),
+            vec![(a, rect(0, 0, 700, 500)), (b, rect(750, 0, 200, 500))]
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Window rules
+//!
+//! Rules are declared in the config with `Windows.rule(..)`, every rule that matches a freshly
+//! mapped window is applied, later rules override earlier ones.
+
+use smithay::{
+    desktop::{Kind, Window},
+    utils::{Logical, Point, Size},
+    wayland::{compositor, shell::xdg::XdgToplevelSurfaceData},
+};
+
+use crate::{data::window::WindowState, State};
+
+/// Properties of a window that rules are matched against
+#[derive(Debug, Default, Clone)]
+pub struct WindowInfo {
+    pub app_id: Option<String>,
+    pub title: Option<String>,
+    /// X11 `WM_CLASS` class
+    pub class: Option<String>,
+    /// X11 window name
+    pub name: Option<String>,
+}
+
+/// Patterns a window has to match, `*` matches any sequence of characters
+#[derive(Debug, Default, Clone)]
+pub struct WindowMatch {
+    pub app_id: Option<String>,
+    pub title: Option<String>,
+    pub class: Option<String>,
+    pub name: Option<String>,
+}
+
+impl WindowMatch {
+    fn matches(&self, info: &WindowInfo) -> bool {
+        let check = |pattern: &Option<String>, value: &Option<String>| match (pattern, value) {
+            (None, _) => true,
+            (Some(pattern), Some(value)) => glob_match(pattern, value),
+            (Some(_), None) => false,
+        };
+
+        check(&self.app_id, &info.app_id)
+            && check(&self.title, &info.title)
+            && check(&self.class, &info.class)
+            && check(&self.name, &info.name)
+    }
+}
+
+/// What happens to a matched window
+#[derive(Debug, Default, Clone)]
+pub struct RuleActions {
+    /// Never tile the window
+    pub floating: Option<bool>,
+    /// Index of the workspace, starting at 0
+    pub workspace: Option<usize>,
+    /// Name of the output
+    pub output: Option<String>,
+    pub size: Option<Size<i32, Logical>>,
+    /// Location relative to the output
+    pub position: Option<Point<i32, Logical>>,
+    pub opacity: Option<f32>,
+    /// Never give keyboard focus to the window
+    pub no_focus: Option<bool>,
+    pub fullscreen: Option<bool>,
+}
+
+impl RuleActions {
+    /// Override actions with the ones set in `other`
+    fn merge(&mut self, other: &RuleActions) {
+        fn set<T: Clone>(this: &mut Option<T>, other: &Option<T>) {
+            if other.is_some() {
+                *this = other.clone();
+            }
+        }
+
+        set(&mut self.floating, &other.floating);
+        set(&mut self.workspace, &other.workspace);
+        set(&mut self.output, &other.output);
+        set(&mut self.size, &other.size);
+        set(&mut self.position, &other.position);
+        set(&mut self.opacity, &other.opacity);
+        set(&mut self.no_focus, &other.no_focus);
+        set(&mut self.fullscreen, &other.fullscreen);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WindowRule {
+    pub matcher: WindowMatch,
+    pub actions: RuleActions,
+}
+
+/// Wildcard match, `*` matches any sequence of characters
+fn glob_match(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+
+    // Text before the first `*` has to be a prefix, there is always at least one part
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match value.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let parts: Vec<&str> = parts.collect();
+
+    let (last, middle) = match parts.split_last() {
+        Some(split) => split,
+        // No `*` in the pattern
+        None => return rest.is_empty(),
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(id) => rest = &rest[id + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+/// Actions of all rules matching the window, merged in order
+fn matching_actions(rules: &[WindowRule], info: &WindowInfo) -> RuleActions {
+    let mut actions = RuleActions::default();
+
+    for rule in rules {
+        if rule.matcher.matches(info) {
+            actions.merge(&rule.actions);
+        }
+    }
+
+    actions
+}
+
+impl State {
+    /// Properties of the window that rules are matched against
+    pub fn window_info(&self, window: &Window) -> WindowInfo {
+        match window.toplevel() {
+            Kind::Xdg(xdg) => compositor::with_states(xdg.wl_surface(), |states| {
+                let attributes = states
+                    .data_map
+                    .get::<XdgToplevelSurfaceData>()
+                    .unwrap()
+                    .lock()
+                    .unwrap();
+
+                WindowInfo {
+                    app_id: attributes.app_id.clone(),
+                    title: attributes.title.clone(),
+                    ..Default::default()
+                }
+            }),
+            #[cfg(feature = "xwayland")]
+            Kind::X11(_) => {
+                let (class, name) = crate::xwayland::window_class_and_name(self, window);
+
+                WindowInfo {
+                    class,
+                    name,
+                    ..Default::default()
+                }
+            }
+        }
+    }
+
+    /// Match configured rules against the window, the result is kept in its [`WindowState`]
+    pub fn match_window_rules(&self, window: &Window) -> RuleActions {
+        let info = self.window_info(window);
+        let actions = matching_actions(&self.config.windows().rules(), &info);
+
+        let window_state = WindowState::for_window(window);
+        window_state.set_floating(actions.floating.unwrap_or(false));
+        window_state.set_no_focus(actions.no_focus.unwrap_or(false));
+        window_state.set_opacity(actions.opacity);
+        window_state.set_initial_position(actions.position);
+
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_without_wildcard() {
+        assert!(glob_match("firefox", "firefox"));
+        assert!(!glob_match("firefox", "firefox-esr"));
+        assert!(!glob_match("firefox", "fire"));
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "firefox"));
+    }
+
+    #[test]
+    fn glob_prefix() {
+        assert!(glob_match("org.gnome.*", "org.gnome.Nautilus"));
+        assert!(glob_match("org.gnome.*", "org.gnome."));
+        assert!(!glob_match("org.gnome.*", "org.kde.dolphin"));
+    }
+
+    #[test]
+    fn glob_suffix() {
+        assert!(glob_match("* - Mozilla Firefox", "Home - Mozilla Firefox"));
+        assert!(!glob_match("* - Mozilla Firefox", "Home - Chromium"));
+    }
+
+    #[test]
+    fn glob_middle() {
+        assert!(glob_match("Steam*Settings", "Steam - Settings"));
+        assert!(glob_match("a*b*c", "abc"));
+        assert!(glob_match("a*b*c", "a-b-b-c"));
+        assert!(!glob_match("a*b*c", "a-c-b"));
+        // Parts can't overlap
+        assert!(!glob_match("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn glob_wildcard_only() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("**", "anything"));
+    }
+
+    fn rule(app_id: &str, actions: RuleActions) -> WindowRule {
+        WindowRule {
+            matcher: WindowMatch {
+                app_id: Some(app_id.to_owned()),
+                ..Default::default()
+            },
+            actions,
+        }
+    }
+
+    fn info(app_id: &str) -> WindowInfo {
+        WindowInfo {
+            app_id: Some(app_id.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn match_requires_all_patterns() {
+        let matcher = WindowMatch {
+            app_id: Some("firefox".into()),
+            title: Some("*Private*".into()),
+            ..Default::default()
+        };
+
+        let mut window = info("firefox");
+        assert!(!matcher.matches(&window));
+
+        window.title = Some("Private Browsing".into());
+        assert!(matcher.matches(&window));
+
+        window.app_id = None;
+        assert!(!matcher.matches(&window));
+    }
+
+    #[test]
+    fn later_rules_win() {
+        let rules = [
+            rule(
+                "*",
+                RuleActions {
+                    floating: Some(true),
+                    workspace: Some(1),
+                    ..Default::default()
+                },
+            ),
+            rule(
+                "mpv",
+                RuleActions {
+                    workspace: Some(3),
+                    opacity: Some(0.5),
+                    ..Default::default()
+                },
+            ),
+            rule(
+                "firefox",
+                RuleActions {
+                    workspace: Some(2),
+                    ..Default::default()
+                },
+            ),
+        ];
+
+        let actions = matching_actions(&rules, &info("mpv"));
+
+        // Unset actions of later rules don't override the earlier ones
+        assert_eq!(actions.floating, Some(true));
+        assert_eq!(actions.workspace, Some(3));
+        assert_eq!(actions.opacity, Some(0.5));
+        assert_eq!(actions.output, None);
+    }
+
+    #[test]
+    fn no_matching_rules() {
+        let rules = [rule(
+            "firefox",
+            RuleActions {
+                floating: Some(true),
+                ..Default::default()
+            },
+        )];
+
+        let actions = matching_actions(&rules, &info("mpv"));
+
+        assert_eq!(actions.floating, None);
+        assert_eq!(actions.workspace, None);
+    }
+}
@@ -0,0 +1,358 @@
+//! Maximized, fullscreen and minimized windows
+//!
+//! Maximized and fullscreen windows are taken out of the tiling layout,
+//! their previous geometry is kept in [`WindowState`] and restored once the state is unset.
+
+use smithay::{
+    desktop::{Kind, Window},
+    output::Output,
+    reexports::wayland_protocols::xdg::shell::server::xdg_toplevel,
+    utils::{Logical, Rectangle, Size, SERIAL_COUNTER},
+};
+
+use crate::{
+    data::{output::OutputState, window::WindowState},
+    State,
+};
+
+impl State {
+    /// Make the window fill the usable area of its output
+    pub fn maximize_window(&mut self, window: &Window) {
+        let window_state = WindowState::for_window(window);
+
+        // Not placed yet, applied by `apply_initial_window_state`
+        let output = match self.output_for_window(window) {
+            Some(output) => output,
+            None => return window_state.set_maximized(true),
+        };
+
+        let area = match self.usable_area(&output) {
+            Some(area) => area,
+            None => return,
+        };
+
+        self.save_window_geometry(window);
+        window_state.set_maximized(true);
+
+        // Fullscreen takes precedence, the window gets maximized once it leaves it
+        if window_state.fullscreen_output().is_some() {
+            return;
+        }
+
+        self.configure_window_state(window, area);
+    }
+
+    pub fn unmaximize_window(&mut self, window: &Window) {
+        let window_state = WindowState::for_window(window);
+
+        // Every request has to be answered with a configure, even if nothing changes
+        if !window_state.is_maximized() {
+            return window.configure();
+        }
+
+        window_state.set_maximized(false);
+
+        if window_state.fullscreen_output().is_none() && self.output_for_window(window).is_some() {
+            self.restore_window_geometry(window);
+        } else {
+            // Still fullscreen or not placed yet, only the state goes away
+            if let Kind::Xdg(xdg) = window.toplevel() {
+                xdg.with_pending_state(|state| {
+                    state.states.unset(xdg_toplevel::State::Maximized);
+                });
+            }
+
+            window.configure();
+        }
+    }
+
+    /// Make the window cover the whole output, the one it is on if `output` is `None`
+    pub fn fullscreen_window(&mut self, window: &Window, output: Option<Output>) {
+        let window_state = WindowState::for_window(window);
+
+        if self.output_for_window(window).is_none() {
+            // Not placed yet, applied by `apply_initial_window_state`
+            window_state.set_fullscreen_output(output);
+            window_state.set_fullscreen_requested(true);
+            return;
+        }
+
+        let output = match output.or_else(|| self.output_for_window(window)) {
+            Some(output) => output,
+            None => return,
+        };
+
+        self.save_window_geometry(window);
+        self.move_window_to_output(window, &output);
+
+        let geometry = match self.space.output_geometry(&output) {
+            Some(geometry) => geometry,
+            None => return,
+        };
+
+        window_state.set_fullscreen_output(Some(output));
+        self.configure_window_state(window, geometry);
+    }
+
+    pub fn unfullscreen_window(&mut self, window: &Window) {
+        let window_state = WindowState::for_window(window);
+
+        // Every request has to be answered with a configure, even if nothing changes
+        if window_state.take_fullscreen_requested() {
+            window_state.set_fullscreen_output(None);
+            return window.configure();
+        }
+
+        if window_state.fullscreen_output().is_none() {
+            return window.configure();
+        }
+
+        window_state.set_fullscreen_output(None);
+
+        if window_state.is_maximized() {
+            match self
+                .output_for_window(window)
+                .and_then(|output| self.usable_area(&output))
+            {
+                Some(area) => self.configure_window_state(window, area),
+                None => window.configure(),
+            }
+        } else {
+            self.restore_window_geometry(window);
+        }
+    }
+
+    /// Apply states requested before the window got placed in a workspace,
+    /// should be called right after [`State::add_window_to_workspace`]
+    pub fn apply_initial_window_state(&mut self, window: &Window) {
+        let window_state = WindowState::for_window(window);
+
+        if window_state.take_fullscreen_requested() {
+            let output = window_state.fullscreen_output();
+            window_state.set_fullscreen_output(None);
+            self.fullscreen_window(window, output);
+        }
+
+        if window_state.is_maximized() {
+            self.maximize_window(window);
+        }
+    }
+
+    /// Is the window maximized or fullscreen, so it should not be moved around
+    pub fn has_fixed_geometry(&self, window: &Window) -> bool {
+        let window_state = WindowState::for_window(window);
+        window_state.is_maximized() || window_state.fullscreen_output().is_some()
+    }
+
+    /// Hide the window until it gets focused again
+    pub fn minimize_window(&mut self, window: &Window) {
+        let window_state = WindowState::for_window(window);
+
+        if window_state.is_minimized() {
+            return;
+        }
+
+        if self.untile_window(window) {
+            window_state.set_was_tiled(true);
+        }
+
+        window_state.set_minimized(true);
+        self.animate_close(window);
+        self.hide_window(window);
+
+        if self.focused_window().as_ref() == Some(window) || self.focused_window().is_none() {
+            let top = self.space.windows().last().cloned();
+
+            match top {
+                Some(top) => self.focus_window(&top),
+                None => {
+                    let keyboard = self.seat.get_keyboard().unwrap();
+                    keyboard.set_focus(self, None, SERIAL_COUNTER.next_serial());
+                }
+            }
+        }
+    }
+
+    pub fn unminimize_window(&mut self, window: &Window) {
+        let window_state = WindowState::for_window(window);
+
+        if !window_state.is_minimized() {
+            return;
+        }
+
+        window_state.set_minimized(false);
+        self.show_window(window);
+
+        // Maximized and fullscreen windows keep their geometry outside of the layout
+        if window_state.was_tiled() && !self.has_fixed_geometry(window) {
+            window_state.set_was_tiled(false);
+            self.retile_window(window);
+        }
+    }
+
+    /// Place the floating window in the snap zone, eg. a half of the output
+    pub fn snap_window(&mut self, window: &Window, zone: Rectangle<i32, Logical>) {
+        let window_state = WindowState::for_window(window);
+
+        self.save_window_geometry(window);
+        window_state.set_snapped(true);
+
+        let zone = self.inside_frame(window, zone);
+        self.map_window_animated(window, zone, true);
+        self.configure_tiled(window, Some(zone.size));
+    }
+
+    /// Bring back the size from before the window got snapped, returns that size
+    pub fn unsnap_window(&mut self, window: &Window) -> Option<Size<i32, Logical>> {
+        let window_state = WindowState::for_window(window);
+
+        if !window_state.is_snapped() {
+            return None;
+        }
+
+        let saved = window_state.take_saved_geometry();
+        self.unset_window_states(window, saved.map(|saved| saved.size));
+
+        saved.map(|saved| saved.size)
+    }
+
+    /// Ask the window to change its size, eg. when a window rule sets it
+    pub fn configure_size(&mut self, window: &Window, size: Size<i32, Logical>) {
+        match window.toplevel() {
+            Kind::Xdg(xdg) => {
+                xdg.with_pending_state(|state| {
+                    state.size = Some(size);
+                });
+
+                xdg.send_configure();
+            }
+            #[cfg(feature = "xwayland")]
+            Kind::X11(_) => {
+                let location = self.space.window_location(window);
+                crate::xwayland::resize_window(self, window, location, size);
+            }
+        }
+    }
+
+    /// Minimized windows of active workspaces
+    pub fn minimized_windows(&self) -> Vec<Window> {
+        let mut windows = Vec::new();
+
+        for output in self.space.outputs() {
+            let workspaces = OutputState::for_output(output).workspaces();
+            windows.extend(
+                workspaces
+                    .active()
+                    .windows()
+                    .filter(|window| WindowState::for_window(window).is_minimized())
+                    .cloned(),
+            );
+        }
+
+        windows
+    }
+
+    /// Remember the geometry of the window and take it out of the tiling layout
+    fn save_window_geometry(&mut self, window: &Window) {
+        let window_state = WindowState::for_window(window);
+
+        if let Some(location) = self.space.window_location(window) {
+            window_state.save_geometry(Rectangle::from_loc_and_size(
+                location,
+                window.geometry().size,
+            ));
+        }
+
+        if self.untile_window(window) {
+            window_state.set_was_tiled(true);
+        }
+    }
+
+    /// Bring back the geometry from before the window got maximized or fullscreened
+    fn restore_window_geometry(&mut self, window: &Window) {
+        let window_state = WindowState::for_window(window);
+        let saved = window_state.take_saved_geometry();
+
+        if window_state.was_tiled() {
+            window_state.set_was_tiled(false);
+            self.unset_window_states(window, None);
+            self.retile_window(window);
+        } else {
+            if let Some(saved) = saved {
+                self.map_window_animated(window, saved, false);
+            }
+
+            self.unset_window_states(window, saved.map(|saved| saved.size));
+        }
+    }
+
+    /// Place the window in the given rectangle, and tell it whether it is maximized or fullscreen
+    fn configure_window_state(&mut self, window: &Window, rect: Rectangle<i32, Logical>) {
+        let window_state = WindowState::for_window(window);
+        let rect = self.inside_frame(window, rect);
+
+        self.map_window_animated(window, rect, false);
+
+        match window.toplevel() {
+            Kind::Xdg(xdg) => {
+                xdg.with_pending_state(|state| {
+                    if window_state.fullscreen_output().is_some() {
+                        state.states.set(xdg_toplevel::State::Fullscreen);
+                    } else {
+                        state.states.unset(xdg_toplevel::State::Fullscreen);
+                    }
+
+                    if window_state.is_maximized() {
+                        state.states.set(xdg_toplevel::State::Maximized);
+                    } else {
+                        state.states.unset(xdg_toplevel::State::Maximized);
+                    }
+
+                    state.size = Some(rect.size);
+                });
+
+                xdg.send_configure();
+            }
+            #[cfg(feature = "xwayland")]
+            Kind::X11(_) => {
+                crate::xwayland::resize_window(self, window, Some(rect.loc), rect.size);
+            }
+        }
+
+        self.space.raise_window(window, false);
+    }
+
+    /// Unset maximized and fullscreen states, `size` of `None` lets the client pick its size
+    fn unset_window_states(&mut self, window: &Window, size: Option<Size<i32, Logical>>) {
+        let window_state = WindowState::for_window(window);
+
+        match window.toplevel() {
+            Kind::Xdg(xdg) => {
+                xdg.with_pending_state(|state| {
+                    state.states.unset(xdg_toplevel::State::Fullscreen);
+                    state.states.unset(xdg_toplevel::State::Maximized);
+
+                    if window_state.is_snapped() {
+                        state.states.unset(xdg_toplevel::State::TiledLeft);
+                        state.states.unset(xdg_toplevel::State::TiledRight);
+                        state.states.unset(xdg_toplevel::State::TiledTop);
+                        state.states.unset(xdg_toplevel::State::TiledBottom);
+                    }
+
+                    state.size = size;
+                });
+
+                xdg.send_configure();
+            }
+            #[cfg(feature = "xwayland")]
+            Kind::X11(_) => {
+                if let Some(size) = size {
+                    let location = self.space.window_location(window);
+                    crate::xwayland::resize_window(self, window, location, size);
+                }
+            }
+        }
+
+        window_state.set_snapped(false);
+    }
+}
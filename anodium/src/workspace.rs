@@ -0,0 +1,1099 @@
+//! Per-output workspaces
+//!
+//! Every output owns an ordered list of workspaces, stored in [`OutputState`].
+//! Only windows of active workspaces are mapped in the [`Space`],
+//! windows of hidden workspaces are kept together with their location relative to the output.
+//!
+//! Tiled windows of a workspace are also leaves of its [`TilingLayout`],
+//! floating windows are always stacked above them.
+
+use anodium_protocol::server::{OutputDescription, WorkspaceDescription};
+use smithay::{
+    desktop::{layer_map_for_output, Space, Window},
+    output::Output,
+    utils::{Logical, Point, Rectangle, SERIAL_COUNTER},
+};
+
+use crate::{
+    data::{output::OutputState, seat::SeatState, window::WindowState},
+    tiling::{self, Direction, Orientation, TilingLayout},
+    State,
+};
+
+/// Number of workspaces every output starts with
+pub const WORKSPACE_COUNT: usize = 9;
+
+#[derive(Debug)]
+struct WorkspaceWindow {
+    window: Window,
+    /// Location relative to the output, only up to date while the workspace is hidden
+    location: Point<i32, Logical>,
+}
+
+/// How windows of a workspace are placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutMode {
+    /// Nothing is tiled
+    Floating,
+    /// New windows are tiled, but they can be made floating
+    Tiling,
+    /// New windows float, but they can be tiled,
+    /// eg. by dropping them onto a tiled window
+    Universal,
+}
+
+#[derive(Debug)]
+pub struct Workspace {
+    name: String,
+    /// Windows from bottom to top
+    windows: Vec<WorkspaceWindow>,
+
+    mode: LayoutMode,
+    tiling: TilingLayout,
+}
+
+impl Workspace {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            windows: Vec::new(),
+            mode: LayoutMode::Floating,
+            tiling: TilingLayout::default(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn windows(&self) -> impl DoubleEndedIterator<Item = &Window> {
+        self.windows.iter().map(|w| &w.window)
+    }
+
+    pub fn contains(&self, window: &Window) -> bool {
+        self.windows.iter().any(|w| w.window == *window)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    pub fn is_tiled(&self, window: &Window) -> bool {
+        self.tiling.contains(window)
+    }
+
+    /// Windows that are not tiled, from bottom to top
+    fn floating(&self) -> impl Iterator<Item = &Window> {
+        self.windows()
+            .filter(|window| !self.tiling.contains(window))
+    }
+
+    /// Add the window on top, in the tiling mode it is inserted next to `focused`,
+    /// unless a window rule keeps it floating
+    fn add(&mut self, entry: WorkspaceWindow, focused: Option<&Window>) {
+        if self.mode == LayoutMode::Tiling && !WindowState::for_window(&entry.window).is_floating()
+        {
+            self.tiling.insert(entry.window.clone(), focused);
+        }
+
+        self.windows.push(entry);
+    }
+
+    fn remove(&mut self, window: &Window) -> Option<WorkspaceWindow> {
+        let id = self.windows.iter().position(|w| w.window == *window)?;
+        self.tiling.remove(window);
+        Some(self.windows.remove(id))
+    }
+
+    /// Remember stacking order and locations, and unmap all windows
+    fn hide(&mut self, space: &mut Space, output_loc: Point<i32, Logical>) {
+        self.sync_stacking(space);
+
+        for entry in self.windows.iter_mut() {
+            if let Some(location) = space.window_location(&entry.window) {
+                entry.location = location - output_loc;
+            }
+
+            space.unmap_window(&entry.window);
+        }
+    }
+
+    /// Map all windows back, in the order they were stacked, minimized windows stay hidden
+    fn show(&self, space: &mut Space, output_loc: Point<i32, Logical>) {
+        for entry in self.windows.iter() {
+            if WindowState::for_window(&entry.window).is_minimized() {
+                continue;
+            }
+
+            space.map_window(&entry.window, output_loc + entry.location, None, false);
+        }
+    }
+
+    /// Order windows the same way as they are stacked in the space
+    fn sync_stacking(&mut self, space: &Space) {
+        let order: Vec<&Window> = space.windows().collect();
+
+        self.windows.sort_by_key(|entry| {
+            order
+                .iter()
+                .position(|w| **w == entry.window)
+                .unwrap_or(usize::MAX)
+        });
+    }
+}
+
+/// Workspaces of a single output
+#[derive(Debug)]
+pub struct Workspaces {
+    workspaces: Vec<Workspace>,
+    active: usize,
+}
+
+impl Default for Workspaces {
+    fn default() -> Self {
+        Self {
+            workspaces: (1..=WORKSPACE_COUNT)
+                .map(|id| Workspace::new(id.to_string()))
+                .collect(),
+            active: 0,
+        }
+    }
+}
+
+impl Workspaces {
+    pub fn iter(&self) -> impl Iterator<Item = &Workspace> {
+        self.workspaces.iter()
+    }
+
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    pub fn active(&self) -> &Workspace {
+        &self.workspaces[self.active]
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = &mut Workspace> {
+        self.workspaces.iter_mut()
+    }
+
+    fn active_mut(&mut self) -> &mut Workspace {
+        &mut self.workspaces[self.active]
+    }
+
+    /// Index of the workspace that holds the window
+    pub fn find(&self, window: &Window) -> Option<usize> {
+        self.workspaces.iter().position(|ws| ws.contains(window))
+    }
+
+    /// Forget windows that got destroyed, returns `true` if the active layout changed
+    fn cleanup(&mut self) -> bool {
+        let mut changed = false;
+
+        for (id, workspace) in self.workspaces.iter_mut().enumerate() {
+            let dead: Vec<Window> = workspace
+                .windows()
+                .filter(|window| !window.toplevel().alive())
+                .cloned()
+                .collect();
+
+            for window in dead {
+                if workspace.tiling.remove(&window) && id == self.active {
+                    changed = true;
+                }
+            }
+
+            workspace
+                .windows
+                .retain(|entry| entry.window.toplevel().alive());
+        }
+
+        changed
+    }
+}
+
+impl State {
+    /// Output that new windows open on and workspace actions apply to
+    pub fn active_output(&self) -> Option<Output> {
+        let pointer_pos = SeatState::for_seat(&self.seat).pointer_pos();
+
+        self.space
+            .output_under(pointer_pos)
+            .next()
+            .or_else(|| self.space.outputs().next())
+            .cloned()
+    }
+
+    /// Output which workspaces hold the window
+    pub fn output_for_window(&self, window: &Window) -> Option<Output> {
+        self.space
+            .outputs()
+            .find(|output| {
+                OutputState::for_output(output)
+                    .workspaces()
+                    .find(window)
+                    .is_some()
+            })
+            .cloned()
+    }
+
+    /// Add a freshly created window to a workspace
+    ///
+    /// The window goes to the active workspace of the active output,
+    /// unless window rules pick another output or workspace.
+    pub fn add_window_to_workspace(&mut self, window: &Window) {
+        let rules = self.match_window_rules(window);
+
+        let output = rules
+            .output
+            .as_ref()
+            .and_then(|name| self.space.outputs().find(|o| o.name() == *name).cloned())
+            .or_else(|| self.active_output());
+
+        let output = match output {
+            Some(output) => output,
+            None => return,
+        };
+
+        let focused = self.focused_window();
+
+        let hidden = {
+            let mut workspaces = OutputState::for_output(&output).workspaces_mut();
+
+            let index = rules
+                .workspace
+                .filter(|index| *index < workspaces.workspaces.len())
+                .unwrap_or(workspaces.active);
+
+            let workspace = &mut workspaces.workspaces[index];
+            let sibling = if index == workspaces.active {
+                focused
+            } else {
+                workspace.windows().last().cloned()
+            };
+
+            workspace.add(
+                WorkspaceWindow {
+                    window: window.clone(),
+                    location: Point::default(),
+                },
+                sibling.as_ref(),
+            );
+
+            index != workspaces.active
+        };
+
+        if hidden {
+            self.space.unmap_window(window);
+        }
+
+        if let Some(size) = rules.size.filter(|_| !self.is_tiled(window)) {
+            self.configure_size(window, size);
+        }
+
+        self.arrange_output(&output);
+
+        if rules.fullscreen == Some(true) {
+            self.fullscreen_window(window, Some(output));
+        }
+    }
+
+    /// Is the window in the active workspace of its output
+    pub fn is_window_visible(&self, window: &Window) -> bool {
+        self.output_for_window(window)
+            .map(|output| {
+                OutputState::for_output(&output)
+                    .workspaces()
+                    .active()
+                    .contains(window)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Move a floating window, windows of hidden workspaces show up there once the workspace is active
+    pub fn set_window_location(&mut self, window: &Window, location: Point<i32, Logical>) {
+        if self.is_window_visible(window) && !WindowState::for_window(window).is_minimized() {
+            self.space.map_window(window, location, None, false);
+            return;
+        }
+
+        let output = match self.output_for_window(window) {
+            Some(output) => output,
+            None => return,
+        };
+
+        let output_loc = self
+            .space
+            .output_geometry(&output)
+            .map(|geometry| geometry.loc)
+            .unwrap_or_default();
+
+        let mut workspaces = OutputState::for_output(&output).workspaces_mut();
+        let entry = workspaces
+            .iter_mut()
+            .flat_map(|ws| ws.windows.iter_mut())
+            .find(|entry| entry.window == *window);
+
+        if let Some(entry) = entry {
+            entry.location = location - output_loc;
+        }
+    }
+
+    /// Should be called every loop iteration, after [`Space::refresh`]
+    pub fn refresh_workspaces(&mut self) {
+        // Has to be known before the destroyed window is forgotten
+        let lost_focus = self.lost_focus_workspace();
+
+        let closed: Vec<Window> = self
+            .space
+            .outputs()
+            .flat_map(|output| {
+                let workspaces = OutputState::for_output(output).workspaces();
+                let closed: Vec<Window> = workspaces
+                    .active()
+                    .windows()
+                    .filter(|window| {
+                        !window.toplevel().alive()
+                            && !WindowState::for_window(window).is_minimized()
+                    })
+                    .cloned()
+                    .collect();
+                closed
+            })
+            .collect();
+
+        for window in closed {
+            self.animate_close(&window);
+        }
+
+        let changed: Vec<Output> = self
+            .space
+            .outputs()
+            .filter(|output| OutputState::for_output(output).workspaces_mut().cleanup())
+            .cloned()
+            .collect();
+
+        for output in changed {
+            self.arrange_output(&output);
+        }
+
+        if let Some(windows) = lost_focus {
+            self.restore_focus(&windows);
+        }
+    }
+
+    /// Is the window managed by the tiling layout
+    pub fn is_tiled(&self, window: &Window) -> bool {
+        self.output_for_window(window)
+            .map(|output| {
+                let workspaces = OutputState::for_output(&output).workspaces();
+                workspaces.iter().any(|ws| ws.is_tiled(window))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Area available for windows of the output, without exclusive zones of layer surfaces
+    pub fn usable_area(&self, output: &Output) -> Option<Rectangle<i32, Logical>> {
+        let geometry = self.space.output_geometry(output)?;
+        let map = layer_map_for_output(output);
+
+        // The zone is only known once layers got arranged
+        if map.layers().next().is_none() {
+            return Some(geometry);
+        }
+
+        let mut zone = map.non_exclusive_zone();
+        zone.loc += geometry.loc;
+
+        Some(zone)
+    }
+
+    /// Tiled windows of the active workspace of the output, with their rectangles
+    fn tiled_windows(&self, output: &Output) -> Vec<(Window, Rectangle<i32, Logical>)> {
+        let area = match self.usable_area(output) {
+            Some(area) => area,
+            None => return Vec::new(),
+        };
+
+        OutputState::for_output(output)
+            .workspaces()
+            .active()
+            .tiling
+            .arrange(area)
+    }
+
+    /// Place and resize tiled windows of the active workspace of the output
+    pub fn arrange_output(&mut self, output: &Output) {
+        for (window, rect) in self.tiled_windows(output) {
+            let rect = self.inside_frame(&window, rect);
+            self.map_window_animated(&window, rect, false);
+            self.configure_tiled(&window, Some(rect.size));
+        }
+
+        self.raise_floating_windows();
+    }
+
+    /// Keep floating windows above the tiled ones, should be called after raising a window
+    pub fn raise_floating_windows(&mut self) {
+        let mut floating: Vec<Window> = Vec::new();
+
+        for output in self.space.outputs() {
+            let workspaces = OutputState::for_output(output).workspaces();
+            let workspace = workspaces.active();
+
+            if !workspace.tiling.windows().is_empty() {
+                floating.extend(workspace.floating().cloned());
+            }
+        }
+
+        // Keep the current order of floating windows
+        let order: Vec<&Window> = self.space.windows().collect();
+        floating.sort_by_key(|window| order.iter().position(|w| *w == window));
+
+        for window in floating {
+            self.space.raise_window(&window, false);
+        }
+    }
+
+    /// Tile a floating window, or make a tiled window float
+    pub fn toggle_floating(&mut self, window: &Window) {
+        let output = match self.output_for_window(window) {
+            Some(output) => output,
+            None => return,
+        };
+
+        let tiled = {
+            let mut workspaces = OutputState::for_output(&output).workspaces_mut();
+            let workspace = match workspaces.find(window) {
+                Some(id) => &mut workspaces.workspaces[id],
+                None => return,
+            };
+
+            if workspace.mode == LayoutMode::Floating {
+                return;
+            }
+
+            if workspace.tiling.remove(window) {
+                false
+            } else {
+                let sibling = workspace.tiling.windows().last().cloned().cloned();
+                workspace.tiling.insert(window.clone(), sibling.as_ref());
+                true
+            }
+        };
+
+        if !tiled {
+            self.configure_tiled(window, None);
+            self.space.raise_window(window, false);
+        }
+
+        self.arrange_output(&output);
+    }
+
+    /// Should be called when a floating window got dropped after being moved by the pointer
+    ///
+    /// In the universal mode a window dropped onto a tiled window gets inserted next to it.
+    pub fn floating_window_dropped(&mut self, window: &Window) {
+        let output = match self.output_for_window(window) {
+            Some(output) => output,
+            None => return,
+        };
+
+        {
+            let workspaces = OutputState::for_output(&output).workspaces();
+            let workspace = workspaces.active();
+
+            if workspace.mode != LayoutMode::Universal
+                || !workspace.contains(window)
+                || workspace.is_tiled(window)
+            {
+                return;
+            }
+        }
+
+        let pointer_pos = SeatState::for_seat(&self.seat).pointer_pos();
+
+        let target = self
+            .tiled_windows(&output)
+            .into_iter()
+            .find(|(_, rect)| rect.to_f64().contains(pointer_pos));
+
+        let (target, rect) = match target {
+            Some(target) => target,
+            None => return,
+        };
+
+        // Split along the axis the pointer is further away from the center on
+        let dx =
+            (pointer_pos.x - (rect.loc.x as f64 + rect.size.w as f64 / 2.0)) / rect.size.w as f64;
+        let dy =
+            (pointer_pos.y - (rect.loc.y as f64 + rect.size.h as f64 / 2.0)) / rect.size.h as f64;
+
+        let split = if dx.abs() > dy.abs() {
+            Orientation::Horizontal
+        } else {
+            Orientation::Vertical
+        };
+
+        OutputState::for_output(&output)
+            .workspaces_mut()
+            .active_mut()
+            .tiling
+            .insert_with_split(window.clone(), Some(&target), split);
+
+        self.arrange_output(&output);
+    }
+
+    /// Take the window out of the tiling layout, returns `true` if it was tiled
+    pub fn untile_window(&mut self, window: &Window) -> bool {
+        let output = match self.output_for_window(window) {
+            Some(output) => output,
+            None => return false,
+        };
+
+        let removed = {
+            let mut workspaces = OutputState::for_output(&output).workspaces_mut();
+            match workspaces.find(window) {
+                Some(id) => workspaces.workspaces[id].tiling.remove(window),
+                None => false,
+            }
+        };
+
+        if removed {
+            self.configure_tiled(window, None);
+            self.arrange_output(&output);
+        }
+
+        removed
+    }
+
+    /// Put the window back into the tiling layout of its workspace, unless the workspace is floating
+    pub fn retile_window(&mut self, window: &Window) {
+        let output = match self.output_for_window(window) {
+            Some(output) => output,
+            None => return,
+        };
+
+        {
+            let mut workspaces = OutputState::for_output(&output).workspaces_mut();
+            let workspace = match workspaces.find(window) {
+                Some(id) => &mut workspaces.workspaces[id],
+                None => return,
+            };
+
+            if workspace.mode == LayoutMode::Floating || workspace.is_tiled(window) {
+                return;
+            }
+
+            let sibling = workspace.tiling.windows().last().cloned().cloned();
+            workspace.tiling.insert(window.clone(), sibling.as_ref());
+        }
+
+        self.arrange_output(&output);
+    }
+
+    /// Unmap the window, but keep it in its workspace together with its location
+    pub fn hide_window(&mut self, window: &Window) {
+        let output = match self.output_for_window(window) {
+            Some(output) => output,
+            None => return,
+        };
+
+        let output_loc = self
+            .space
+            .output_geometry(&output)
+            .map(|geometry| geometry.loc)
+            .unwrap_or_default();
+
+        let location = self.space.window_location(window);
+
+        let mut workspaces = OutputState::for_output(&output).workspaces_mut();
+        if let Some(id) = workspaces.find(window) {
+            let workspace = &mut workspaces.workspaces[id];
+            workspace.sync_stacking(&self.space);
+
+            if let Some(entry) = workspace.windows.iter_mut().find(|w| w.window == *window) {
+                if let Some(location) = location {
+                    entry.location = location - output_loc;
+                }
+            }
+        }
+
+        self.space.unmap_window(window);
+    }
+
+    /// Map the window hidden by [`State::hide_window`] back, if its workspace is active
+    pub fn show_window(&mut self, window: &Window) {
+        let output = match self.output_for_window(window) {
+            Some(output) => output,
+            None => return,
+        };
+
+        let output_loc = match self.space.output_geometry(&output) {
+            Some(geometry) => geometry.loc,
+            None => return,
+        };
+
+        let location = {
+            let workspaces = OutputState::for_output(&output).workspaces();
+            workspaces
+                .active()
+                .windows
+                .iter()
+                .find(|w| w.window == *window)
+                .map(|entry| entry.location)
+        };
+
+        if let Some(location) = location {
+            self.space
+                .map_window(window, output_loc + location, None, false);
+        }
+    }
+
+    /// Move the window to the active workspace of another output, keeping it at the same place relative to the output
+    pub fn move_window_to_output(&mut self, window: &Window, output: &Output) {
+        let current = match self.output_for_window(window) {
+            Some(current) => current,
+            None => return,
+        };
+
+        if current == *output {
+            return;
+        }
+
+        let (from, to) = match (
+            self.space.output_geometry(&current),
+            self.space.output_geometry(output),
+        ) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return,
+        };
+
+        let entry = OutputState::for_output(&current)
+            .workspaces_mut()
+            .iter_mut()
+            .find_map(|ws| ws.remove(window));
+
+        let mut entry = match entry {
+            Some(entry) => entry,
+            None => return,
+        };
+
+        if let Some(location) = self.space.window_location(window) {
+            entry.location = location - from.loc;
+        }
+
+        if !WindowState::for_window(window).is_minimized() {
+            self.space
+                .map_window(window, to.loc + entry.location, None, false);
+        }
+
+        let tiled = {
+            let mut workspaces = OutputState::for_output(output).workspaces_mut();
+            let workspace = workspaces.active_mut();
+            let sibling = workspace.windows().last().cloned();
+            workspace.add(entry, sibling.as_ref());
+            workspace.is_tiled(window)
+        };
+
+        if !tiled {
+            self.configure_tiled(window, None);
+        }
+
+        self.arrange_output(&current);
+        self.arrange_output(output);
+    }
+
+    /// Take workspaces of an output that is going away, its windows get unmapped
+    ///
+    /// Should be called before the output gets unmapped, so the locations of windows are known.
+    pub fn take_workspaces(&mut self, output: &Output) -> Workspaces {
+        let output_loc = self
+            .space
+            .output_geometry(output)
+            .map(|geometry| geometry.loc)
+            .unwrap_or_default();
+
+        let mut workspaces = std::mem::take(&mut *OutputState::for_output(output).workspaces_mut());
+        workspaces.active_mut().hide(&mut self.space, output_loc);
+
+        workspaces
+    }
+
+    /// Move windows of workspaces taken by [`State::take_workspaces`] onto the output
+    ///
+    /// Every workspace is merged into the one with the same index,
+    /// windows keep their location relative to the output, as long as they stay visible.
+    pub fn adopt_workspaces(&mut self, adopted: Workspaces, output: &Output) {
+        let geometry = match self.space.output_geometry(output) {
+            Some(geometry) => geometry,
+            None => return,
+        };
+
+        let output_state = OutputState::for_output(output);
+        let mut untiled = Vec::new();
+        let mut shown = Vec::new();
+
+        {
+            let mut workspaces = output_state.workspaces_mut();
+            let last = workspaces.workspaces.len() - 1;
+            let active = workspaces.active;
+
+            for (id, mut workspace) in adopted.workspaces.into_iter().enumerate() {
+                let id = id.min(last);
+                let target = &mut workspaces.workspaces[id];
+
+                for mut entry in workspace.windows.drain(..) {
+                    let size = entry.window.geometry().size;
+                    entry.location.x = entry
+                        .location
+                        .x
+                        .min(geometry.size.w - size.w.min(geometry.size.w))
+                        .max(0);
+                    entry.location.y = entry
+                        .location
+                        .y
+                        .min(geometry.size.h - size.h.min(geometry.size.h))
+                        .max(0);
+
+                    let was_tiled = workspace.tiling.contains(&entry.window);
+                    let window = entry.window.clone();
+                    let sibling = target.windows().last().cloned();
+                    target.add(entry, sibling.as_ref());
+
+                    if was_tiled && !target.is_tiled(&window) {
+                        untiled.push(window.clone());
+                    }
+
+                    if id == active {
+                        shown.push(window);
+                    }
+                }
+            }
+        }
+
+        for window in untiled {
+            self.configure_tiled(&window, None);
+        }
+
+        let locations: Vec<_> = output_state
+            .workspaces()
+            .active()
+            .windows
+            .iter()
+            .filter(|entry| shown.contains(&entry.window))
+            .map(|entry| (entry.window.clone(), entry.location))
+            .collect();
+
+        for (window, location) in locations {
+            let window_state = WindowState::for_window(&window);
+
+            if let Some(fullscreen) = window_state.fullscreen_output() {
+                if fullscreen != *output {
+                    window_state.set_fullscreen_output(None);
+                    self.fullscreen_window(&window, Some(output.clone()));
+                    continue;
+                }
+            }
+
+            if window_state.is_minimized() {
+                continue;
+            }
+
+            self.space
+                .map_window(&window, geometry.loc + location, None, false);
+
+            if window_state.is_maximized() {
+                self.maximize_window(&window);
+            }
+        }
+
+        self.arrange_output(output);
+    }
+
+    pub fn arrange_all_outputs(&mut self) {
+        let outputs: Vec<Output> = self.space.outputs().cloned().collect();
+
+        for output in outputs {
+            self.arrange_output(&output);
+        }
+    }
+
+    /// Change the layout mode of the active workspace of the active output
+    pub fn set_layout_mode(&mut self, mode: LayoutMode) {
+        let output = match self.active_output() {
+            Some(output) => output,
+            None => return,
+        };
+
+        let floated = {
+            let mut workspaces = OutputState::for_output(&output).workspaces_mut();
+            let workspace = workspaces.active_mut();
+
+            if workspace.mode == mode {
+                return;
+            }
+
+            workspace.mode = mode;
+
+            match mode {
+                LayoutMode::Tiling => {
+                    let windows: Vec<Window> = workspace
+                        .windows()
+                        .filter(|window| !WindowState::for_window(window).is_floating())
+                        .cloned()
+                        .collect();
+                    for window in windows {
+                        workspace.tiling.insert(window, None);
+                    }
+
+                    Vec::new()
+                }
+                // Whatever is tiled stays tiled
+                LayoutMode::Universal => Vec::new(),
+                LayoutMode::Floating => workspace.tiling.clear(),
+            }
+        };
+
+        for window in floated {
+            self.configure_tiled(&window, None);
+        }
+
+        self.arrange_output(&output);
+    }
+
+    /// Orientation of the split that the next window of the active workspace is going to be placed in
+    pub fn set_split(&mut self, orientation: Orientation) {
+        if let Some(output) = self.active_output() {
+            OutputState::for_output(&output)
+                .workspaces_mut()
+                .active_mut()
+                .tiling
+                .set_split(orientation);
+        }
+    }
+
+    /// Tiled window next to the focused one, in the given direction
+    fn tiled_neighbour(&self, direction: Direction) -> Option<(Window, Window)> {
+        let focused = self.focused_window()?;
+        let output = self.output_for_window(&focused)?;
+
+        let tiled = self.tiled_windows(&output);
+        let (_, from) = tiled.iter().find(|(window, _)| *window == focused)?;
+
+        let neighbour = tiling::neighbour(&tiled, *from, direction)?.clone();
+
+        Some((focused, neighbour))
+    }
+
+    /// Visible window in the given direction from the focused one, by geometry
+    ///
+    /// Unlike [`State::tiled_neighbour`] this includes floating windows.
+    fn visible_neighbour(&self, direction: Direction) -> Option<Window> {
+        let focused = self.focused_window()?;
+
+        let windows: Vec<(Window, Rectangle<i32, Logical>)> = self
+            .space
+            .windows()
+            .filter(|window| !WindowState::for_window(window).is_no_focus())
+            .filter_map(|window| {
+                let loc = self.space.window_location(window)?;
+                Some((
+                    window.clone(),
+                    Rectangle::from_loc_and_size(loc, window.geometry().size),
+                ))
+            })
+            .collect();
+
+        let from = Rectangle::from_loc_and_size(
+            self.space.window_location(&focused)?,
+            focused.geometry().size,
+        );
+
+        tiling::neighbour(&windows, from, direction).cloned()
+    }
+
+    /// Move focus to the window in the given direction
+    ///
+    /// Tiled windows prefer their tiled neighbours, floating windows are found by geometry.
+    pub fn focus_direction(&mut self, direction: Direction) {
+        let neighbour = self
+            .tiled_neighbour(direction)
+            .map(|(_, neighbour)| neighbour)
+            .or_else(|| self.visible_neighbour(direction));
+
+        if let Some(neighbour) = neighbour {
+            self.focus_window(&neighbour);
+        }
+    }
+
+    /// Swap the focused window with the tiled window in the given direction
+    pub fn swap_direction(&mut self, direction: Direction) {
+        let (focused, neighbour) = match self.tiled_neighbour(direction) {
+            Some(pair) => pair,
+            None => return,
+        };
+
+        if let Some(output) = self.output_for_window(&focused) {
+            OutputState::for_output(&output)
+                .workspaces_mut()
+                .active_mut()
+                .tiling
+                .swap(&focused, &neighbour);
+
+            self.arrange_output(&output);
+        }
+    }
+
+    /// Make workspace with given index active on the active output
+    pub fn switch_workspace(&mut self, index: usize) {
+        let output = match self.active_output() {
+            Some(output) => output,
+            None => return,
+        };
+
+        let output_loc = match self.space.output_geometry(&output) {
+            Some(geometry) => geometry.loc,
+            None => return,
+        };
+
+        let output_width = self
+            .space
+            .output_geometry(&output)
+            .map(|geometry| geometry.size.w)
+            .unwrap_or_default();
+
+        let output_state = OutputState::for_output(&output);
+
+        let (hidden, previous) = {
+            let workspaces = output_state.workspaces();
+
+            if index >= workspaces.workspaces.len() || index == workspaces.active {
+                return;
+            }
+
+            let hidden: Vec<Window> = workspaces
+                .active()
+                .windows()
+                .filter(|window| self.space.window_location(window).is_some())
+                .cloned()
+                .collect();
+
+            (hidden, workspaces.active)
+        };
+
+        // Workspaces with higher indices come from the right
+        let direction = if index > previous { 1.0 } else { -1.0 };
+        let offset: Point<f64, Logical> = (output_width as f64 * direction, 0.0).into();
+
+        self.animate_workspace_out(&hidden, (-offset.x, 0.0).into());
+
+        {
+            let mut workspaces = output_state.workspaces_mut();
+
+            workspaces.active_mut().hide(&mut self.space, output_loc);
+            workspaces.active = index;
+            workspaces.active().show(&mut self.space, output_loc);
+        }
+
+        // The output could have changed its size while the workspace was hidden
+        self.arrange_output(&output);
+
+        let shown: Vec<Window> = output_state
+            .workspaces()
+            .active()
+            .windows()
+            .cloned()
+            .collect();
+        self.animate_workspace_in(&shown, offset);
+
+        let top = output_state.workspaces().active().windows().last().cloned();
+
+        match top {
+            Some(window) => self.focus_window(&window),
+            None => {
+                let keyboard = self.seat.get_keyboard().unwrap();
+                keyboard.set_focus(self, None, SERIAL_COUNTER.next_serial());
+            }
+        }
+    }
+
+    /// Move the window to the workspace with given index, on the output that holds the window
+    pub fn move_window_to_workspace(&mut self, window: &Window, index: usize) {
+        let output = match self.output_for_window(window) {
+            Some(output) => output,
+            None => return,
+        };
+
+        let output_loc = match self.space.output_geometry(&output) {
+            Some(geometry) => geometry.loc,
+            None => return,
+        };
+
+        {
+            let mut workspaces = OutputState::for_output(&output).workspaces_mut();
+
+            let current = match workspaces.find(window) {
+                Some(current) => current,
+                None => return,
+            };
+
+            if index >= workspaces.workspaces.len() || index == current {
+                return;
+            }
+
+            let was_tiled = workspaces.workspaces[current].is_tiled(window);
+
+            if let Some(mut entry) = workspaces.workspaces[current].remove(window) {
+                if let Some(location) = self.space.window_location(window) {
+                    entry.location = location - output_loc;
+                }
+
+                if index == workspaces.active {
+                    self.space
+                        .map_window(window, output_loc + entry.location, None, false);
+                } else {
+                    self.space.unmap_window(window);
+                }
+
+                let target = &mut workspaces.workspaces[index];
+                let sibling = target.windows().last().cloned();
+                target.add(entry, sibling.as_ref());
+
+                if was_tiled && !target.is_tiled(window) {
+                    drop(workspaces);
+                    self.configure_tiled(window, None);
+                }
+            }
+        }
+
+        self.arrange_output(&output);
+    }
+
+    /// Workspaces of every output as status bars see them, see [`State::refresh_anodium_protocol`]
+    ///
+    /// Only active and non-empty workspaces are listed.
+    pub fn describe_workspaces(&self) -> Vec<OutputDescription> {
+        self.space
+            .outputs()
+            .map(|output| {
+                let workspaces = OutputState::for_output(output).workspaces();
+
+                let workspaces = workspaces
+                    .iter()
+                    .enumerate()
+                    .filter(|(id, ws)| *id == workspaces.active_index() || !ws.is_empty())
+                    .map(|(id, ws)| WorkspaceDescription {
+                        name: ws.name().to_string(),
+                        active: id == workspaces.active_index(),
+                    })
+                    .collect();
+
+                OutputDescription {
+                    name: output.name(),
+                    workspaces,
+                }
+            })
+            .collect()
+    }
+}
@@ -1,24 +1,29 @@
 use std::{collections::HashMap, convert::TryFrom, os::unix::net::UnixStream, time::Duration};
 
-use crate::{data::seat::SeatState, positioning, CalloopData, State};
+use crate::{CalloopData, State};
 use calloop::{timer::Timer, LoopHandle};
 use slog_scope::{debug, error};
 use smithay::{
+    desktop::Window,
     reexports::wayland_server::{protocol::wl_surface::WlSurface, Client, DisplayHandle, Resource},
-    utils::{Logical, Point},
+    utils::{Logical, Point, Size},
     xwayland::{XWayland, XWaylandEvent},
 };
 use x11rb::{
     connection::Connection,
     errors::ReplyOrIdError,
     protocol::{
-        xproto::{ConfigWindow, ConfigureWindowAux, ConnectionExt, Window as X11Window},
+        xproto::{
+            Atom, AtomEnum, ClientMessageEvent, ConfigWindow, ConfigureWindowAux, ConnectionExt,
+            EventMask, Window as X11Window,
+        },
         Event as X11Event,
     },
 };
 
 mod pending_window;
 use pending_window::PendingWindow;
+pub use pending_window::X11WindowId;
 
 mod xwayland_client;
 use xwayland_client::XWaylandClient;
@@ -206,9 +211,114 @@ fn handle_x11_event(state: &mut State, event: X11Event) -> Result<(), ReplyOrIdE
     Ok(())
 }
 
+/// Ask X11 window to close, using `WM_DELETE_WINDOW`
+pub fn close_window(state: &mut State, window: &Window) {
+    let id = match window.user_data().get::<X11WindowId>() {
+        Some(id) => id.0,
+        None => return,
+    };
+
+    if let Some(client) = state.xwayland.client.as_ref() {
+        let event = ClientMessageEvent::new(
+            32,
+            id,
+            client.atoms.WM_PROTOCOLS,
+            [client.atoms.WM_DELETE_WINDOW, x11rb::CURRENT_TIME, 0, 0, 0],
+        );
+
+        let res = client
+            .conn
+            .send_event(false, id, EventMask::NO_EVENT, event)
+            .and_then(|_| client.conn.flush());
+
+        if let Err(err) = res {
+            error!("Failed to close X11 window {:x?}: {}", id, err);
+        }
+    }
+}
+
+/// Ask X11 window to change its size, eg. during an interactive resize
+///
+/// X11 windows position themselves, so the `location` they should end up at is sent along,
+/// `None` keeps the current one.
+pub fn resize_window(
+    state: &mut State,
+    window: &Window,
+    location: Option<Point<i32, Logical>>,
+    size: Size<i32, Logical>,
+) {
+    let id = match window.user_data().get::<X11WindowId>() {
+        Some(id) => id.0,
+        None => return,
+    };
+
+    if let Some(client) = state.xwayland.client.as_ref() {
+        let mut aux = ConfigureWindowAux::default()
+            .width(size.w.max(1) as u32)
+            .height(size.h.max(1) as u32);
+
+        if let Some(location) = location {
+            aux = aux.x(location.x).y(location.y);
+        }
+
+        let res = client
+            .conn
+            .configure_window(id, &aux)
+            .and_then(|_| client.conn.flush());
+
+        if let Err(err) = res {
+            error!("Failed to resize X11 window {:x?}: {}", id, err);
+        }
+    }
+}
+
+/// `WM_CLASS` class and the name of X11 window, used by window rules
+pub fn window_class_and_name(state: &State, window: &Window) -> (Option<String>, Option<String>) {
+    let id = match window.user_data().get::<X11WindowId>() {
+        Some(id) => id.0,
+        None => return (None, None),
+    };
+
+    let client = match state.xwayland.client.as_ref() {
+        Some(client) => client,
+        None => return (None, None),
+    };
+
+    let get_property = |property: Atom, ty: Atom| -> Option<Vec<u8>> {
+        client
+            .conn
+            .get_property(false, id, property, ty, 0, 1024)
+            .ok()?
+            .reply()
+            .ok()
+            .map(|reply| reply.value)
+            .filter(|value| !value.is_empty())
+    };
+
+    // `WM_CLASS` holds the instance and the class, separated by a nul
+    let class =
+        get_property(AtomEnum::WM_CLASS.into(), AtomEnum::STRING.into()).and_then(|value| {
+            value
+                .split(|b| *b == 0)
+                .nth(1)
+                .map(|class| String::from_utf8_lossy(class).into_owned())
+        });
+
+    let name = get_property(client.atoms._NET_WM_NAME, client.atoms.UTF8_STRING)
+        .or_else(|| get_property(AtomEnum::WM_NAME.into(), AtomEnum::STRING.into()))
+        .map(|name| String::from_utf8_lossy(&name).into_owned());
+
+    (class, name)
+}
+
 fn on_window_map(state: &mut State, pending: PendingWindow) {
-    let pointer_pos = SeatState::for_seat(&state.seat).pointer_pos();
-    positioning::position_window_center(&mut state.space, pending.window, pointer_pos);
+    state.add_window_to_workspace(&pending.window);
+
+    if !state.is_tiled(&pending.window) {
+        state.place_window(&pending.window);
+    }
+
+    state.animate_open(&pending.window);
 }
 
 fn handle_new_window(state: &mut State, pending: PendingWindow) {
@@ -9,6 +9,10 @@ use smithay::{
 
 use x11rb::protocol::xproto::Window as X11Window;
 
+/// X11 window id, stored in [`Window::user_data`] of X11 windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct X11WindowId(pub X11Window);
+
 #[derive(Debug)]
 pub struct PendingWindow {
     pub window: Window,
@@ -30,7 +34,11 @@ impl PendingWindow {
         }
 
         let x11surface = X11Surface { surface };
+        let x11window = window;
         let window = Window::new(Kind::X11(x11surface));
+        window
+            .user_data()
+            .insert_if_missing(|| X11WindowId(x11window));
 
         Some(Self {
             window,
@@ -13,7 +13,11 @@ use x11rb::{
 x11rb::atom_manager! {
     pub Atoms: AtomsCookie {
         WM_S0,
+        WM_PROTOCOLS,
+        WM_DELETE_WINDOW,
         WL_SURFACE_ID,
+        UTF8_STRING,
+        _NET_WM_NAME,
         _ANODIUM_CLOSE_CONNECTION,
     }
 }